@@ -337,7 +337,7 @@ impl FPUFloatInstruction {
 pub struct Assembler {}
 
 impl Assembler {
-    const fn make_main_immediate(op: Opcode, rt: GPR, rs: GPR, imm: u16) -> u32 {
+    pub const fn make_main_immediate(op: Opcode, rt: GPR, rs: GPR, imm: u16) -> u32 {
         (imm as u32) |
             ((rt.raw_value().value() as u32) << 16) |
             ((rs.raw_value().value() as u32) << 21) |
@@ -392,6 +392,14 @@ impl Assembler {
                 ((Opcode::COP1 as u32) << 26))
     }
 
+    pub const fn make_addiu(rt: GPR, rs: GPR, imm: u16) -> u32 {
+        Self::make_main_immediate(Opcode::ADDIU, rt, rs, imm)
+    }
+
+    pub const fn make_jr(rs: GPR) -> u32 {
+        Self::make_special(SpecialOpcode::JR, u5::new(0), u5::new(0), rs.raw_value(), u5::new(0))
+    }
+
     pub const fn make_beq(rt: GPR, rs: GPR, offset_as_instruction_count: i16) -> u32 {
         Self::make_main_immediate(Opcode::BEQ, rt, rs, offset_as_instruction_count as u16)
     }
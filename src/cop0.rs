@@ -419,6 +419,12 @@ pub unsafe fn set_status(value: Status) {
     unsafe { write_cop0::<INDEX>(value.raw_value()) }
 }
 
+/// Only the two software interrupt bits (IP0 and IP1) are writable
+pub unsafe fn set_cause(value: Cause) {
+    const INDEX: u32 = RegisterIndex::Cause as u32;
+    unsafe { write_cop0::<INDEX>(value.raw_value()) }
+}
+
 pub fn status_64() -> u64 {
     const INDEX: u32 = RegisterIndex::Status as u32;
     unsafe { read_cop0_64::<INDEX>() }
@@ -469,6 +475,26 @@ pub unsafe fn set_lladdr(value: u64) {
     unsafe { write_cop0_64::<INDEX>(value) }
 }
 
+pub fn watchlo() -> u32 {
+    const INDEX: u32 = RegisterIndex::WatchLo as u32;
+    unsafe { read_cop0::<INDEX>() }
+}
+
+pub unsafe fn set_watchlo(value: u32) {
+    const INDEX: u32 = RegisterIndex::WatchLo as u32;
+    unsafe { write_cop0::<INDEX>(value) }
+}
+
+pub fn watchhi() -> u32 {
+    const INDEX: u32 = RegisterIndex::WatchHi as u32;
+    unsafe { read_cop0::<INDEX>() }
+}
+
+pub unsafe fn set_watchhi(value: u32) {
+    const INDEX: u32 = RegisterIndex::WatchHi as u32;
+    unsafe { write_cop0::<INDEX>(value) }
+}
+
 pub fn xcontext() -> XContext {
     const INDEX: u32 = RegisterIndex::XContext as u32;
     XContext::new_with_raw_value(unsafe { read_cop0_64::<INDEX>() })
@@ -504,6 +530,12 @@ pub unsafe fn tlbwi() {
     }
 }
 
+pub unsafe fn tlbwr() {
+    unsafe {
+        asm!("tlbwr; nop; nop;")
+    }
+}
+
 pub unsafe fn tlbr() {
     unsafe {
         asm!("tlbr; nop; nop;")
@@ -4,7 +4,7 @@ use core::ops::{Deref, DerefMut};
 
 use spinning_top::Spinlock;
 
-use crate::cop0::{Cause, CauseException, Context, XContext};
+use crate::cop0::{Cause, CauseException, Context, Status, StatusKSU, XContext};
 use crate::cop1::FCSR;
 use crate::graphics::color::Color;
 use crate::graphics::cursor::Cursor;
@@ -15,7 +15,19 @@ use crate::VIDEO;
 
 use super::cop0;
 
-static EXCEPTION_SKIP: Spinlock<Option<u64>> = Spinlock::new(None);
+/// How the exception handler continues after an expected exception
+#[derive(Copy, Clone)]
+enum ExceptionReturn {
+    /// Skip the given number of instructions, relative to ExceptPC
+    Skip(u64),
+
+    /// Return to the address in RA with Status.KSU and Status.IE cleared. This allows code that runs in
+    /// user or supervisor mode to get back into kernel mode, even if it can't access COP0 itself.
+    /// Clearing IE ensures that an interrupt that is still pending isn't taken right after returning
+    KernelAtReturnAddress,
+}
+
+static EXCEPTION_SKIP: Spinlock<Option<ExceptionReturn>> = Spinlock::new(None);
 
 /// Once an exception is seen, this is set to the exception context, along with 1. If another
 /// exception comes in, the counter is incremented and its Context is lost)
@@ -236,11 +248,18 @@ extern "C" fn exception_handler_compiled(stackpointer: usize) -> usize {
     let skip_guard = EXCEPTION_SKIP.lock();
     if guard.is_none() || avoid_bluescreen {
         // Skip the offending instruction(s) and return
-        if skip_guard.is_some() {
-            context.return_to = context.exceptpc + skip_guard.unwrap() * 4;
-        } else {
-            crate::isviewer::text_out("Got unhandled exception. Attempting to continue\n");
-            context.return_to = context.exceptpc + (if context.cause.branch_delay() { 8 } else { 4 });
+        match *skip_guard {
+            Some(ExceptionReturn::Skip(skip)) => {
+                context.return_to = context.exceptpc + skip * 4;
+            }
+            Some(ExceptionReturn::KernelAtReturnAddress) => {
+                context.return_to = context.ra;
+                context.status = Status::new_with_raw_value(context.status).with_ksu(StatusKSU::Kernel).with_ie(false).raw_value();
+            }
+            None => {
+                crate::isviewer::text_out("Got unhandled exception. Attempting to continue\n");
+                context.return_to = context.exceptpc + (if context.cause.branch_delay() { 8 } else { 4 });
+            }
         }
         context.return_to = context.return_to & !0x3;
 
@@ -268,6 +287,18 @@ pub fn drain_seen_exception() -> Option<(ExceptionContext, u32)> {
 }
 
 pub fn expect_exception<F>(code: CauseException, skip_instructions_on_hit: u64, f: F) -> Result<ExceptionContext, alloc::string::String>
+    where F: FnOnce() -> Result<(), &'static str> {
+    expect_exception_with_return(code, ExceptionReturn::Skip(skip_instructions_on_hit), f)
+}
+
+/// Like [`expect_exception`], but instead of skipping instructions the exception handler returns
+/// to the address in RA in kernel mode. See [`crate::user_mode`]
+pub fn expect_exception_returning_to_kernel<F>(code: CauseException, f: F) -> Result<ExceptionContext, alloc::string::String>
+    where F: FnOnce() -> Result<(), &'static str> {
+    expect_exception_with_return(code, ExceptionReturn::KernelAtReturnAddress, f)
+}
+
+fn expect_exception_with_return<F>(code: CauseException, exception_return: ExceptionReturn, f: F) -> Result<ExceptionContext, alloc::string::String>
     where F: FnOnce() -> Result<(), &'static str> {
     let guard = SEEN_EXCEPTION.lock();
     if guard.is_some() {
@@ -277,7 +308,7 @@ pub fn expect_exception<F>(code: CauseException, skip_instructions_on_hit: u64,
 
     let mut skip_guard = EXCEPTION_SKIP.lock();
     assert!(skip_guard.is_none());
-    *skip_guard = Some(skip_instructions_on_hit);
+    *skip_guard = Some(exception_return);
     drop(skip_guard);
 
     let result = f();
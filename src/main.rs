@@ -45,6 +45,7 @@ mod rdp;
 mod rsp;
 mod tests;
 mod uncached_memory;
+mod user_mode;
 
 static VIDEO: Spinlock<Video> = Spinlock::new(Video::new());
 
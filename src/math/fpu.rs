@@ -0,0 +1,268 @@
+use crate::cop1::{FCSRFlags, FCSRRoundingMode};
+
+// A reference model of the COP1 conversions, ABS/NEG/MOV and compares. Everything is calculated on the
+// integer side so that the model doesn't depend on the FPU that it is compared against.
+// It follows the behavior that the individual tests in tests::cop1 check for:
+// - Signalling NANs and denormal inputs cause an Unimplemented Operation exception
+// - Quiet NANs produce the COP1 NAN and set Invalid Operation
+// - A result that is too small for a normal float causes Unimplemented Operation, unless flush-denorm
+//   is set. In that case it is flushed to zero (or the smallest normal, depending on the rounding mode)
+// - CVT.S.L and CVT.D.L only handle inputs in -2^55..2^55
+// - Conversions to W fire Unimplemented Operation for NANs, infinity and results outside of the i32
+//   range. Conversions to L do the same for results whose magnitude is 2^53 or above
+// - Compares don't care about denormals. Quiet NANs always set Invalid Operation, signalling NANs only
+//   do for the signalling compares (C.SF to C.NGT)
+
+/// The result of an operation: Flags and the bits of the result or Err(()) if Unimplemented Operation fires.
+/// 32 bit results are in the lower bits
+pub type FPUResult = Result<(FCSRFlags, u64), ()>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    S,
+    D,
+    W,
+    L,
+}
+
+impl Format {
+    /// Returns the number of mantissa bits and exponent bits of a floating point format
+    const fn layout(self) -> (i32, i32) {
+        match self {
+            Format::S => (23, 8),
+            Format::D => (52, 11),
+            Format::W | Format::L => panic!("Not a floating point format"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Class {
+    Zero,
+    Denormal,
+    Normal,
+    Infinity,
+    QuietNAN,
+    SignallingNAN,
+}
+
+fn classify(format: Format, bits: u64) -> Class {
+    let (mantissa_bits, exponent_bits) = format.layout();
+    let mantissa = bits & ((1u64 << mantissa_bits) - 1);
+    let exponent = (bits >> mantissa_bits) & ((1u64 << exponent_bits) - 1);
+    if exponent == 0 {
+        if mantissa == 0 { Class::Zero } else { Class::Denormal }
+    } else if exponent == (1u64 << exponent_bits) - 1 {
+        if mantissa == 0 {
+            Class::Infinity
+        } else if (mantissa >> (mantissa_bits - 1)) != 0 {
+            Class::QuietNAN
+        } else {
+            Class::SignallingNAN
+        }
+    } else {
+        Class::Normal
+    }
+}
+
+const fn sign_bit(format: Format) -> u64 {
+    let (mantissa_bits, exponent_bits) = format.layout();
+    1u64 << (mantissa_bits + exponent_bits)
+}
+
+const fn infinity(format: Format) -> u64 {
+    let (mantissa_bits, exponent_bits) = format.layout();
+    ((1u64 << exponent_bits) - 1) << mantissa_bits
+}
+
+/// The NAN that COP1 produces
+const fn nan(format: Format) -> u64 {
+    let (mantissa_bits, _) = format.layout();
+    infinity(format) | ((1u64 << (mantissa_bits - 1)) - 1)
+}
+
+/// Splits a normal float into sign, significand and exponent, so that the value is significand * 2^exponent
+fn unpack(format: Format, bits: u64) -> (bool, u64, i32) {
+    let (mantissa_bits, exponent_bits) = format.layout();
+    let bias = (1i32 << (exponent_bits - 1)) - 1;
+    let mantissa = bits & ((1u64 << mantissa_bits) - 1);
+    let exponent = ((bits >> mantissa_bits) & ((1u64 << exponent_bits) - 1)) as i32;
+    ((bits & sign_bit(format)) != 0, mantissa | (1u64 << mantissa_bits), exponent - bias - mantissa_bits)
+}
+
+/// Shifts value right, rounding according to the rounding mode. Also returns whether bits were lost
+fn shift_right_rounded(value: u64, shift: u32, negative: bool, rounding_mode: FCSRRoundingMode) -> (u64, bool) {
+    if shift == 0 {
+        return (value, false);
+    }
+    // Everything below 2^64 is less than half when shifting this far
+    let shift = shift.min(100);
+    let value = value as u128;
+    let kept = value >> shift;
+    let remainder = value & ((1u128 << shift) - 1);
+    let half = 1u128 << (shift - 1);
+    let round_up = match rounding_mode {
+        FCSRRoundingMode::Nearest => remainder > half || (remainder == half && (kept & 1) != 0),
+        FCSRRoundingMode::Zero => false,
+        FCSRRoundingMode::PositiveInfinity => remainder != 0 && !negative,
+        FCSRRoundingMode::NegativeInfinity => remainder != 0 && negative,
+    };
+    ((kept as u64) + (round_up as u64), remainder != 0)
+}
+
+/// Rounds significand * 2^exponent (with a non-zero significand) into the given floating point format
+fn round_to_float(format: Format, negative: bool, significand: u64, exponent: i32, rounding_mode: FCSRRoundingMode, flush_denorm_to_zero: bool) -> FPUResult {
+    let (mantissa_bits, exponent_bits) = format.layout();
+    let bias = (1i32 << (exponent_bits - 1)) - 1;
+    let sign = if negative { sign_bit(format) } else { 0 };
+    let msb = 63 - significand.leading_zeros() as i32;
+    let mut unbiased_exponent = msb + exponent;
+
+    if unbiased_exponent < 1 - bias {
+        if !flush_denorm_to_zero {
+            return Err(());
+        }
+        let round_up = match rounding_mode {
+            FCSRRoundingMode::PositiveInfinity => !negative,
+            FCSRRoundingMode::NegativeInfinity => negative,
+            _ => false,
+        };
+        let magnitude = if round_up { 1u64 << mantissa_bits } else { 0 };
+        return Ok((FCSRFlags::new().with_underflow(true).with_inexact_operation(true), sign | magnitude));
+    }
+
+    let (mut mantissa, inexact) = if msb > mantissa_bits {
+        shift_right_rounded(significand, (msb - mantissa_bits) as u32, negative, rounding_mode)
+    } else {
+        (significand << (mantissa_bits - msb), false)
+    };
+    if mantissa == (1u64 << (mantissa_bits + 1)) {
+        mantissa >>= 1;
+        unbiased_exponent += 1;
+    }
+
+    if unbiased_exponent > bias {
+        let to_infinity = match rounding_mode {
+            FCSRRoundingMode::Nearest => true,
+            FCSRRoundingMode::Zero => false,
+            FCSRRoundingMode::PositiveInfinity => !negative,
+            FCSRRoundingMode::NegativeInfinity => negative,
+        };
+        // The largest finite number is right below infinity
+        let magnitude = if to_infinity { infinity(format) } else { infinity(format) - 1 };
+        return Ok((FCSRFlags::new().with_overflow(true).with_inexact_operation(true), sign | magnitude));
+    }
+
+    let bits = sign | (((unbiased_exponent + bias) as u64) << mantissa_bits) | (mantissa & ((1u64 << mantissa_bits) - 1));
+    Ok((FCSRFlags::new().with_inexact_operation(inexact), bits))
+}
+
+fn float_to_float(input: Format, output: Format, bits: u64, rounding_mode: FCSRRoundingMode, flush_denorm_to_zero: bool) -> FPUResult {
+    let sign = if (bits & sign_bit(input)) != 0 { sign_bit(output) } else { 0 };
+    match classify(input, bits) {
+        Class::Zero => Ok((FCSRFlags::NONE, sign)),
+        Class::Infinity => Ok((FCSRFlags::NONE, sign | infinity(output))),
+        Class::QuietNAN => Ok((FCSRFlags::new().with_invalid_operation(true), nan(output))),
+        Class::SignallingNAN | Class::Denormal => Err(()),
+        Class::Normal => {
+            let (negative, significand, exponent) = unpack(input, bits);
+            round_to_float(output, negative, significand, exponent, rounding_mode, flush_denorm_to_zero)
+        }
+    }
+}
+
+fn float_to_integer(input: Format, output: Format, bits: u64, rounding_mode: FCSRRoundingMode) -> FPUResult {
+    let (negative, magnitude, inexact) = match classify(input, bits) {
+        Class::Zero => (false, 0, false),
+        Class::Normal => {
+            let (negative, significand, exponent) = unpack(input, bits);
+            if exponent >= 0 {
+                // Anything at 2^62 or above is out of range for both W and L
+                if 63 - (significand.leading_zeros() as i32) + exponent >= 62 {
+                    return Err(());
+                }
+                (negative, significand << exponent, false)
+            } else {
+                let (magnitude, inexact) = shift_right_rounded(significand, (-exponent) as u32, negative, rounding_mode);
+                (negative, magnitude, inexact)
+            }
+        }
+        _ => return Err(()),
+    };
+
+    let in_range = match output {
+        Format::W => if negative { magnitude <= 1u64 << 31 } else { magnitude < 1u64 << 31 },
+        _ => magnitude < 1u64 << 53,
+    };
+    if !in_range {
+        return Err(());
+    }
+
+    let value = if negative { (magnitude as i64).wrapping_neg() } else { magnitude as i64 };
+    let bits = if output == Format::W { value as u32 as u64 } else { value as u64 };
+    Ok((FCSRFlags::new().with_inexact_operation(inexact), bits))
+}
+
+fn integer_to_float(input: Format, output: Format, bits: u64, rounding_mode: FCSRRoundingMode, flush_denorm_to_zero: bool) -> FPUResult {
+    let value = if input == Format::W { bits as u32 as i32 as i64 } else { bits as i64 };
+    if input == Format::L && (value >= (1i64 << 55) || value < -(1i64 << 55)) {
+        return Err(());
+    }
+    if value == 0 {
+        return Ok((FCSRFlags::NONE, 0));
+    }
+    round_to_float(output, value < 0, value.unsigned_abs(), 0, rounding_mode, flush_denorm_to_zero)
+}
+
+/// CVT.fmt.fmt as well as ROUND, TRUNC, CEIL and FLOOR (which are conversions with a fixed rounding mode)
+pub fn convert(input: Format, output: Format, bits: u64, rounding_mode: FCSRRoundingMode, flush_denorm_to_zero: bool) -> FPUResult {
+    match (input, output) {
+        (Format::S, Format::D) | (Format::D, Format::S) => float_to_float(input, output, bits, rounding_mode, flush_denorm_to_zero),
+        (Format::S | Format::D, Format::W | Format::L) => float_to_integer(input, output, bits, rounding_mode),
+        (Format::W | Format::L, Format::S | Format::D) => integer_to_float(input, output, bits, rounding_mode, flush_denorm_to_zero),
+        // S => S, D => D and conversions between integers don't exist
+        _ => Err(()),
+    }
+}
+
+fn sign_operation(format: Format, bits: u64, change_sign: fn(u64, u64) -> u64) -> FPUResult {
+    match classify(format, bits) {
+        Class::SignallingNAN | Class::Denormal => Err(()),
+        Class::QuietNAN => Ok((FCSRFlags::new().with_invalid_operation(true), nan(format))),
+        _ => Ok((FCSRFlags::NONE, change_sign(bits, sign_bit(format)))),
+    }
+}
+
+pub fn abs(format: Format, bits: u64) -> FPUResult {
+    sign_operation(format, bits, |bits, sign| bits & !sign)
+}
+
+pub fn neg(format: Format, bits: u64) -> FPUResult {
+    sign_operation(format, bits, |bits, sign| bits ^ sign)
+}
+
+/// MOV never fails and doesn't even look at its input
+pub fn mov(_format: Format, bits: u64) -> FPUResult {
+    Ok((FCSRFlags::NONE, bits))
+}
+
+/// C.cond.fmt. `condition` is the function field of the instruction (see Cop1Condition).
+/// Returns the flags and the new value of the condition bit
+pub fn compare(format: Format, condition: u8, bits1: u64, bits2: u64) -> (FCSRFlags, bool) {
+    let class1 = classify(format, bits1);
+    let class2 = classify(format, bits2);
+    let quiet_nan = class1 == Class::QuietNAN || class2 == Class::QuietNAN;
+    let signalling_nan = class1 == Class::SignallingNAN || class2 == Class::SignallingNAN;
+    let unordered = quiet_nan || signalling_nan;
+    let invalid = quiet_nan || (signalling_nan && (condition & 0b1000) != 0);
+
+    // Sign-magnitude to something that can be compared directly. This also makes -0 equal to 0
+    let key = |bits: u64| {
+        let magnitude = (bits & (sign_bit(format) - 1)) as i64;
+        if (bits & sign_bit(format)) != 0 { -magnitude } else { magnitude }
+    };
+    let (less, equal) = if unordered { (false, false) } else { (key(bits1) < key(bits2), key(bits1) == key(bits2)) };
+
+    let result = (unordered && (condition & 0b001) != 0) || (equal && (condition & 0b010) != 0) || (less && (condition & 0b100) != 0);
+    (FCSRFlags::new().with_invalid_operation(invalid), result)
+}
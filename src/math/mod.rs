@@ -1,3 +1,5 @@
 pub mod bits;
+pub mod fpu;
 pub mod soft_float;
 pub mod vector;
+pub mod vector_unit;
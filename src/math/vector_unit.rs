@@ -0,0 +1,690 @@
+use crate::math::vector::Vector;
+use crate::rsp::rsp_assembler::{Element, VR};
+
+// A reference model of the RSP vector unit. Tests can feed it the same inputs as the RSP and compare
+// results instead of hardcoding expected vectors.
+// The per-lane functions of the multiply instructions have been verified through the stress tests. The
+// other instructions follow the behavior that the individual op_* tests check for.
+
+// The generation of the RCP and RSP tables was ported from Ares: https://github.com/ares-emulator/ares/blob/acd2130a4d4c9e7208f61e0ff762895f7c9b8dc6/ares/n64/rsp/rsp.cpp#L102
+// which uses the following license:
+
+// Copyright (c) 2004-2021 ares team, Near et al
+//
+// Permission to use, copy, modify, and/or distribute this software for any
+// purpose with or without fee is hereby granted, provided that the above
+// copyright notice and this permission notice appear in all copies.
+//
+// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+
+const fn rcp_table_value(index: usize) -> u16 {
+    if index == 0 {
+        return 0xFFFF;
+    }
+
+    ((((1u64 << 34) / ((index as u64) + 512)) + 1) >> 8) as u16
+}
+
+const fn rsq_table_value(index: usize) -> u16 {
+    // The basic idea for this algorithm was taken from Ares, but modified:
+    // - indexes are different to match the algorithm here
+    // - using the increment loop this function was made faster; it is now fast enough to run
+    //   in a const context
+    let a = (if index < 256 { index + 256 } else { ((index - 256) << 1) + 512 }) as u64;
+    let mut b = 1u64 << 17;
+    // find the largest b where b < 1.0 / sqrt(a)
+    let mut increment = 512;
+    while increment != 0 {
+        while a * (b + increment) * (b + increment) < (1u64 << 44) {
+            b += increment;
+        }
+        increment >>= 1;
+    }
+    (b >> 1) as u16
+}
+
+pub const fn make_rcp_table() -> [u16; 512] {
+    let mut result = [0u16; 512];
+    let mut i = 0;
+    while i < 512 {
+        result[i] = rcp_table_value(i);
+        i += 1;
+    }
+    result
+}
+
+pub const fn make_rsq_table() -> [u16; 512] {
+    let mut result = [0u16; 512];
+    let mut i = 0;
+    while i < 512 {
+        result[i] = rsq_table_value(i);
+        i += 1;
+    }
+    result
+}
+
+pub const RCP_DATA: [u16; 512] = make_rcp_table();
+pub const RSQ_DATA: [u16; 512] = make_rsq_table();
+
+pub const fn rcp(value: u32) -> u32 {
+    if value == 0 {
+        return 0x7FFF_FFFF;
+    }
+    if value == 0xFFFF_8000 {
+        return 0xFFFF_0000;
+    }
+    // After 0xFFFF_8000, everything is shifted by one. Why? No idea
+    let adjusted_value = if value > 0xFFFF_8000 { value - 1 } else { value };
+    let is_negative = (adjusted_value as i32).is_negative();
+    let positive_value = if is_negative { !adjusted_value } else { adjusted_value };
+    let shift = positive_value.leading_zeros() + 1;
+    let index = ((positive_value << shift) >> 23) as usize;
+    let positive_result = (0x4000_0000 | ((RCP_DATA[index] as u32) << 14)) >> (32 - shift);
+    if is_negative { !positive_result } else { positive_result }
+}
+
+pub fn rsq(value: u32) -> u32 {
+    if value == 0 {
+        return 0x7FFF_FFFF;
+    }
+    if value == 0xFFFF_8000 {
+        return 0xFFFF_0000;
+    }
+    // After 0xFFFF_8000, everything is offset by one. Why? No idea
+    let adjusted_value = if value > 0xFFFF_8000 { value - 1 } else { value };
+    let is_negative = (adjusted_value as i32).is_negative();
+    let positive_value = if is_negative { !adjusted_value } else { adjusted_value };
+    let shift = positive_value.leading_zeros() + 1;
+    // For uneven shifts, take the second half of the table
+    let index = (((positive_value << shift) >> 24) | ((shift & 1) << 8)) as usize;
+    let positive_result = (0x4000_0000 | ((RSQ_DATA[index] as u32) << 14)) >> ((32 - shift) >> 1);
+    if is_negative { !positive_result } else { positive_result }
+}
+
+pub fn zero_extend_accum48(v: i64) -> u64 { ((v as u64) << 16) >> 16 }
+
+pub fn sign_extend_accum48(v: u64) -> i64 { ((v as i64) << 16) >> 16 }
+
+fn clamp_signed_16(value: i64) -> u16 {
+    if value < i16::MIN as i64 {
+        0x8000
+    } else if value > i16::MAX as i64 {
+        0x7FFF
+    } else {
+        value as u16
+    }
+}
+
+// Per-lane computations of the multiply instructions. Each takes an element of vs, an element of vt (with the
+// element specifier already applied) and the lane's accumulator (lower 48 bits) and returns the element that
+// is written into vd and the new accumulator
+
+pub fn vmulf_lane(vs: u16, vt: u16, _accumulator: u64) -> (u16, u64) {
+    let product = (vs as i16 as i32) * (vt as i16 as i32);
+    let temp = ((product as i64) << 1) + 0x8000;
+    let result_val = if (temp > 0) && ((temp & !0x7FFFFFFF) != 0) { 0x7FFF } else { (temp >> 16) as u16 };
+
+    (result_val, zero_extend_accum48(temp))
+}
+
+pub fn vmulu_lane(vs: u16, vt: u16, _accumulator: u64) -> (u16, u64) {
+    let product = (vs as i16 as i32) * (vt as i16 as i32);
+    let product_shifted = ((product as i64) << 1) + 0x8000;
+    let result_val =
+        if (product_shifted & (1 << 48)) != 0 {
+            0
+        } else if (product_shifted & !((1 << 31) - 1)) != 0 {
+            0xFFFF
+        } else {
+            (product_shifted >> 16) as u16
+        };
+
+    (result_val, zero_extend_accum48(product_shifted))
+}
+
+pub fn vmudl_lane(vs: u16, vt: u16, _accumulator: u64) -> (u16, u64) {
+    let product = (vs as u32) * (vt as u32);
+    let product_shifted = (product >> 16) as u16;
+
+    (product_shifted, product_shifted as u64)
+}
+
+pub fn vmudh_lane(vs: u16, vt: u16, _accumulator: u64) -> (u16, u64) {
+    let product = (vs as i16 as i32) * (vt as i16 as i32);
+
+    let result_val =
+        if product >= 0 {
+            if (product & !0x7FFF) != 0 { 0x7FFFu16 } else { product as u16 }
+        } else {
+            if (!product & !0x7FFF) != 0 { 0x8000u16 } else { product as u16 }
+        } as u16;
+
+    (result_val, (product as u32 as u64) << 16)
+}
+
+pub fn vmudm_lane(vs: u16, vt: u16, _accumulator: u64) -> (u16, u64) {
+    let product = (vs as i16 as i32) * (vt as u16 as i32);
+
+    ((product >> 16) as u16, zero_extend_accum48(product as i64))
+}
+
+pub fn vmudn_lane(vs: u16, vt: u16, _accumulator: u64) -> (u16, u64) {
+    let product = (vs as u16 as i32) * (vt as i16 as i32);
+    let result_val = product as u16;
+
+    (result_val, zero_extend_accum48(product as i64))
+}
+
+pub fn vmulq_lane(vs: u16, vt: u16, _accumulator: u64) -> (u16, u64) {
+    let product_shifted = ((vs as i16 as i64) * (vt as i16 as i64)) << 16;
+    let adjusted = product_shifted + (if product_shifted < 0 { 0x1F0000 } else { 0 });
+
+    let clamped =
+        if adjusted < 0 {
+            if (!adjusted >> 32) != 0 {
+                0x8000u16
+            } else {
+                (adjusted >> 17) as u16
+            }
+        } else {
+            if (adjusted >> 32) != 0 {
+                0x7fffu16
+            } else {
+                (adjusted >> 17) as u16
+            }
+        };
+
+    let result = clamped & 0xFFF0;
+
+    (result, zero_extend_accum48(adjusted))
+}
+
+pub fn vmacf_lane(vs: u16, vt: u16, accumulator: u64) -> (u16, u64) {
+    let product = (vs as i16 as i32) * (vt as i16 as i32);
+
+    let new_accum = ((product as i64) << 1) + sign_extend_accum48(accumulator);
+    let temp_shifted32 = (new_accum >> 16) as i32;
+    let result_val =
+        if temp_shifted32 >= 0 {
+            if (temp_shifted32 & !0x7FFF) != 0 { 0x7FFFu16 } else { temp_shifted32 as u16 }
+        } else {
+            if (!temp_shifted32 & !0x7FFF) != 0 { 0x8000u16 } else { temp_shifted32 as u16 }
+        } as u16;
+
+    (result_val, zero_extend_accum48(new_accum))
+}
+
+pub fn vmacu_lane(vs: u16, vt: u16, accumulator: u64) -> (u16, u64) {
+    let product = (vs as i16 as i32) * (vt as i16 as i32);
+    let new_accum = ((product as i64) << 1) + sign_extend_accum48(accumulator);
+    let result_val =
+        if (new_accum & (1 << 48)) != 0 {
+            0
+        } else if (new_accum & !((1 << 31) - 1)) != 0 {
+            0xFFFF
+        } else {
+            (new_accum >> 16) as u16
+        };
+
+    (result_val, zero_extend_accum48(new_accum))
+}
+
+pub fn vmadl_lane(vs: u16, vt: u16, accumulator: u64) -> (u16, u64) {
+    let product = (vs as u32) * (vt as u32);
+    let product_shifted = (product >> 16) as u16;
+
+    let new_accum = (product_shifted as i64) + sign_extend_accum48(accumulator);
+    let result_val =
+        if new_accum >= 0 {
+            if (new_accum & !0x7FFFFFFF) != 0 { 0xffff } else { new_accum as u16 }
+        } else {
+            if (!new_accum & !0x7FFFFFFF) != 0 { 0 } else { new_accum as u16 }
+        } as u16;
+
+    (result_val, zero_extend_accum48(new_accum))
+}
+
+pub fn vmadh_lane(vs: u16, vt: u16, accumulator: u64) -> (u16, u64) {
+    let product = (vs as i16 as i32) * (vt as i16 as i32);
+
+    let new_accum = ((product as i64) << 16) + sign_extend_accum48(accumulator);
+    let temp_shifted32 = (new_accum >> 16) as i32;
+    let result_val =
+        if temp_shifted32 >= 0 {
+            if (temp_shifted32 & !0x7FFF) != 0 { 0x7FFFu16 } else { temp_shifted32 as u16 }
+        } else {
+            if (!temp_shifted32 & !0x7FFF) != 0 { 0x8000u16 } else { temp_shifted32 as u16 }
+        } as u16;
+
+    (result_val, zero_extend_accum48(new_accum))
+}
+
+pub fn vmadm_lane(vs: u16, vt: u16, accumulator: u64) -> (u16, u64) {
+    let product = (vs as i16 as i32) * (vt as u16 as i32);
+    let new_accum = (product as i64) + sign_extend_accum48(accumulator);
+    let product_shifted = new_accum >> 16;
+
+    let result_val =
+        if product_shifted >= 0 {
+            if (product_shifted & !0x7FFF) != 0 { 0x7FFFu16 } else { product_shifted as u16 }
+        } else {
+            if (!product_shifted & !0x7FFF) != 0 { 0x8000u16 } else { product_shifted as u16 }
+        } as u16;
+
+    (result_val, zero_extend_accum48(new_accum))
+}
+
+pub fn vmadn_lane(vs: u16, vt: u16, accumulator: u64) -> (u16, u64) {
+    let product = (vs as u16 as i32) * (vt as i16 as i32);
+
+    let new_accum = (product as i64) + sign_extend_accum48(accumulator);
+    let result_val =
+        if new_accum >= 0 {
+            if (new_accum & !0x7FFFFFFF) != 0 { 0xffff } else { new_accum as u16 }
+        } else {
+            if (!new_accum & !0x7FFFFFFF) != 0 { 0 } else { new_accum as u16 }
+        } as u16;
+
+    (result_val, zero_extend_accum48(new_accum))
+}
+
+/// VMACQ ignores its inputs and only looks at the accumulator
+pub fn vmacq_lane(_vs: u16, _vt: u16, accumulator: u64) -> (u16, u64) {
+    let acc_input = sign_extend_accum48(accumulator);
+
+    // Add/Remove value, depending on bits in the accumulator
+    let should_change = (acc_input & 0x20_0000) == 0;
+    let acc_output = if should_change {
+        let upper_bits = (acc_input >> 22) as i32;
+        if upper_bits < 0 {
+            acc_input + 0x20_0000
+        } else if upper_bits > 0 {
+            acc_input - 0x20_0000
+        } else {
+            acc_input
+        }
+    } else {
+        acc_input
+    };
+
+    // We removed 0x20_0000 only if the original number was larger than 0x40_0000. Same for negative.
+    // Therefore, the sign of the result can't change and we don't need to sign-extend from 48 to 64
+
+    let clamped_and_shifted = if acc_output < 0 {
+        if ((!acc_output) >> 32) != 0 {
+            0x8000
+        } else {
+            (acc_output >> 17) as u16
+        }
+    } else {
+        if (acc_output >> 32) != 0 {
+            0x7FFF
+        } else {
+            (acc_output >> 17) as u16
+        }
+    };
+    let result = clamped_and_shifted & 0xFFF0;
+    (result, zero_extend_accum48(acc_output))
+}
+
+/// The architectural state of the vector unit
+#[derive(Copy, Clone)]
+pub struct VectorUnit {
+    registers: [Vector; 32],
+    /// One 48 bit value per lane, zero extended
+    accumulator: [u64; 8],
+    vco: u16,
+    vcc: u16,
+    vce: u8,
+    /// Upper 16 bits of the input for VRCPL/VRSQL, set by VRCPH/VRSQH
+    divide_input: u16,
+    /// Upper 16 bits of the last reciprocal, returned by VRCPH/VRSQH
+    divide_output: u16,
+    /// Whether divide_input is to be used by the next VRCPL/VRSQL
+    divide_double_precision: bool,
+}
+
+impl VectorUnit {
+    pub const fn new() -> Self {
+        Self {
+            registers: [Vector::new(); 32],
+            accumulator: [0; 8],
+            vco: 0,
+            vcc: 0,
+            vce: 0,
+            divide_input: 0,
+            divide_output: 0,
+            divide_double_precision: false,
+        }
+    }
+
+    pub fn register(&self, vr: VR) -> Vector { self.registers[vr.index()] }
+
+    pub fn set_register(&mut self, vr: VR, value: Vector) { self.registers[vr.index()] = value }
+
+    /// Sets the accumulator the same way as assemble_set_accumulator_to
+    pub fn set_accumulator_to(&mut self, high: Vector, mid: Vector, low: Vector) {
+        for i in 0..8 {
+            self.accumulator[i] = ((high.get16(i) as u64) << 32) | ((mid.get16(i) as u64) << 16) | (low.get16(i) as u64);
+        }
+    }
+
+    fn accumulator_part(&self, shift: u32) -> Vector {
+        let mut result = Vector::new();
+        for i in 0..8 {
+            result.set16(i, (self.accumulator[i] >> shift) as u16);
+        }
+        result
+    }
+
+    /// Same as VSAR with VSARAccumulator::High
+    pub fn accumulator_high(&self) -> Vector { self.accumulator_part(32) }
+
+    /// Same as VSAR with VSARAccumulator::Mid
+    pub fn accumulator_mid(&self) -> Vector { self.accumulator_part(16) }
+
+    /// Same as VSAR with VSARAccumulator::Low
+    pub fn accumulator_low(&self) -> Vector { self.accumulator_part(0) }
+
+    pub fn vco(&self) -> u16 { self.vco }
+
+    pub fn set_vco(&mut self, value: u16) { self.vco = value }
+
+    pub fn vcc(&self) -> u16 { self.vcc }
+
+    pub fn set_vcc(&mut self, value: u16) { self.vcc = value }
+
+    pub fn vce(&self) -> u8 { self.vce }
+
+    pub fn set_vce(&mut self, value: u8) { self.vce = value }
+
+    fn set_accumulator_low(&mut self, lane: usize, value: u16) {
+        self.accumulator[lane] = (self.accumulator[lane] & !0xFFFF) | (value as u64);
+    }
+
+    /// Returns vs and vt with the element specifier applied. Both are copies, so vd can alias either
+    fn sources(&self, vt: VR, vs: VR, e: Element) -> (Vector, Vector) {
+        (self.register(vs), self.register(vt).copy_with_element_specifier_applied(e))
+    }
+
+    fn multiply(&mut self, vd: VR, vt: VR, vs: VR, e: Element, lane: fn(u16, u16, u64) -> (u16, u64)) {
+        let (vs, vt) = self.sources(vt, vs, e);
+        let mut result = Vector::new();
+        for i in 0..8 {
+            let (value, accumulator) = lane(vs.get16(i), vt.get16(i), self.accumulator[i]);
+            result.set16(i, value);
+            self.accumulator[i] = accumulator;
+        }
+        self.set_register(vd, result);
+    }
+
+    /// Runs an instruction that writes its result into both vd and the lower accumulator
+    fn per_lane(&mut self, vd: VR, vt: VR, vs: VR, e: Element, mut lane: impl FnMut(&mut Self, usize, u16, u16) -> u16) {
+        let (vs, vt) = self.sources(vt, vs, e);
+        let mut result = Vector::new();
+        for i in 0..8 {
+            let value = lane(self, i, vs.get16(i), vt.get16(i));
+            result.set16(i, value);
+            self.set_accumulator_low(i, value);
+        }
+        self.set_register(vd, result);
+    }
+
+    // Instructions. Arguments are in the same order as in RSPAssembler, so that a test can mirror its program
+
+    pub fn vmulf(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.multiply(vd, vt, vs, e, vmulf_lane) }
+
+    pub fn vmudh(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.multiply(vd, vt, vs, e, vmudh_lane) }
+
+    pub fn vmadl(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.multiply(vd, vt, vs, e, vmadl_lane) }
+
+    pub fn vmadn(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.multiply(vd, vt, vs, e, vmadn_lane) }
+
+    pub fn vmacq(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.multiply(vd, vt, vs, e, vmacq_lane) }
+
+    /// VRNDN and VRNDP use the index of vs (not its contents) to decide whether to shift vt
+    fn round(&mut self, vd: VR, vt: VR, vs: VR, e: Element, positive: bool) {
+        let vt = self.register(vt).copy_with_element_specifier_applied(e);
+        let mut result = Vector::new();
+        for i in 0..8 {
+            let product = if (vs.index() & 1) != 0 { (vt.get16(i) as i16 as i64) << 16 } else { vt.get16(i) as i16 as i64 };
+            let accumulator = sign_extend_accum48(self.accumulator[i]);
+            let new_accumulator = if (accumulator >= 0) == positive { sign_extend_accum48(zero_extend_accum48(accumulator + product)) } else { accumulator };
+            self.accumulator[i] = zero_extend_accum48(new_accumulator);
+            result.set16(i, clamp_signed_16(new_accumulator >> 16));
+        }
+        self.set_register(vd, result);
+    }
+
+    pub fn vrndn(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.round(vd, vt, vs, e, false) }
+
+    pub fn vrndp(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.round(vd, vt, vs, e, true) }
+
+    pub fn vadd(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        let (vs, vt) = self.sources(vt, vs, e);
+        let mut result = Vector::new();
+        for i in 0..8 {
+            let sum = (vs.get16(i) as i16 as i64) + (vt.get16(i) as i16 as i64) + ((self.vco >> i) & 1) as i64;
+            self.set_accumulator_low(i, sum as u16);
+            result.set16(i, clamp_signed_16(sum));
+        }
+        self.vco = 0;
+        self.set_register(vd, result);
+    }
+
+    pub fn vsub(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        let (vs, vt) = self.sources(vt, vs, e);
+        let mut result = Vector::new();
+        for i in 0..8 {
+            let difference = (vs.get16(i) as i16 as i64) - (vt.get16(i) as i16 as i64) - ((self.vco >> i) & 1) as i64;
+            self.set_accumulator_low(i, difference as u16);
+            result.set16(i, clamp_signed_16(difference));
+        }
+        self.vco = 0;
+        self.set_register(vd, result);
+    }
+
+    pub fn vabs(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        let (vs, vt) = self.sources(vt, vs, e);
+        let mut result = Vector::new();
+        for i in 0..8 {
+            let (accumulator, value) = match (vs.get16(i) as i16).signum() {
+                -1 if vt.get16(i) == 0x8000 => (0x8000, 0x7FFF),
+                -1 => (vt.get16(i).wrapping_neg(), vt.get16(i).wrapping_neg()),
+                1 => (vt.get16(i), vt.get16(i)),
+                _ => (0, 0),
+            };
+            self.set_accumulator_low(i, accumulator);
+            result.set16(i, value);
+        }
+        self.set_register(vd, result);
+    }
+
+    pub fn vaddc(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        let mut vco = 0;
+        self.per_lane(vd, vt, vs, e, |_, i, vs, vt| {
+            let sum = (vs as u32) + (vt as u32);
+            vco |= ((sum >> 16) as u16) << i;
+            sum as u16
+        });
+        self.vco = vco;
+    }
+
+    pub fn vsubc(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        let mut vco = 0;
+        self.per_lane(vd, vt, vs, e, |_, i, vs, vt| {
+            let difference = (vs as u32).wrapping_sub(vt as u32);
+            vco |= (((difference >> 16) & 1) as u16) << i;
+            vco |= ((difference != 0) as u16) << (i + 8);
+            difference as u16
+        });
+        self.vco = vco;
+    }
+
+    pub fn vand(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.per_lane(vd, vt, vs, e, |_, _, vs, vt| vs & vt) }
+
+    pub fn vnand(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.per_lane(vd, vt, vs, e, |_, _, vs, vt| !(vs & vt)) }
+
+    pub fn vor(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.per_lane(vd, vt, vs, e, |_, _, vs, vt| vs | vt) }
+
+    pub fn vnor(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.per_lane(vd, vt, vs, e, |_, _, vs, vt| !(vs | vt)) }
+
+    pub fn vxor(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.per_lane(vd, vt, vs, e, |_, _, vs, vt| vs ^ vt) }
+
+    pub fn vnxor(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.per_lane(vd, vt, vs, e, |_, _, vs, vt| !(vs ^ vt)) }
+
+    /// Shared by VLT, VEQ, VNE and VGE: Sets VCC (low) through the condition, clears VCC (high) and VCO and selects vs or vt
+    fn select(&mut self, vd: VR, vt: VR, vs: VR, e: Element, condition: fn(i16, i16, bool, bool) -> bool) {
+        let mut vcc = 0;
+        self.per_lane(vd, vt, vs, e, |unit, i, vs, vt| {
+            let carry = ((unit.vco >> i) & 1) != 0;
+            let not_equal = ((unit.vco >> (i + 8)) & 1) != 0;
+            let selected = condition(vs as i16, vt as i16, carry, not_equal);
+            vcc |= (selected as u16) << i;
+            if selected { vs } else { vt }
+        });
+        self.vcc = vcc;
+        self.vco = 0;
+    }
+
+    pub fn vlt(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        self.select(vd, vt, vs, e, |vs, vt, carry, not_equal| (vs < vt) || ((vs == vt) && carry && not_equal))
+    }
+
+    pub fn veq(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        self.select(vd, vt, vs, e, |vs, vt, _, not_equal| (vs == vt) && !not_equal)
+    }
+
+    pub fn vne(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        self.select(vd, vt, vs, e, |vs, vt, _, not_equal| (vs != vt) || not_equal)
+    }
+
+    pub fn vge(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        self.select(vd, vt, vs, e, |vs, vt, carry, not_equal| (vs > vt) || ((vs == vt) && !(carry && not_equal)))
+    }
+
+    pub fn vmrg(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        self.per_lane(vd, vt, vs, e, |unit, i, vs, vt| if ((unit.vcc >> i) & 1) != 0 { vs } else { vt });
+        self.vco = 0;
+    }
+
+    pub fn vch(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        let (mut vco, mut vcc, mut vce) = (0u16, 0u16, 0u8);
+        self.per_lane(vd, vt, vs, e, |_, i, vs, vt| {
+            let (svs, svt) = (vs as i16 as i32, vt as i16 as i32);
+            let not_equal = |result: i32| (result != 0) && (vs != !vt);
+            if (svs ^ svt) < 0 {
+                let result = svs + svt;
+                vcc |= (((result <= 0) as u16) << i) | (((svt < 0) as u16) << (i + 8));
+                vco |= (1 << i) | ((not_equal(result) as u16) << (i + 8));
+                vce |= ((result == -1) as u8) << i;
+                if result <= 0 { vt.wrapping_neg() } else { vs }
+            } else {
+                let result = svs - svt;
+                vcc |= (((svt < 0) as u16) << i) | (((result >= 0) as u16) << (i + 8));
+                vco |= (not_equal(result) as u16) << (i + 8);
+                if result >= 0 { vt } else { vs }
+            }
+        });
+        self.vco = vco;
+        self.vcc = vcc;
+        self.vce = vce;
+    }
+
+    pub fn vcl(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        let mut vcc = self.vcc;
+        self.per_lane(vd, vt, vs, e, |unit, i, vs, vt| {
+            let carry = ((unit.vco >> i) & 1) != 0;
+            let not_equal = ((unit.vco >> (i + 8)) & 1) != 0;
+            if carry {
+                if !not_equal {
+                    let sum = (vs as u32) + (vt as u32);
+                    let lte = if ((unit.vce >> i) & 1) != 0 { sum <= 0x10000 } else { sum == 0 };
+                    vcc = (vcc & !(1 << i)) | ((lte as u16) << i);
+                }
+                if ((vcc >> i) & 1) != 0 { vt.wrapping_neg() } else { vs }
+            } else {
+                if !not_equal {
+                    vcc = (vcc & !(1 << (i + 8))) | (((vs >= vt) as u16) << (i + 8));
+                }
+                if ((vcc >> (i + 8)) & 1) != 0 { vt } else { vs }
+            }
+        });
+        self.vcc = vcc;
+        self.vco = 0;
+        self.vce = 0;
+    }
+
+    pub fn vcr(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        let mut vcc = 0;
+        self.per_lane(vd, vt, vs, e, |_, i, vs, vt| {
+            let (svs, svt) = (vs as i16 as i32, vt as i16 as i32);
+            if (svs ^ svt) < 0 {
+                let lte = svs + svt + 1 <= 0;
+                vcc |= ((lte as u16) << i) | (((svt < 0) as u16) << (i + 8));
+                if lte { !vt } else { vs }
+            } else {
+                let gte = svs - svt >= 0;
+                vcc |= (((svt < 0) as u16) << i) | ((gte as u16) << (i + 8));
+                if gte { vt } else { vs }
+            }
+        });
+        self.vcc = vcc;
+        self.vco = 0;
+        self.vce = 0;
+    }
+
+    /// Shared by the single-lane instructions: The lower accumulator receives vt (with element specifier applied),
+    /// the source is vt[e & 7] and the destination lane is the index of vs
+    fn single_lane(&mut self, vd: VR, vt: VR, vs: VR, e: Element, f: impl FnOnce(&mut Self, u16) -> u16) {
+        let vt_vector = self.register(vt);
+        let vt_with_element = vt_vector.copy_with_element_specifier_applied(e);
+        for i in 0..8 {
+            self.set_accumulator_low(i, vt_with_element.get16(i));
+        }
+        let value = f(self, vt_vector.get16((e as usize) & 7));
+        let mut result = self.register(vd);
+        result.set16(vs.index() & 7, value);
+        self.set_register(vd, result);
+    }
+
+    fn divide(&mut self, vd: VR, vt: VR, vs: VR, e: Element, low: bool, f: fn(u32) -> u32) {
+        self.single_lane(vd, vt, vs, e, |unit, value| {
+            let input = if low && unit.divide_double_precision {
+                ((unit.divide_input as u32) << 16) | (value as u32)
+            } else {
+                value as i16 as u32
+            };
+            let result = f(input);
+            unit.divide_output = (result >> 16) as u16;
+            unit.divide_double_precision = false;
+            result as u16
+        });
+    }
+
+    fn divide_high(&mut self, vd: VR, vt: VR, vs: VR, e: Element) {
+        self.single_lane(vd, vt, vs, e, |unit, value| {
+            unit.divide_input = value;
+            unit.divide_double_precision = true;
+            unit.divide_output
+        });
+    }
+
+    pub fn vrcp(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.divide(vd, vt, vs, e, false, rcp) }
+
+    pub fn vrcpl(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.divide(vd, vt, vs, e, true, rcp) }
+
+    pub fn vrcph(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.divide_high(vd, vt, vs, e) }
+
+    pub fn vrsq(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.divide(vd, vt, vs, e, false, rsq) }
+
+    pub fn vrsql(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.divide(vd, vt, vs, e, true, rsq) }
+
+    pub fn vrsqh(&mut self, vd: VR, vt: VR, vs: VR, e: Element) { self.divide_high(vd, vt, vs, e) }
+}
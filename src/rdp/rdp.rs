@@ -8,6 +8,10 @@ enum RegisterOffset {
     End = 0x04,
     Current = 0x08,
     Status = 0x0C,
+    Clock = 0x10,
+    BufBusy = 0x14,
+    PipeBusy = 0x18,
+    Tmem = 0x1C,
 }
 
 pub const DP_STATUS_XBUS: u32 = 0x1;
@@ -61,6 +65,14 @@ impl RDP {
 
     pub fn status() -> u32 { Self::get_register(RegisterOffset::Status) }
 
+    pub fn clock() -> u32 { Self::get_register(RegisterOffset::Clock) }
+
+    pub fn buf_busy() -> u32 { Self::get_register(RegisterOffset::BufBusy) }
+
+    pub fn pipe_busy() -> u32 { Self::get_register(RegisterOffset::PipeBusy) }
+
+    pub fn tmem() -> u32 { Self::get_register(RegisterOffset::Tmem) }
+
     /// Runs the RDP and immediately returns
     /// This is marked as unsafe as the memory that is being written to by the RDP (framebuffer/depth-buffer)
     /// might not be valid by the time this finishes
@@ -1,7 +1,11 @@
 use core::mem::size_of;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::rsp::spmem::SPMEM;
 
+/// The end (in bytes) of the furthest instruction that was written. Programs that wrap around end at 0x1000
+static WRITTEN_END: AtomicUsize = AtomicUsize::new(0);
+
 pub struct DMEMWriter {
     offset: usize,
 }
@@ -13,8 +17,24 @@ impl DMEMWriter {
 
     pub fn write(&mut self, value: u32) {
         SPMEM::write(self.offset | 0x1000, value);
+        WRITTEN_END.fetch_max(self.offset + size_of::<u32>(), Ordering::Relaxed);
         self.offset = (self.offset + size_of::<u32>()) & 0xFFC;
     }
 
     pub fn offset(&self) -> usize { return self.offset; }
+
+    /// Reads back a word that was written before
+    pub fn read_at(&self, offset: usize) -> u32 {
+        SPMEM::read((offset & 0xFFC) | 0x1000)
+    }
+
+    /// Overwrites a word that was written before. The current offset stays unchanged
+    pub fn patch(&mut self, offset: usize, value: u32) {
+        SPMEM::write((offset & 0xFFC) | 0x1000, value);
+    }
+
+    /// Returns how many bytes of IMEM (starting at 0) were written since the last call and resets that state
+    pub fn take_written_end() -> usize {
+        WRITTEN_END.swap(0, Ordering::Relaxed)
+    }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 pub mod dmem_writer;
 pub mod rsp;
 pub mod rsp_assembler;
+pub mod rsp_disassembler;
 pub mod rsp_macros;
 pub mod spmem;
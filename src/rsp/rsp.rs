@@ -1,6 +1,13 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+use arbitrary_int::u12;
+use bitbybit::bitfield;
+
 const SP_BASE_REG: *mut u32 = 0xA404_0000 as *mut u32;
 const SP_PC_REG: *mut u32 = 0xA408_0000 as *mut u32;
 
+/// Set by start_running. The test runner uses this to find out whether a failed test ran anything on the RSP
+static STARTED: AtomicBool = AtomicBool::new(false);
+
 #[allow(dead_code)]
 enum RegisterOffset {
     SPAddress = 0x00,
@@ -15,6 +22,7 @@ enum RegisterOffset {
 
 pub const SP_STATUS_HALT: u32 = 0b1;
 pub const SP_STATUS_DMA_BUSY: u32 = 0b100;
+pub const SP_STATUS_DMA_FULL: u32 = 0b1000;
 pub const SP_STATUS_INTERRUPT_ON_BREAK: u32 = 0b1000000;
 
 pub const SP_STATUS_SET_CLEAR_HALT: u32 = 0b1;
@@ -25,6 +33,21 @@ pub const SP_STATUS_SET_SET_INTERRUPT: u32 = 0b1_0000;
 pub const SP_STATUS_SET_CLEAR_INTERRUPT_ON_BREAK: u32 = 0b1_0000_000;
 pub const SP_STATUS_SET_SET_INTERRUPT_ON_BREAK: u32 = 0b10_0000_000;
 
+/// Value of SP_RD_LEN/SP_WR_LEN. A DMA transfers count + 1 rows of length + 1 bytes (rounded up to a multiple
+/// of 8) each. After each row, skip bytes are skipped in RDRAM (but not in SPMEM)
+#[bitfield(u32, default: 0)]
+#[derive(Eq, PartialEq, Debug)]
+pub struct DMALength {
+    #[bits(20..=31, rw)]
+    pub skip: u12,
+
+    #[bits(12..=19, rw)]
+    pub count: u8,
+
+    #[bits(0..=11, rw)]
+    pub length: u12,
+}
+
 pub struct RSP {
 }
 
@@ -53,12 +76,20 @@ impl RSP {
         Self::get_register(RegisterOffset::DRAMAddress)
     }
 
-    fn set_read_length(value: u32) {
-        Self::set_register(RegisterOffset::ReadLength, value);
+    fn set_read_length(value: DMALength) {
+        Self::set_register(RegisterOffset::ReadLength, value.raw_value());
     }
 
-    fn set_write_length(value: u32) {
-        Self::set_register(RegisterOffset::WriteLength, value);
+    pub fn read_length() -> DMALength {
+        DMALength::new_with_raw_value(Self::get_register(RegisterOffset::ReadLength))
+    }
+
+    fn set_write_length(value: DMALength) {
+        Self::set_register(RegisterOffset::WriteLength, value.raw_value());
+    }
+
+    pub fn write_length() -> DMALength {
+        DMALength::new_with_raw_value(Self::get_register(RegisterOffset::WriteLength))
     }
 
     pub fn status() -> u32 {
@@ -69,6 +100,14 @@ impl RSP {
         Self::set_register(RegisterOffset::Status, value);
     }
 
+    pub fn dma_full() -> u32 {
+        Self::get_register(RegisterOffset::DMAFull)
+    }
+
+    pub fn dma_busy() -> u32 {
+        Self::get_register(RegisterOffset::DMABusy)
+    }
+
     pub fn set_pc(value: u32) {
         unsafe { SP_PC_REG.write_volatile(value) }
     }
@@ -78,6 +117,7 @@ impl RSP {
     }
 
     pub fn start_running(pc: usize) {
+        STARTED.store(true, Ordering::Relaxed);
         Self::set_pc(pc as u32);
 
         // Clear status and clear interrupt just in case
@@ -86,13 +126,26 @@ impl RSP {
             SP_STATUS_SET_CLEAR_INTERRUPT_ON_BREAK);
     }
 
+    /// Returns whether start_running was called since the last call and resets that state
+    pub fn take_started() -> bool {
+        STARTED.swap(false, Ordering::Relaxed)
+    }
+
     pub unsafe fn start_dma_sp_to_cpu(spmem: u32, to: *mut u8, length: u32) {
+        Self::start_dma_sp_to_cpu_2d(spmem, to, DMALength::new_with_raw_value(length));
+    }
+
+    pub fn start_dma_cpu_to_sp(from: *const u8, spmem: u32, length: u32) {
+        Self::start_dma_cpu_to_sp_2d(from, spmem, DMALength::new_with_raw_value(length));
+    }
+
+    pub unsafe fn start_dma_sp_to_cpu_2d(spmem: u32, to: *mut u8, length: DMALength) {
         Self::set_sp_address(spmem);
         Self::set_dram_address(to as usize as u32);
         Self::set_write_length(length);
     }
 
-    pub fn start_dma_cpu_to_sp(from: *const u8, spmem: u32, length: u32) {
+    pub fn start_dma_cpu_to_sp_2d(from: *const u8, spmem: u32, length: DMALength) {
         Self::set_sp_address(spmem);
         Self::set_dram_address(from as usize as u32);
         Self::set_read_length(length);
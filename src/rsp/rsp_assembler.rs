@@ -246,7 +246,7 @@ enum CP0OP {
 #[repr(u8)]
 pub enum CP0Register {
     SPAddress = 0, DRAMAddress = 1, ReadLength = 2, WriteLength = 3, SPStatus = 4, DmaFull = 5, DmaBusy = 6, Semaphore = 7,
-    DPStart = 8, DPEnd = 9, DPStatus = 11, DPClock = 12
+    DPStart = 8, DPEnd = 9, DPCurrent = 10, DPStatus = 11, DPClock = 12, DPBufBusy = 13, DPPipeBusy = 14, DPTmem = 15
 }
 // @formatter:on
 
@@ -518,6 +518,21 @@ impl RSPAssembler {
         self.write_bgtz(rs, offset as i16);
     }
 
+    /// Points the branch or jump that was written at `branch` to `target`. This allows branching forward: The
+    /// branch is written with an offset of 0 and patched once the target is known
+    pub fn patch_branch(&mut self, branch: &RSMAssemblerJumpTarget, target: &RSMAssemblerJumpTarget) {
+        let instruction = self.writer.read_at(branch.offset);
+        let op = instruction >> 26;
+        let patched = if op == OP::J as u32 || op == OP::JAL as u32 {
+            (instruction & 0xFC00_0000) | ((target.offset >> 2) as u32)
+        } else {
+            // Branches count instructions, starting at the delay slot
+            let offset = (target.offset as isize - (branch.offset as isize + 4)) >> 2;
+            (instruction & 0xFFFF_0000) | ((offset as u16) as u32)
+        };
+        self.writer.patch(branch.offset, patched);
+    }
+
     // COP0
     pub fn write_mfc0(&mut self, cp0register: CP0Register, rt: GPR) {
         self.write_cop0(CP0OP::MFC0, cp0register, rt);
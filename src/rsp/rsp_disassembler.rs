@@ -0,0 +1,201 @@
+use alloc::format;
+use alloc::string::String;
+
+use crate::rsp::rsp_assembler::{E, Element, VR};
+use crate::rsp::spmem::SPMEM;
+
+// Turns RSP instructions back into text. Register and element names match the enums in rsp_assembler.rs.
+// Operands are in MIPS order (destination first), vector instructions are written as "VMULF vd, vs, vt[e]".
+
+// @formatter:off
+const GPR_NAMES: [&str; 32] = [
+    "R0", "AT", "V0", "V1", "A0", "A1", "R2", "R3",
+    "T0", "T1", "T2", "T3", "T4", "T5", "T6", "T7",
+    "S0", "S1", "S2", "S3", "S4", "S5", "S6", "S7",
+    "T8", "T9", "K0", "K1", "GP", "SP", "S8", "RA",
+];
+
+const CP0_REGISTER_NAMES: [&str; 16] = [
+    "SPAddress", "DRAMAddress", "ReadLength", "WriteLength", "SPStatus", "DmaFull", "DmaBusy", "Semaphore",
+    "DPStart", "DPEnd", "DPCurrent", "DPStatus", "DPClock", "DPBufBusy", "DPPipeBusy", "DPTmem",
+];
+
+const VECTOR_OP_NAMES: [&str; 64] = [
+    "VMULF", "VMULU", "VRNDP", "VMULQ", "VMUDL", "VMUDM", "VMUDN", "VMUDH", "VMACF", "VMACU", "VRNDN", "VMACQ", "VMADL", "VMADM", "VMADN", "VMADH",
+    "VADD", "VSUB", "VSUT", "VABS", "VADDC", "VSUBC", "VADDB", "VSUBB", "VACCB", "VSUCB", "VSAD", "VSAC", "VSUM", "VSAR", "V30", "V31",
+    "VLT", "VEQ", "VNE", "VGE", "VCL", "VCH", "VCR", "VMRG", "VAND", "VNAND", "VOR", "VNOR", "VXOR", "VNXOR", "V46", "V47",
+    "VRCP", "VRCPL", "VRCPH", "VMOV", "VRSQ", "VRSQL", "VRSQH", "VNOP", "VEXTT", "VEXTQ", "VEXTN", "V59", "VINST", "VINSQ", "VINSN", "VNULL",
+];
+
+/// Name and offset shift of LWC2/SWC2, indexed by the opcode in bits 11..16
+const WC2_OPS: [Option<(&str, u32)>; 16] = [
+    Some(("B", 0)), Some(("S", 1)), Some(("L", 2)), Some(("D", 3)), Some(("Q", 4)), Some(("R", 4)), Some(("P", 3)), Some(("U", 3)),
+    Some(("H", 4)), Some(("F", 4)), Some(("W", 4)), Some(("T", 4)), None, None, None, None,
+];
+// @formatter:on
+
+const CP2_FLAGS_REGISTER_NAMES: [&str; 3] = ["VCO", "VCC", "VCE"];
+
+fn gpr(index: u32) -> &'static str { GPR_NAMES[(index & 0x1F) as usize] }
+
+fn vr(index: u32) -> VR { VR::from_index((index & 0x1F) as usize).unwrap() }
+
+fn e(index: u32) -> E { E::from_index((index & 0xF) as usize).unwrap() }
+
+fn cp2_flags_register(index: u32) -> String {
+    match CP2_FLAGS_REGISTER_NAMES.get(index as usize) {
+        Some(name) => String::from(*name),
+        None => format!("{}", index),
+    }
+}
+
+/// Returns the branch target of a relative branch at the given pc
+fn branch_target(pc: usize, instruction: u32) -> usize {
+    (pc + 4).wrapping_add(((instruction as i16 as i32) << 2) as usize) & 0xFFC
+}
+
+fn disassemble_special(instruction: u32) -> Option<String> {
+    let rs = gpr(instruction >> 21);
+    let rt = gpr(instruction >> 16);
+    let rd = gpr(instruction >> 11);
+    let sa = (instruction >> 6) & 0x1F;
+    Some(match instruction & 0x3F {
+        0 if instruction == 0 => String::from("NOP"),
+        0 => format!("SLL {}, {}, {}", rd, rt, sa),
+        2 => format!("SRL {}, {}, {}", rd, rt, sa),
+        3 => format!("SRA {}, {}, {}", rd, rt, sa),
+        4 => format!("SLLV {}, {}, {}", rd, rt, rs),
+        6 => format!("SRLV {}, {}, {}", rd, rt, rs),
+        7 => format!("SRAV {}, {}, {}", rd, rt, rs),
+        8 => format!("JR {}", rs),
+        9 => format!("JALR {}, {}", rd, rs),
+        13 => String::from("BREAK"),
+        32 => format!("ADD {}, {}, {}", rd, rs, rt),
+        33 => format!("ADDU {}, {}, {}", rd, rs, rt),
+        34 => format!("SUB {}, {}, {}", rd, rs, rt),
+        35 => format!("SUBU {}, {}, {}", rd, rs, rt),
+        36 => format!("AND {}, {}, {}", rd, rs, rt),
+        37 => format!("OR {}, {}, {}", rd, rs, rt),
+        38 => format!("XOR {}, {}, {}", rd, rs, rt),
+        39 => format!("NOR {}, {}, {}", rd, rs, rt),
+        42 => format!("SLT {}, {}, {}", rd, rs, rt),
+        43 => format!("SLTU {}, {}, {}", rd, rs, rt),
+        _ => return None,
+    })
+}
+
+fn disassemble_cop0(instruction: u32) -> Option<String> {
+    let rt = gpr(instruction >> 16);
+    let register = CP0_REGISTER_NAMES[((instruction >> 11) & 0xF) as usize];
+    match (instruction >> 21) & 0x1F {
+        0 => Some(format!("MFC0 {}, {}", rt, register)),
+        4 => Some(format!("MTC0 {}, {}", rt, register)),
+        _ => None,
+    }
+}
+
+fn disassemble_vector(instruction: u32) -> String {
+    let name = VECTOR_OP_NAMES[(instruction & 0x3F) as usize];
+    let vd = vr(instruction >> 6);
+    let vs = vr(instruction >> 11);
+    let vt = vr(instruction >> 16);
+    let element_index = ((instruction >> 21) & 0xF) as usize;
+    match name {
+        "VSAR" => match element_index {
+            8 => format!("VSAR {:?}, High", vd),
+            9 => format!("VSAR {:?}, Mid", vd),
+            10 => format!("VSAR {:?}, Low", vd),
+            _ => format!("VSAR {:?}, {:?}, {:?}[{:?}]", vd, vs, vt, e(element_index as u32)),
+        },
+        // The single lane instructions use vs as the destination lane
+        "VRCP" | "VRCPL" | "VRCPH" | "VMOV" | "VRSQ" | "VRSQL" | "VRSQH" => {
+            format!("{} {:?}[{}], {:?}[{:?}]", name, vd, vs.index() & 7, vt, Element::from_index(element_index).unwrap())
+        }
+        _ => {
+            let element = Element::from_index(element_index).unwrap();
+            if element == Element::All {
+                format!("{} {:?}, {:?}, {:?}", name, vd, vs, vt)
+            } else {
+                format!("{} {:?}, {:?}, {:?}[{:?}]", name, vd, vs, vt, element)
+            }
+        }
+    }
+}
+
+fn disassemble_cop2(instruction: u32) -> Option<String> {
+    let rt = gpr(instruction >> 16);
+    let rd = (instruction >> 11) & 0x1F;
+    match (instruction >> 21) & 0x1F {
+        0 => Some(format!("MFC2 {}, {:?}[{:?}]", rt, vr(rd), e(instruction >> 7))),
+        2 => Some(format!("CFC2 {}, {}", rt, cp2_flags_register(rd))),
+        4 => Some(format!("MTC2 {}, {:?}[{:?}]", rt, vr(rd), e(instruction >> 7))),
+        6 => Some(format!("CTC2 {}, {}", rt, cp2_flags_register(rd))),
+        16..=31 => Some(disassemble_vector(instruction)),
+        _ => None,
+    }
+}
+
+fn disassemble_wc2(prefix: &str, instruction: u32) -> Option<String> {
+    let (name, shift) = (*WC2_OPS.get(((instruction >> 11) & 0x1F) as usize)?)?;
+    // Sign extend the 7 bit offset and scale it back into a byte offset like RSPAssembler expects it
+    let offset = ((((instruction & 0x7F) << 25) as i32) >> 25) << shift;
+    let sign = if offset < 0 { "-" } else { "" };
+    Some(format!("{}{}V {:?}[{:?}], {}0x{:X}({})", prefix, name, vr(instruction >> 16), e(instruction >> 7), sign, offset.unsigned_abs(), gpr(instruction >> 21)))
+}
+
+/// Disassembles a single instruction. The pc is needed to resolve branch targets
+pub fn disassemble(pc: usize, instruction: u32) -> String {
+    let rs = gpr(instruction >> 21);
+    let rt = gpr(instruction >> 16);
+    let imm = instruction as u16;
+    let simm = instruction as i16;
+    let result = match instruction >> 26 {
+        0 => disassemble_special(instruction),
+        1 => match (instruction >> 16) & 0x1F {
+            0 => Some(format!("BLTZ {}, 0x{:03X}", rs, branch_target(pc, instruction))),
+            1 => Some(format!("BGEZ {}, 0x{:03X}", rs, branch_target(pc, instruction))),
+            16 => Some(format!("BLTZAL {}, 0x{:03X}", rs, branch_target(pc, instruction))),
+            17 => Some(format!("BGEZAL {}, 0x{:03X}", rs, branch_target(pc, instruction))),
+            _ => None,
+        },
+        2 => Some(format!("J 0x{:03X}", (instruction << 2) & 0xFFC)),
+        3 => Some(format!("JAL 0x{:03X}", (instruction << 2) & 0xFFC)),
+        4 => Some(format!("BEQ {}, {}, 0x{:03X}", rs, rt, branch_target(pc, instruction))),
+        5 => Some(format!("BNE {}, {}, 0x{:03X}", rs, rt, branch_target(pc, instruction))),
+        6 => Some(format!("BLEZ {}, 0x{:03X}", rs, branch_target(pc, instruction))),
+        7 => Some(format!("BGTZ {}, 0x{:03X}", rs, branch_target(pc, instruction))),
+        8 => Some(format!("ADDI {}, {}, {}", rt, rs, simm)),
+        9 => Some(format!("ADDIU {}, {}, {}", rt, rs, simm)),
+        10 => Some(format!("SLTI {}, {}, {}", rt, rs, simm)),
+        11 => Some(format!("SLTIU {}, {}, {}", rt, rs, simm)),
+        12 => Some(format!("ANDI {}, {}, 0x{:X}", rt, rs, imm)),
+        13 => Some(format!("ORI {}, {}, 0x{:X}", rt, rs, imm)),
+        14 => Some(format!("XORI {}, {}, 0x{:X}", rt, rs, imm)),
+        15 => Some(format!("LUI {}, 0x{:X}", rt, imm)),
+        16 => disassemble_cop0(instruction),
+        18 => disassemble_cop2(instruction),
+        32 => Some(format!("LB {}, {}({})", rt, simm, rs)),
+        33 => Some(format!("LH {}, {}({})", rt, simm, rs)),
+        35 => Some(format!("LW {}, {}({})", rt, simm, rs)),
+        36 => Some(format!("LBU {}, {}({})", rt, simm, rs)),
+        37 => Some(format!("LHU {}, {}({})", rt, simm, rs)),
+        39 => Some(format!("LWU {}, {}({})", rt, simm, rs)),
+        40 => Some(format!("SB {}, {}({})", rt, simm, rs)),
+        41 => Some(format!("SH {}, {}({})", rt, simm, rs)),
+        43 => Some(format!("SW {}, {}({})", rt, simm, rs)),
+        50 => disassemble_wc2("L", instruction),
+        58 => disassemble_wc2("S", instruction),
+        _ => None,
+    };
+    result.unwrap_or_else(|| format!(".word 0x{:08X}", instruction))
+}
+
+/// Disassembles the first `length` bytes of IMEM. Each line has the address and the raw instruction
+pub fn disassemble_imem(length: usize) -> String {
+    let mut result = String::new();
+    for address in (0..length.min(0x1000)).step_by(4) {
+        let instruction = SPMEM::read(0x1000 + address);
+        result += &format!("{:03X}: {:08X}  {}\n", address, instruction, disassemble(address, instruction));
+    }
+    result
+}
@@ -1,4 +1,6 @@
-use crate::rsp::rsp_assembler::{E, Element, GPR, RSPAssembler, VR};
+use alloc::vec::Vec;
+
+use crate::rsp::rsp_assembler::{E, Element, GPR, RSMAssemblerJumpTarget, RSPAssembler, VR};
 
 /// Sets the accumulator to the value that is currently in the three registers top::mid::low
 pub fn assemble_set_accumulator_to(assembler: &mut RSPAssembler, top: VR, mid: VR, low: VR, scratch: VR, scratch2: VR, scratch3: VR, scratch_gpr: GPR) {
@@ -36,3 +38,397 @@ pub fn assemble_set_accumulator_to(assembler: &mut RSPAssembler, top: VR, mid: V
     assembler.write_vaddc(scratch3, scratch2, low, Element::_0);
 }
 
+
+/// Keeps track of the labels of an [rsp_asm] block. Branches and jumps are written with a zero target first and
+/// patched through [RSPAssembler::patch_branch] once all labels are known, which allows branching forward
+pub struct RSPAssemblerLabels {
+    labels: Vec<(&'static str, RSMAssemblerJumpTarget)>,
+    references: Vec<(&'static str, RSMAssemblerJumpTarget)>,
+}
+
+impl RSPAssemblerLabels {
+    pub const fn new() -> Self { Self { labels: Vec::new(), references: Vec::new() } }
+
+    pub fn define(&mut self, name: &'static str, target: RSMAssemblerJumpTarget) {
+        assert!(self.labels.iter().all(|(existing, _)| *existing != name), "Label {} is defined twice", name);
+        self.labels.push((name, target));
+    }
+
+    /// Remembers that the given branch or jump needs to point at the label
+    pub fn reference(&mut self, name: &'static str, branch: RSMAssemblerJumpTarget) {
+        self.references.push((name, branch));
+    }
+
+    /// Calls `patch` for every branch or jump with the target of its label. [rsp_asm] passes
+    /// [RSPAssembler::patch_branch] here, which works no matter whether the assembler is owned or borrowed
+    pub fn resolve<F: FnMut(&RSMAssemblerJumpTarget, &RSMAssemblerJumpTarget)>(&self, mut patch: F) {
+        for (name, branch) in &self.references {
+            match self.labels.iter().find(|(label, _)| label == name) {
+                Some((_, target)) => patch(branch, target),
+                None => panic!("Label {} is used but not defined", name),
+            }
+        }
+    }
+}
+
+/// Writes RSP assembly through an [RSPAssembler]. Every instruction ends with a semicolon and labels end with a colon:
+/// ```ignore
+/// rsp_asm!(assembler,
+///     li S1, 4;
+///   loop_start:
+///     lqv V0[_0], 0x00(S0);
+///     vmudh V2, V0, V1[Q1];
+///     addiu S1, S1, -1;
+///     bgtz S1, loop_start;
+///     addiu S0, S0, 0x10;
+///     beq R0, R0, done;
+///     nop;
+///   done:
+///     break;
+/// );
+/// ```
+/// - Registers and element specifiers use the names of GPR, VR, E and Element. Vector instructions are written as
+///   "op vd, vs, vt[e]" (no element means Element::All), VRCP and friends as "op vd[lane], vt[e]"
+/// - Anything in braces is Rust: registers, elements, lanes, immediates and offsets can be interpolated, e.g.
+///   "lqv {vt}[_0], {offset}(R0);"
+/// - Branches and jumps take labels, which can be defined before or after their use. They are resolved when the
+///   block ends, so a label is only visible within its own block
+/// - Every instruction costs one level of macro recursion. With the default recursion limit of 128, a block can hold
+///   roughly 120 instructions - longer programs need to be split into several blocks (which can't share labels)
+#[macro_export]
+macro_rules! rsp_asm {
+    ($a:ident, $($body:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut labels = $crate::rsp::rsp_macros::RSPAssemblerLabels::new();
+        $crate::rsp_asm!(@munch $a, labels, $($body)*);
+        labels.resolve(|branch, target| $a.patch_branch(branch, target));
+    }};
+
+    // Operands
+    (@gpr $r:ident) => { $crate::rsp::rsp_assembler::GPR::$r };
+    (@gpr {$r:expr}) => { $r };
+    (@vr $r:ident) => { $crate::rsp::rsp_assembler::VR::$r };
+    (@vr {$r:expr}) => { $r };
+    (@lane $lane:literal) => { $crate::rsp::rsp_assembler::VR::from_index($lane).unwrap() };
+    (@lane {$lane:expr}) => { $crate::rsp::rsp_assembler::VR::from_index($lane).unwrap() };
+    (@e $e:ident) => { $crate::rsp::rsp_assembler::E::$e };
+    (@e {$e:expr}) => { $e };
+    (@element) => { $crate::rsp::rsp_assembler::Element::All };
+    (@element $e:ident) => { $crate::rsp::rsp_assembler::Element::$e };
+    (@element {$e:expr}) => { $e };
+    (@offset $off:literal) => { $off };
+    (@offset $off:ident) => { $off };
+    (@offset {$off:expr}) => { $off };
+
+    // Labels and branches
+    (@munch $a:ident, $l:ident, ) => {};
+    (@munch $a:ident, $l:ident, $label:ident : $($rest:tt)*) => {
+        $l.define(stringify!($label), $a.get_jump_target());
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, j $label:ident; $($rest:tt)*) => {
+        $l.reference(stringify!($label), $a.get_jump_target());
+        $a.write_j(0);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, jal $label:ident; $($rest:tt)*) => {
+        $l.reference(stringify!($label), $a.get_jump_target());
+        $a.write_jal(0);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, beq $rs:tt, $rt:tt, $label:ident; $($rest:tt)*) => {
+        $l.reference(stringify!($label), $a.get_jump_target());
+        $a.write_beq($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), 0);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, bne $rs:tt, $rt:tt, $label:ident; $($rest:tt)*) => {
+        $l.reference(stringify!($label), $a.get_jump_target());
+        $a.write_bne($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), 0);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+
+    // Scalar instructions
+    (@munch $a:ident, $l:ident, nop; $($rest:tt)*) => {
+        $a.write_nop();
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, break; $($rest:tt)*) => {
+        $a.write_break();
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, jr $rs:tt; $($rest:tt)*) => {
+        $a.write_jr($crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, jalr $rd:tt, $rs:tt; $($rest:tt)*) => {
+        $a.write_jalr($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, lui $rt:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_lui($crate::rsp_asm!(@gpr $rt), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, li $rt:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_li($crate::rsp_asm!(@gpr $rt), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, mfc0 $rt:tt, $cp0register:ident; $($rest:tt)*) => {
+        $a.write_mfc0($crate::rsp::rsp_assembler::CP0Register::$cp0register, $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, mtc0 $rt:tt, $cp0register:ident; $($rest:tt)*) => {
+        $a.write_mtc0($crate::rsp::rsp_assembler::CP0Register::$cp0register, $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, cfc2 $rt:tt, $flags:ident; $($rest:tt)*) => {
+        $a.write_cfc2($crate::rsp::rsp_assembler::CP2FlagsRegister::$flags, $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, ctc2 $rt:tt, $flags:ident; $($rest:tt)*) => {
+        $a.write_ctc2($crate::rsp::rsp_assembler::CP2FlagsRegister::$flags, $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, mfc2 $rt:tt, $vd:tt[$e:tt]; $($rest:tt)*) => {
+        $a.write_mfc2($crate::rsp_asm!(@vr $vd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@e $e));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, mtc2 $rt:tt, $vd:tt[$e:tt]; $($rest:tt)*) => {
+        $a.write_mtc2($crate::rsp_asm!(@vr $vd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@e $e));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, vsar $vd:tt, $accumulator:ident; $($rest:tt)*) => {
+        $a.write_vsar($crate::rsp_asm!(@vr $vd), $crate::rsp::rsp_assembler::VSARAccumulator::$accumulator);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, add $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_add($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, addu $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_addu($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, sub $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_sub($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, subu $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_subu($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, and $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_and($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, or $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_or($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rs), $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, xor $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_xor($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rs), $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, nor $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_nor($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rs), $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, slt $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_slt($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rs), $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, sltu $rd:tt, $rs:tt, $rt:tt; $($rest:tt)*) => {
+        $a.write_sltu($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rs), $crate::rsp_asm!(@gpr $rt));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, sll $rd:tt, $rt:tt, $sa:expr; $($rest:tt)*) => {
+        $a.write_sll($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), arbitrary_int::u5::new($sa));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, srl $rd:tt, $rt:tt, $sa:expr; $($rest:tt)*) => {
+        $a.write_srl($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), arbitrary_int::u5::new($sa));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, sra $rd:tt, $rt:tt, $sa:expr; $($rest:tt)*) => {
+        $a.write_sra($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), arbitrary_int::u5::new($sa));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, sllv $rd:tt, $rt:tt, $rs:tt; $($rest:tt)*) => {
+        $a.write_sllv($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, srlv $rd:tt, $rt:tt, $rs:tt; $($rest:tt)*) => {
+        $a.write_srlv($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, srav $rd:tt, $rt:tt, $rs:tt; $($rest:tt)*) => {
+        $a.write_srav($crate::rsp_asm!(@gpr $rd), $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, addi $rt:tt, $rs:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_addi($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, addiu $rt:tt, $rs:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_addiu($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, slti $rt:tt, $rs:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_slti($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, sltiu $rt:tt, $rs:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_sltiu($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, andi $rt:tt, $rs:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_andi($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, ori $rt:tt, $rs:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_ori($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, xori $rt:tt, $rs:tt, $imm:expr; $($rest:tt)*) => {
+        $a.write_xori($crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $rs), $imm);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+
+    // Branches that compare against zero. This comes after the scalar instructions as e.g. MTC0 has the same shape
+    (@munch $a:ident, $l:ident, $op:ident $rs:tt, $label:ident; $($rest:tt)*) => {
+        $l.reference(stringify!($label), $a.get_jump_target());
+        $crate::rsp_asm!(@call $a, $op, $crate::rsp_asm!(@gpr $rs), 0);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+
+    // Loads and stores
+    (@munch $a:ident, $l:ident, $op:ident $rt:tt, $off:tt($base:tt); $($rest:tt)*) => {
+        $crate::rsp_asm!(@call $a, $op, $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $base), $crate::rsp_asm!(@offset $off));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, $op:ident $rt:tt, -$off:literal($base:tt); $($rest:tt)*) => {
+        $crate::rsp_asm!(@call $a, $op, $crate::rsp_asm!(@gpr $rt), $crate::rsp_asm!(@gpr $base), -$off);
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, $op:ident $vt:tt[$e:tt], $off:tt($base:tt); $($rest:tt)*) => {
+        $crate::rsp_asm!(@call $a, $op, $crate::rsp_asm!(@vr $vt), $crate::rsp_asm!(@e $e), $crate::rsp_asm!(@offset $off), $crate::rsp_asm!(@gpr $base));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, $op:ident $vt:tt[$e:tt], -$off:literal($base:tt); $($rest:tt)*) => {
+        $crate::rsp_asm!(@call $a, $op, $crate::rsp_asm!(@vr $vt), $crate::rsp_asm!(@e $e), -$off, $crate::rsp_asm!(@gpr $base));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+
+    // Vector instructions
+    (@munch $a:ident, $l:ident, $op:ident $vd:tt[$lane:tt], $vt:tt[$e:tt]; $($rest:tt)*) => {
+        $crate::rsp_asm!(@call $a, $op, $crate::rsp_asm!(@vr $vd), $crate::rsp_asm!(@vr $vt), $crate::rsp_asm!(@lane $lane), $crate::rsp_asm!(@element $e));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+    (@munch $a:ident, $l:ident, $op:ident $vd:tt, $vs:tt, $vt:tt $([$e:tt])?; $($rest:tt)*) => {
+        $crate::rsp_asm!(@call $a, $op, $crate::rsp_asm!(@vr $vd), $crate::rsp_asm!(@vr $vt), $crate::rsp_asm!(@vr $vs), $crate::rsp_asm!(@element $($e)?));
+        $crate::rsp_asm!(@munch $a, $l, $($rest)*);
+    };
+
+    // Instructions that share a syntax with others, by name
+    (@call $a:ident, bltz, $($args:tt)*) => { $a.write_bltz($($args)*) };
+    (@call $a:ident, bgez, $($args:tt)*) => { $a.write_bgez($($args)*) };
+    (@call $a:ident, bltzal, $($args:tt)*) => { $a.write_bltzal($($args)*) };
+    (@call $a:ident, bgezal, $($args:tt)*) => { $a.write_bgezal($($args)*) };
+    (@call $a:ident, blez, $($args:tt)*) => { $a.write_blez($($args)*) };
+    (@call $a:ident, bgtz, $($args:tt)*) => { $a.write_bgtz($($args)*) };
+    (@call $a:ident, lb, $($args:tt)*) => { $a.write_lb($($args)*) };
+    (@call $a:ident, lbu, $($args:tt)*) => { $a.write_lbu($($args)*) };
+    (@call $a:ident, lh, $($args:tt)*) => { $a.write_lh($($args)*) };
+    (@call $a:ident, lhu, $($args:tt)*) => { $a.write_lhu($($args)*) };
+    (@call $a:ident, lw, $($args:tt)*) => { $a.write_lw($($args)*) };
+    (@call $a:ident, lwu, $($args:tt)*) => { $a.write_lwu($($args)*) };
+    (@call $a:ident, sb, $($args:tt)*) => { $a.write_sb($($args)*) };
+    (@call $a:ident, sh, $($args:tt)*) => { $a.write_sh($($args)*) };
+    (@call $a:ident, sw, $($args:tt)*) => { $a.write_sw($($args)*) };
+    (@call $a:ident, lbv, $($args:tt)*) => { $a.write_lbv($($args)*) };
+    (@call $a:ident, ldv, $($args:tt)*) => { $a.write_ldv($($args)*) };
+    (@call $a:ident, lfv, $($args:tt)*) => { $a.write_lfv($($args)*) };
+    (@call $a:ident, lhv, $($args:tt)*) => { $a.write_lhv($($args)*) };
+    (@call $a:ident, llv, $($args:tt)*) => { $a.write_llv($($args)*) };
+    (@call $a:ident, lpv, $($args:tt)*) => { $a.write_lpv($($args)*) };
+    (@call $a:ident, lqv, $($args:tt)*) => { $a.write_lqv($($args)*) };
+    (@call $a:ident, lrv, $($args:tt)*) => { $a.write_lrv($($args)*) };
+    (@call $a:ident, lsv, $($args:tt)*) => { $a.write_lsv($($args)*) };
+    (@call $a:ident, ltv, $($args:tt)*) => { $a.write_ltv($($args)*) };
+    (@call $a:ident, luv, $($args:tt)*) => { $a.write_luv($($args)*) };
+    (@call $a:ident, lwv, $($args:tt)*) => { $a.write_lwv($($args)*) };
+    (@call $a:ident, sbv, $($args:tt)*) => { $a.write_sbv($($args)*) };
+    (@call $a:ident, sdv, $($args:tt)*) => { $a.write_sdv($($args)*) };
+    (@call $a:ident, sfv, $($args:tt)*) => { $a.write_sfv($($args)*) };
+    (@call $a:ident, shv, $($args:tt)*) => { $a.write_shv($($args)*) };
+    (@call $a:ident, slv, $($args:tt)*) => { $a.write_slv($($args)*) };
+    (@call $a:ident, spv, $($args:tt)*) => { $a.write_spv($($args)*) };
+    (@call $a:ident, sqv, $($args:tt)*) => { $a.write_sqv($($args)*) };
+    (@call $a:ident, srv, $($args:tt)*) => { $a.write_srv($($args)*) };
+    (@call $a:ident, ssv, $($args:tt)*) => { $a.write_ssv($($args)*) };
+    (@call $a:ident, stv, $($args:tt)*) => { $a.write_stv($($args)*) };
+    (@call $a:ident, suv, $($args:tt)*) => { $a.write_suv($($args)*) };
+    (@call $a:ident, swv, $($args:tt)*) => { $a.write_swv($($args)*) };
+    (@call $a:ident, vrcp, $($args:tt)*) => { $a.write_vrcp($($args)*) };
+    (@call $a:ident, vrcpl, $($args:tt)*) => { $a.write_vrcpl($($args)*) };
+    (@call $a:ident, vrcph, $($args:tt)*) => { $a.write_vrcph($($args)*) };
+    (@call $a:ident, vmov, $($args:tt)*) => { $a.write_vmov($($args)*) };
+    (@call $a:ident, vrsq, $($args:tt)*) => { $a.write_vrsq($($args)*) };
+    (@call $a:ident, vrsql, $($args:tt)*) => { $a.write_vrsql($($args)*) };
+    (@call $a:ident, vrsqh, $($args:tt)*) => { $a.write_vrsqh($($args)*) };
+    (@call $a:ident, v30, $($args:tt)*) => { $a.write_v30($($args)*) };
+    (@call $a:ident, v31, $($args:tt)*) => { $a.write_v31($($args)*) };
+    (@call $a:ident, v46, $($args:tt)*) => { $a.write_v46($($args)*) };
+    (@call $a:ident, v47, $($args:tt)*) => { $a.write_v47($($args)*) };
+    (@call $a:ident, v59, $($args:tt)*) => { $a.write_v59($($args)*) };
+    (@call $a:ident, vabs, $($args:tt)*) => { $a.write_vabs($($args)*) };
+    (@call $a:ident, vaccb, $($args:tt)*) => { $a.write_vaccb($($args)*) };
+    (@call $a:ident, vadd, $($args:tt)*) => { $a.write_vadd($($args)*) };
+    (@call $a:ident, vaddb, $($args:tt)*) => { $a.write_vaddb($($args)*) };
+    (@call $a:ident, vaddc, $($args:tt)*) => { $a.write_vaddc($($args)*) };
+    (@call $a:ident, vand, $($args:tt)*) => { $a.write_vand($($args)*) };
+    (@call $a:ident, vch, $($args:tt)*) => { $a.write_vch($($args)*) };
+    (@call $a:ident, vcl, $($args:tt)*) => { $a.write_vcl($($args)*) };
+    (@call $a:ident, vcr, $($args:tt)*) => { $a.write_vcr($($args)*) };
+    (@call $a:ident, veq, $($args:tt)*) => { $a.write_veq($($args)*) };
+    (@call $a:ident, vextn, $($args:tt)*) => { $a.write_vextn($($args)*) };
+    (@call $a:ident, vextq, $($args:tt)*) => { $a.write_vextq($($args)*) };
+    (@call $a:ident, vextt, $($args:tt)*) => { $a.write_vextt($($args)*) };
+    (@call $a:ident, vge, $($args:tt)*) => { $a.write_vge($($args)*) };
+    (@call $a:ident, vinsn, $($args:tt)*) => { $a.write_vinsn($($args)*) };
+    (@call $a:ident, vinsq, $($args:tt)*) => { $a.write_vinsq($($args)*) };
+    (@call $a:ident, vinst, $($args:tt)*) => { $a.write_vinst($($args)*) };
+    (@call $a:ident, vlt, $($args:tt)*) => { $a.write_vlt($($args)*) };
+    (@call $a:ident, vmacf, $($args:tt)*) => { $a.write_vmacf($($args)*) };
+    (@call $a:ident, vmacq, $($args:tt)*) => { $a.write_vmacq($($args)*) };
+    (@call $a:ident, vmacu, $($args:tt)*) => { $a.write_vmacu($($args)*) };
+    (@call $a:ident, vmadh, $($args:tt)*) => { $a.write_vmadh($($args)*) };
+    (@call $a:ident, vmadl, $($args:tt)*) => { $a.write_vmadl($($args)*) };
+    (@call $a:ident, vmadm, $($args:tt)*) => { $a.write_vmadm($($args)*) };
+    (@call $a:ident, vmadn, $($args:tt)*) => { $a.write_vmadn($($args)*) };
+    (@call $a:ident, vmrg, $($args:tt)*) => { $a.write_vmrg($($args)*) };
+    (@call $a:ident, vmudh, $($args:tt)*) => { $a.write_vmudh($($args)*) };
+    (@call $a:ident, vmudl, $($args:tt)*) => { $a.write_vmudl($($args)*) };
+    (@call $a:ident, vmudm, $($args:tt)*) => { $a.write_vmudm($($args)*) };
+    (@call $a:ident, vmudn, $($args:tt)*) => { $a.write_vmudn($($args)*) };
+    (@call $a:ident, vmulf, $($args:tt)*) => { $a.write_vmulf($($args)*) };
+    (@call $a:ident, vmulq, $($args:tt)*) => { $a.write_vmulq($($args)*) };
+    (@call $a:ident, vmulu, $($args:tt)*) => { $a.write_vmulu($($args)*) };
+    (@call $a:ident, vnand, $($args:tt)*) => { $a.write_vnand($($args)*) };
+    (@call $a:ident, vne, $($args:tt)*) => { $a.write_vne($($args)*) };
+    (@call $a:ident, vnop, $($args:tt)*) => { $a.write_vnop($($args)*) };
+    (@call $a:ident, vnor, $($args:tt)*) => { $a.write_vnor($($args)*) };
+    (@call $a:ident, vnull, $($args:tt)*) => { $a.write_vnull($($args)*) };
+    (@call $a:ident, vnxor, $($args:tt)*) => { $a.write_vnxor($($args)*) };
+    (@call $a:ident, vor, $($args:tt)*) => { $a.write_vor($($args)*) };
+    (@call $a:ident, vrndn, $($args:tt)*) => { $a.write_vrndn($($args)*) };
+    (@call $a:ident, vrndp, $($args:tt)*) => { $a.write_vrndp($($args)*) };
+    (@call $a:ident, vsac, $($args:tt)*) => { $a.write_vsac($($args)*) };
+    (@call $a:ident, vsad, $($args:tt)*) => { $a.write_vsad($($args)*) };
+    (@call $a:ident, vsub, $($args:tt)*) => { $a.write_vsub($($args)*) };
+    (@call $a:ident, vsubb, $($args:tt)*) => { $a.write_vsubb($($args)*) };
+    (@call $a:ident, vsubc, $($args:tt)*) => { $a.write_vsubc($($args)*) };
+    (@call $a:ident, vsucb, $($args:tt)*) => { $a.write_vsucb($($args)*) };
+    (@call $a:ident, vsum, $($args:tt)*) => { $a.write_vsum($($args)*) };
+    (@call $a:ident, vsut, $($args:tt)*) => { $a.write_vsut($($args)*) };
+    (@call $a:ident, vxor, $($args:tt)*) => { $a.write_vxor($($args)*) };
+}
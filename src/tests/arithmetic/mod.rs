@@ -1,3 +1,4 @@
 pub mod nemu_port;
 pub mod shifts;
 pub mod div;
+pub mod randomized;
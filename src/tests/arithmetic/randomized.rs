@@ -0,0 +1,337 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::arch::asm;
+use arbitrary_int::u5;
+use oorandom::Rand64;
+
+use crate::assembler::{Assembler, SpecialOpcode};
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2};
+
+// The tests in here perform a lot of randomized multiplications and divisions and hash HI and LO.
+// The same inputs are fed through a reference implementation (written in plain Rust) and the two hashes
+// are compared. To make debugging easier, the first mismatch is reported as well.
+//
+// Expectations:
+// - MULT/MULTU/DIV/DIVU only look at the lower 32 bit of their inputs (so inputs that aren't sign-extended
+//   are fine). HI and LO are the sign-extended upper and lower 32 bits of the result
+// - Division by zero: LO is -1 (for DIVU/DDIVU or positive dividends) or 1 (negative dividend for DIV/DDIV).
+//   HI is the (sign-extended) dividend
+// - MIN / -1: LO is MIN, HI is 0
+// - HI and LO are always fully overwritten, no matter what was in there before
+
+/// Values that are likely to cause trouble. These are picked a lot more often than random values would be
+const SPECIAL_VALUES: [u64; 8] = [
+    0,
+    1,
+    0xFFFFFFFF_FFFFFFFF,
+    0xFFFFFFFF_80000000,
+    0x00000000_80000000,
+    0x80000000_00000000,
+    0x7FFFFFFF_FFFFFFFF,
+    0x00000000_7FFFFFFF,
+];
+
+/// Returns a random register value. This is either fully random (which in most cases isn't a
+/// properly sign-extended 32 bit value), a properly sign-extended 32 bit value, a special value or a
+/// small value
+fn random_operand(random: &mut Rand64) -> u64 {
+    let r = random.rand_u64();
+    match r & 3 {
+        0 => random.rand_u64(),
+        1 => random.rand_u64() as i32 as i64 as u64,
+        2 => SPECIAL_VALUES[((r >> 2) & 7) as usize],
+        _ => random.rand_u64() as i8 as i64 as u64,
+    }
+}
+
+fn randomized_test<const INSTRUCTION: u32>(iterations: u32, reference: fn(u64, u64) -> (u64, u64)) -> Result<(), String> {
+    let mut random = Rand64::new(0);
+    let mut hash = 0u64;
+    let mut expected_hash = 0u64;
+    let mut first_mismatch: Option<(u64, u64, [u64; 2], (u64, u64))> = None;
+
+    for _ in 0..iterations {
+        // rs, rt and garbage to preset HI and LO with
+        let inputs: [u64; 4] = [random_operand(&mut random), random_operand(&mut random), random.rand_u64(), random.rand_u64()];
+        let mut lo_hi = [0u64; 2];
+        unsafe {
+            asm!("
+                .set noat
+                .set noreorder
+
+                LD $2, 0($4)
+                LD $3, 8($4)
+                LD $5, 16($4)
+                LD $6, 24($4)
+                MTHI $5
+                MTLO $6
+                .word {INSTRUCTION}
+                MFLO $2
+                MFHI $3
+                SD $2, 0($7)
+                SD $3, 8($7)
+            ", INSTRUCTION = const INSTRUCTION,
+                out("$2") _, out("$3") _, in("$4") &inputs, out("$5") _, out("$6") _, in("$7") &mut lo_hi)
+        }
+
+        let expected = reference(inputs[0], inputs[1]);
+        if first_mismatch.is_none() && (lo_hi[0], lo_hi[1]) != expected {
+            first_mismatch = Some((inputs[0], inputs[1], lo_hi, expected));
+        }
+
+        hash = hash.wrapping_mul(397) ^ lo_hi[0];
+        hash = hash.wrapping_mul(397) ^ lo_hi[1];
+        expected_hash = expected_hash.wrapping_mul(397) ^ expected.0;
+        expected_hash = expected_hash.wrapping_mul(397) ^ expected.1;
+    }
+
+    soft_assert_eq2(hash, expected_hash, || {
+        let (rs, rt, lo_hi, expected) = first_mismatch.unwrap();
+        format!("Hash. First mismatch: rs=0x{:016x}, rt=0x{:016x} resulted in LO=0x{:016x}, HI=0x{:016x}. Expected LO=0x{:016x}, HI=0x{:016x}", rs, rt, lo_hi[0], lo_hi[1], expected.0, expected.1)
+    })?;
+
+    Ok(())
+}
+
+const fn sign_extend_32(value: u32) -> u64 { value as i32 as i64 as u64 }
+
+fn reference_mult(rs: u64, rt: u64) -> (u64, u64) {
+    let result = (rs as i32 as i64).wrapping_mul(rt as i32 as i64) as u64;
+    (sign_extend_32(result as u32), sign_extend_32((result >> 32) as u32))
+}
+
+fn reference_multu(rs: u64, rt: u64) -> (u64, u64) {
+    let result = (rs as u32 as u64) * (rt as u32 as u64);
+    (sign_extend_32(result as u32), sign_extend_32((result >> 32) as u32))
+}
+
+fn reference_dmult(rs: u64, rt: u64) -> (u64, u64) {
+    let result = (rs as i64 as i128).wrapping_mul(rt as i64 as i128) as u128;
+    (result as u64, (result >> 64) as u64)
+}
+
+fn reference_dmultu(rs: u64, rt: u64) -> (u64, u64) {
+    let result = (rs as u128) * (rt as u128);
+    (result as u64, (result >> 64) as u64)
+}
+
+fn reference_div(rs: u64, rt: u64) -> (u64, u64) {
+    let dividend = rs as i32;
+    let divisor = rt as i32;
+    if divisor == 0 {
+        (if dividend < 0 { 1 } else { u64::MAX }, dividend as i64 as u64)
+    } else {
+        (sign_extend_32(dividend.wrapping_div(divisor) as u32), sign_extend_32(dividend.wrapping_rem(divisor) as u32))
+    }
+}
+
+fn reference_divu(rs: u64, rt: u64) -> (u64, u64) {
+    let dividend = rs as u32;
+    let divisor = rt as u32;
+    if divisor == 0 {
+        (u64::MAX, sign_extend_32(dividend))
+    } else {
+        (sign_extend_32(dividend / divisor), sign_extend_32(dividend % divisor))
+    }
+}
+
+fn reference_ddiv(rs: u64, rt: u64) -> (u64, u64) {
+    let dividend = rs as i64;
+    let divisor = rt as i64;
+    if divisor == 0 {
+        (if dividend < 0 { 1 } else { u64::MAX }, dividend as u64)
+    } else {
+        (dividend.wrapping_div(divisor) as u64, dividend.wrapping_rem(divisor) as u64)
+    }
+}
+
+fn reference_ddivu(rs: u64, rt: u64) -> (u64, u64) {
+    if rt == 0 {
+        (u64::MAX, rs)
+    } else {
+        (rs / rt, rs % rt)
+    }
+}
+
+const fn make_instruction(op: SpecialOpcode) -> u32 {
+    Assembler::make_special(op, u5::new(0), u5::new(0), u5::new(2), u5::new(3))
+}
+
+pub struct MULT;
+
+impl Test for MULT {
+    fn name(&self) -> &str { "MULT (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const INSTRUCTION: u32 = make_instruction(SpecialOpcode::MULT);
+        randomized_test::<INSTRUCTION>(10000, reference_mult)
+    }
+}
+
+pub struct MULTU;
+
+impl Test for MULTU {
+    fn name(&self) -> &str { "MULTU (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const INSTRUCTION: u32 = make_instruction(SpecialOpcode::MULTU);
+        randomized_test::<INSTRUCTION>(10000, reference_multu)
+    }
+}
+
+pub struct DMULT;
+
+impl Test for DMULT {
+    fn name(&self) -> &str { "DMULT (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const INSTRUCTION: u32 = make_instruction(SpecialOpcode::DMULT);
+        randomized_test::<INSTRUCTION>(10000, reference_dmult)
+    }
+}
+
+pub struct DMULTU;
+
+impl Test for DMULTU {
+    fn name(&self) -> &str { "DMULTU (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const INSTRUCTION: u32 = make_instruction(SpecialOpcode::DMULTU);
+        randomized_test::<INSTRUCTION>(10000, reference_dmultu)
+    }
+}
+
+pub struct DIV;
+
+impl Test for DIV {
+    fn name(&self) -> &str { "DIV (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const INSTRUCTION: u32 = make_instruction(SpecialOpcode::DIV);
+        randomized_test::<INSTRUCTION>(10000, reference_div)
+    }
+}
+
+pub struct DIVU;
+
+impl Test for DIVU {
+    fn name(&self) -> &str { "DIVU (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const INSTRUCTION: u32 = make_instruction(SpecialOpcode::DIVU);
+        randomized_test::<INSTRUCTION>(10000, reference_divu)
+    }
+}
+
+pub struct DDIV;
+
+impl Test for DDIV {
+    fn name(&self) -> &str { "DDIV (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const INSTRUCTION: u32 = make_instruction(SpecialOpcode::DDIV);
+        randomized_test::<INSTRUCTION>(10000, reference_ddiv)
+    }
+}
+
+pub struct DDIVU;
+
+impl Test for DDIVU {
+    fn name(&self) -> &str { "DDIVU (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const INSTRUCTION: u32 = make_instruction(SpecialOpcode::DDIVU);
+        randomized_test::<INSTRUCTION>(10000, reference_ddivu)
+    }
+}
+
+/// MTHI/MTLO write the full 64 bit and only touch their own register
+pub struct HILOMoves;
+
+impl Test for HILOMoves {
+    fn name(&self) -> &str { "MTHI/MTLO/MFHI/MFLO" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let inputs: [u64; 3] = [0x01234567_89ABCDEF, 0xFEDCBA98_76543210, 0x11223344_55667788];
+        let mut results = [0u64; 6];
+        unsafe {
+            asm!("
+                .set noat
+                .set noreorder
+
+                LD $2, 0($4)
+                LD $3, 8($4)
+                LD $5, 16($4)
+                MTHI $2
+                MTLO $3
+                MFHI $6
+                SD $6, 0($7)
+                MFLO $6
+                SD $6, 8($7)
+
+                // Overwrite LO only, HI has to stay
+                MTLO $5
+                MFHI $6
+                SD $6, 16($7)
+                MFLO $6
+                SD $6, 24($7)
+
+                // Overwrite HI only, LO has to stay
+                MTHI $3
+                MFHI $6
+                SD $6, 32($7)
+                MFLO $6
+                SD $6, 40($7)
+            ", out("$2") _, out("$3") _, in("$4") &inputs, out("$5") _, out("$6") _, in("$7") &mut results)
+        }
+
+        soft_assert_eq(results[0], 0x01234567_89ABCDEF, "HI after MTHI")?;
+        soft_assert_eq(results[1], 0xFEDCBA98_76543210, "LO after MTLO")?;
+        soft_assert_eq(results[2], 0x01234567_89ABCDEF, "HI after MTLO")?;
+        soft_assert_eq(results[3], 0x11223344_55667788, "LO after MTLO")?;
+        soft_assert_eq(results[4], 0xFEDCBA98_76543210, "HI after MTHI")?;
+        soft_assert_eq(results[5], 0x11223344_55667788, "LO after MTHI")?;
+
+        Ok(())
+    }
+}
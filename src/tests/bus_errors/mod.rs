@@ -0,0 +1,114 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::arch::asm;
+
+use crate::cop0::{Cause, CauseException};
+use crate::exception_handler::expect_exception;
+use crate::MemoryMap;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+
+// Accessing physical addresses that aren't backed by anything:
+// - Unused RDRAM space (beyond the installed memory): Reads return 0, writes are ignored
+// - PI space that isn't backed by the cartridge (e.g. behind the end of the ROM): Reads return PI open
+//   bus, which is the lower 16 bit of the address for each halfword (so reading 0x1E001230 returns
+//   0x12301232). Flashcarts might map something here, so keep to addresses that they don't commonly use.
+//   Writes to PI space are not ignored (see cart_memory::write), so they aren't tested here
+// - Unmapped RCP space (0x04900000 and up, gaps between register blocks): Not well understood and not yet
+//   observed on hardware. DataBusError guesses that these raise a bus error (DBE). It stays
+//   PoorlyUnderstoodQuirk (and isn't part of a regular run) until the actual behavior has been recorded.
+// Instruction fetches are covered by instruction_fetch.
+
+/// Name, physical address, expected value
+const OPEN_BUS_READS: [(&str, usize, u32); 3] = [
+    ("RDRAM beyond installed memory", 0x0200_0000, 0),
+    ("RDRAM beyond installed memory (2)", 0x03E0_1234, 0),
+    ("Cartridge domain 1 behind ROM", 0x1E00_1230, 0x1230_1232),
+];
+
+/// Name, physical address
+const BUS_ERROR_ADDRESSES: [(&str, usize); 3] = [
+    ("Unmapped RCP space", 0x0490_0000),
+    ("Unmapped RCP space (end)", 0x04FF_FFF0),
+    ("Behind SI registers", 0x0480_0020),
+];
+
+pub struct OpenBusReads {}
+
+impl Test for OpenBusReads {
+    fn name(&self) -> &str { "Bus: Reads from unused physical addresses" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        (0..OPEN_BUS_READS.len() as u32).map(|i| -> Box<dyn Any> { Box::new(i) }).collect()
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, physical, expected) = OPEN_BUS_READS[*(*value).downcast_ref::<u32>().unwrap() as usize];
+        let address = MemoryMap::physical_to_uncached_mut::<u32>(physical);
+
+        soft_assert_eq2(unsafe { address.read_volatile() }, expected, || format!("{}: Value read from 0x{:08X}", name, physical))?;
+
+        // Writes to RDRAM space are expected to be ignored
+        if physical < MemoryMap::PHYSICAL_SPMEM_BASE {
+            unsafe { address.write_volatile(0x5555_AAAA); }
+            soft_assert_eq2(unsafe { address.read_volatile() }, expected, || format!("{}: Value read from 0x{:08X} after writing", name, physical))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn bus_error_values() -> Vec<Box<dyn Any>> {
+    let mut result: Vec<Box<dyn Any>> = Vec::new();
+    for i in 0..BUS_ERROR_ADDRESSES.len() {
+        result.push(Box::new((false, i as u32)));
+        result.push(Box::new((true, i as u32)));
+    }
+    result
+}
+
+pub struct DataBusError {}
+
+impl Test for DataBusError {
+    fn name(&self) -> &str { "Bus: DBE on unmapped RCP space (unconfirmed)" }
+
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { bus_error_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (store, index) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+        let (name, physical) = BUS_ERROR_ADDRESSES[index as usize];
+        let address = MemoryMap::physical_to_uncached_mut::<u32>(physical);
+
+        let exception_context = expect_exception(CauseException::DBE, 1, || {
+            unsafe {
+                if store {
+                    asm!("
+                        .set noat
+                        .set noreorder
+                        SW $0, 0($2)
+                    ", in("$2") address)
+                } else {
+                    asm!("
+                        .set noat
+                        .set noreorder
+                        LW $0, 0($2)
+                    ", in("$2") address)
+                }
+            }
+            Ok(())
+        }).map_err(|error| format!("{} (0x{:08X}): {}", name, physical, error))?;
+
+        soft_assert_eq2(exception_context.k0_exception_vector, 0xFFFFFFFF_80000180, || format!("{}: Exception Vector", name))?;
+        soft_assert_eq2(exception_context.cause, Cause::new().with_exception(CauseException::DBE), || format!("{}: Cause", name))?;
+        soft_assert_eq2(exception_context.status, 0x24000002, || format!("{}: Status", name))?;
+
+        Ok(())
+    }
+}
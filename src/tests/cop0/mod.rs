@@ -480,6 +480,46 @@ impl Test for StatusIs32Bit {
     }
 }
 
+/// Tests if read/write masking is correct for the COP0 WatchLo register. Bit 2 is reserved.
+pub struct WatchLoMasking;
+
+impl Test for WatchLoMasking {
+    fn name(&self) -> &str { "WatchLo (masking)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        for value in [0xFFFFFFFF, 0x00000004, 0x12345678, 0x00000000] {
+            unsafe { cop0::set_watchlo(value); }
+            let readback = cop0::watchlo();
+            soft_assert_eq(readback, value & 0xFFFFFFFB, format!("WatchLo was written as 0x{:x}", value).as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// Tests if read/write masking is correct for the COP0 WatchHi register. Only the lower 4 bits exist.
+pub struct WatchHiMasking;
+
+impl Test for WatchHiMasking {
+    fn name(&self) -> &str { "WatchHi (masking)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        for value in [0xFFFFFFFF, 0x00000010, 0x12345678, 0x00000000] {
+            unsafe { cop0::set_watchhi(value); }
+            let readback = cop0::watchhi();
+            soft_assert_eq(readback, value & 0xF, format!("WatchHi was written as 0x{:x}", value).as_str())?;
+        }
+        Ok(())
+    }
+}
+
 /// Tests if read/write masking is correct for the COP0 Status register.
 pub struct StatusMasking;
 
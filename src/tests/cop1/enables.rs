@@ -0,0 +1,182 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use arbitrary_int::u2;
+
+use crate::assembler::{Assembler, FR};
+use crate::cop0::{Cause, CauseException, preset_cause_to_copindex2};
+use crate::cop1::{fcsr, FCSR, FCSRFlags, FCSRRoundingMode, set_fcsr};
+use crate::exception_handler::expect_exception;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+
+use super::{asm_block_f32, asm_block_f64, COP1_RESULT_NAN_32, COP1_RESULT_NAN_64, TARGET_REG_DEFAULT_F32, TARGET_REG_DEFAULT_F64};
+
+// The tests in mod.rs run every operation with either all or only the expected exceptions enabled. The tests
+// in here go through all 32 combinations of the enable bits for one operation per exception type:
+// - An FPE is fired if any of the cause bits of an operation is enabled. The cause bits contain all
+//   causes (not just the enabled ones), the flag bits aren't touched and the target register isn't written
+// - Underflow is special: If underflow or inexact is enabled, unimplemented operation is fired instead
+// - Without an exception, causes are copied into the flags. Flags are sticky and are never cleared by
+//   an operation
+
+/// Returns the bits of the result of the given single precision instruction
+fn single<const INSTRUCTION: u32>(value1: u64, value2: u64) -> u64 {
+    asm_block_f32::<0, INSTRUCTION>(f32::from_bits(value1 as u32), f32::from_bits(value2 as u32)).to_bits() as u64
+}
+
+/// Returns the bits of the result of the given double precision instruction
+fn double<const INSTRUCTION: u32>(value1: u64, value2: u64) -> u64 {
+    asm_block_f64::<0, INSTRUCTION>(f64::from_bits(value1), f64::from_bits(value2)).to_bits()
+}
+
+const ADD_S: u32 = Assembler::make_add(FR::F4, FR::F0, FR::F2).s();
+const MUL_S: u32 = Assembler::make_mul(FR::F4, FR::F0, FR::F2).s();
+const DIV_S: u32 = Assembler::make_div(FR::F4, FR::F0, FR::F2).s();
+const SQRT_S: u32 = Assembler::make_sqrt(FR::F4, FR::F0).s();
+const ADD_D: u32 = Assembler::make_add(FR::F4, FR::F0, FR::F2).d();
+const MUL_D: u32 = Assembler::make_mul(FR::F4, FR::F0, FR::F2).d();
+const DIV_D: u32 = Assembler::make_div(FR::F4, FR::F0, FR::F2).d();
+const SQRT_D: u32 = Assembler::make_sqrt(FR::F4, FR::F0).d();
+
+const NONE: FCSRFlags = FCSRFlags::new();
+const INEXACT: FCSRFlags = FCSRFlags::new().with_inexact_operation(true);
+const UNDERFLOW_INEXACT: FCSRFlags = FCSRFlags::new().with_underflow(true).with_inexact_operation(true);
+const OVERFLOW_INEXACT: FCSRFlags = FCSRFlags::new().with_overflow(true).with_inexact_operation(true);
+const DIVISION_BY_ZERO: FCSRFlags = FCSRFlags::new().with_division_by_zero(true);
+const INVALID: FCSRFlags = FCSRFlags::new().with_invalid_operation(true);
+
+const S_DEFAULT: u64 = TARGET_REG_DEFAULT_F32.to_bits() as u64;
+const D_DEFAULT: u64 = TARGET_REG_DEFAULT_F64.to_bits();
+
+const fn s(value: f32) -> u64 { value.to_bits() as u64 }
+const fn d(value: f64) -> u64 { value.to_bits() }
+
+/// Name, operation, instruction, value1, value2, expected flags, expected result (without exception), target register before the operation.
+/// All operations run with flush-denorm-to-zero and rounding to nearest
+const OPERATIONS: [(&str, fn(u64, u64) -> u64, u32, u64, u64, FCSRFlags, u64, u64); 12] = [
+    ("ADD.S exact", single::<ADD_S>, ADD_S, s(1f32), s(2f32), NONE, s(3f32), S_DEFAULT),
+    ("ADD.S inexact", single::<ADD_S>, ADD_S, s(1f32), s(0.00000001f32), INEXACT, s(1f32), S_DEFAULT),
+    ("MUL.S overflow", single::<MUL_S>, MUL_S, s(f32::MAX), s(2f32), OVERFLOW_INEXACT, s(f32::INFINITY), S_DEFAULT),
+    ("DIV.S underflow", single::<DIV_S>, DIV_S, s(f32::MIN_POSITIVE), s(2f32), UNDERFLOW_INEXACT, s(0f32), S_DEFAULT),
+    ("DIV.S division by zero", single::<DIV_S>, DIV_S, s(2f32), s(0f32), DIVISION_BY_ZERO, s(f32::INFINITY), S_DEFAULT),
+    ("SQRT.S invalid", single::<SQRT_S>, SQRT_S, s(-1f32), s(0f32), INVALID, s(COP1_RESULT_NAN_32), S_DEFAULT),
+    ("ADD.D exact", double::<ADD_D>, ADD_D, d(1f64), d(2f64), NONE, d(3f64), D_DEFAULT),
+    ("ADD.D inexact", double::<ADD_D>, ADD_D, d(1f64), d(0.0000000000000001f64), INEXACT, d(1f64), D_DEFAULT),
+    ("MUL.D overflow", double::<MUL_D>, MUL_D, d(f64::MAX), d(2f64), OVERFLOW_INEXACT, d(f64::INFINITY), D_DEFAULT),
+    ("DIV.D underflow", double::<DIV_D>, DIV_D, d(f64::MIN_POSITIVE), d(2f64), UNDERFLOW_INEXACT, d(0f64), D_DEFAULT),
+    ("DIV.D division by zero", double::<DIV_D>, DIV_D, d(2f64), d(0f64), DIVISION_BY_ZERO, d(f64::INFINITY), D_DEFAULT),
+    ("SQRT.D invalid", double::<SQRT_D>, SQRT_D, d(-1f64), d(0f64), INVALID, d(COP1_RESULT_NAN_64), D_DEFAULT),
+];
+
+/// Returns the cause bits of the exception that is expected for the given enables (or None if no exception is expected)
+fn expected_exception(flags: FCSRFlags, enables: FCSRFlags) -> Option<FCSR> {
+    if (flags.raw_value() & enables.raw_value()) == 0 {
+        None
+    } else if flags == UNDERFLOW_INEXACT {
+        Some(FCSR::new().with_cause_unimplemented_operation(true))
+    } else {
+        Some(FCSR::new().with_maskable_causes(flags))
+    }
+}
+
+pub struct ExceptionEnableMatrix;
+
+impl Test for ExceptionEnableMatrix {
+    fn name(&self) -> &str { "COP1: Exception enable matrix" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        let mut result: Vec<Box<dyn Any>> = Vec::new();
+        for operation in 0..OPERATIONS.len() as u32 {
+            for enables in 0..32u32 {
+                result.push(Box::new((operation, enables)));
+            }
+        }
+        result
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (operation, enables) = *(*value).downcast_ref::<(u32, u32)>().unwrap();
+        let (name, perform, instruction, value1, value2, flags, expected_result, default) = OPERATIONS[operation as usize];
+        let enables = FCSRFlags::new_with_raw_value(enables as u8);
+        let fcsr_before = FCSR::new().with_flush_denorm_to_zero(true).with_rounding_mode(FCSRRoundingMode::Nearest).with_enables(enables);
+
+        match expected_exception(flags, enables) {
+            None => {
+                set_fcsr(fcsr_before);
+                let result = perform(value1, value2);
+                let result_fcsr = fcsr();
+                set_fcsr(FCSR::new());
+
+                soft_assert_eq2(result, expected_result, || format!("{} with enables {:?}: Result", name, enables))?;
+                soft_assert_eq2(result_fcsr, fcsr_before.with_flags(flags).with_maskable_causes(flags), || format!("{} with enables {:?}: FCSR", name, enables))?;
+            }
+            Some(expected_causes) => {
+                preset_cause_to_copindex2()?;
+
+                let mut result = 0;
+                let exception_context = expect_exception(CauseException::FPE, 1, || {
+                    set_fcsr(fcsr_before);
+                    result = perform(value1, value2);
+                    set_fcsr(FCSR::new());
+                    Ok(())
+                }).map_err(|error| format!("{} with enables {:?}: {}", name, enables, error))?;
+
+                soft_assert_eq2(exception_context.k0_exception_vector, 0xFFFFFFFF_80000180, || format!("{} with enables {:?}: Exception Vector", name, enables))?;
+                soft_assert_eq2(unsafe { *(exception_context.exceptpc as *const u32) }, instruction, || format!("{} with enables {:?}: ExceptPC points to wrong instruction", name, enables))?;
+                soft_assert_eq2(exception_context.cause, Cause::new().with_coprocessor_error(u2::new(0)).with_exception(CauseException::FPE), || format!("{} with enables {:?}: Cause", name, enables))?;
+                soft_assert_eq2(exception_context.status, 0x24000002, || format!("{} with enables {:?}: Status", name, enables))?;
+                // The cause bits are set, but neither the flags nor the target register are written
+                soft_assert_eq2(exception_context.fcsr, fcsr_before.with_maskable_causes(expected_causes.maskable_causes()).with_cause_unimplemented_operation(expected_causes.cause_unimplemented_operation()), || format!("{} with enables {:?}: FCSR", name, enables))?;
+                soft_assert_eq2(result, default, || format!("{} with enables {:?}: Target register is expected to be unchanged", name, enables))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct StickyFlags;
+
+impl Test for StickyFlags {
+    fn name(&self) -> &str { "COP1: Sticky flags" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let fcsr_before = FCSR::new().with_flush_denorm_to_zero(true).with_rounding_mode(FCSRRoundingMode::Nearest);
+
+        // Flags accumulate while the causes only reflect the last operation. An exact operation doesn't clear any flags
+        set_fcsr(fcsr_before);
+        single::<ADD_S>(s(1f32), s(0.00000001f32));
+        let after_inexact = fcsr();
+        single::<DIV_S>(s(2f32), s(0f32));
+        let after_division_by_zero = fcsr();
+        single::<ADD_S>(s(1f32), s(2f32));
+        let after_exact = fcsr();
+        set_fcsr(FCSR::new());
+
+        soft_assert_eq2(after_inexact, fcsr_before.with_flags(INEXACT).with_maskable_causes(INEXACT), || String::from("FCSR after inexact operation"))?;
+        soft_assert_eq2(after_division_by_zero, fcsr_before.with_flags(INEXACT | DIVISION_BY_ZERO).with_maskable_causes(DIVISION_BY_ZERO), || String::from("FCSR after division by zero"))?;
+        soft_assert_eq2(after_exact, fcsr_before.with_flags(INEXACT | DIVISION_BY_ZERO), || String::from("FCSR after exact operation"))?;
+
+        // Flags that were set before are kept when an exception fires, but the new cause isn't added to them
+        let fcsr_before_exception = fcsr_before.with_flags(INEXACT).with_enable_division_by_zero(true);
+        let exception_context = expect_exception(CauseException::FPE, 1, || {
+            set_fcsr(fcsr_before_exception);
+            single::<DIV_S>(s(2f32), s(0f32));
+            set_fcsr(FCSR::new());
+            Ok(())
+        })?;
+        soft_assert_eq2(exception_context.fcsr, fcsr_before_exception.with_maskable_causes(DIVISION_BY_ZERO), || String::from("FCSR after division by zero with exception enabled"))?;
+
+        Ok(())
+    }
+}
@@ -7,16 +7,18 @@ use core::arch::asm;
 use core::mem::transmute;
 use arbitrary_int::u2;
 use oorandom::{Rand32, Rand64};
-use crate::assembler::{Assembler, FR};
+use crate::assembler::{Assembler, Cop1Condition, FR};
 use crate::cop0::{CauseException};
-use crate::cop1::{FCSR, fcsr, FCSRRoundingMode, set_fcsr};
+use crate::cop1::{FCSR, fcsr, FCSRFlags, FCSRRoundingMode, set_fcsr};
 use crate::exception_handler::expect_exception;
 use crate::graphics::color::{Color, RGBA5551};
 use crate::graphics::cursor::Cursor;
 use crate::graphics::font::Font;
 use crate::graphics::system_font::FONT_GENEVA_9;
+use crate::math::fpu;
+use crate::math::fpu::{Format, FPUResult};
 use crate::tests::{Level, Test};
-use crate::tests::soft_asserts::soft_assert_eq;
+use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2};
 use crate::VIDEO;
 
 // The tests in here perform a lot of randomized calculations and hash the output/exception flags
@@ -319,6 +321,303 @@ impl Test for CvtWFromS {
     }
 }
 
+// The tests below cover the remaining conversions as well as ABS/NEG/MOV and all compares. Instead of
+// random bits, the inputs are biased towards values that the FPU can't handle by itself (denormals,
+// NaNs, infinities and integers that are out of range), as those raise the Unimplemented Operation
+// cause bit. Instead of a hash, every result is compared against the reference model in math::fpu.
+
+/// Returns the bits of a random value of the given format, biased towards special values
+fn biased_random(random: &mut Rand64, format: Format) -> u64 {
+    fn float(random: &mut Rand64, exponent_bits: u32, mantissa_bits: u32) -> u64 {
+        let bias = (1u64 << (exponent_bits - 1)) - 1;
+        let max_exponent = (1u64 << exponent_bits) - 1;
+        let sign = random.rand_u64() & 1;
+        let mut mantissa = random.rand_u64() & ((1u64 << mantissa_bits) - 1);
+        let exponent = match random.rand_range(0..10) {
+            // Denormal
+            0 => 0,
+            // Zero
+            1 => {
+                mantissa = 0;
+                0
+            }
+            // Infinity
+            2 => {
+                mantissa = 0;
+                max_exponent
+            }
+            // NaN (any payload)
+            3 => {
+                mantissa |= 1;
+                max_exponent
+            }
+            // Close to the limits of a 32 bit integer
+            4 => bias + random.rand_range(29..33),
+            // Close to the limits of a 64 bit integer (and of what CVT.L can handle)
+            5 => bias + random.rand_range(52..65),
+            // Smallest normals, which easily turn into denormals
+            6 => random.rand_range(1..3),
+            // Anything
+            _ => random.rand_u64() & max_exponent,
+        };
+        (sign << (exponent_bits + mantissa_bits)) | (exponent << mantissa_bits) | mantissa
+    }
+
+    fn integer(random: &mut Rand64, bits: u32) -> u64 {
+        let small = random.rand_range(0..512).wrapping_sub(256);
+        let value = match random.rand_range(0..6) {
+            // Small numbers
+            0 => small,
+            // Close to the precision limit of a single
+            1 => (1u64 << 24).wrapping_add(small),
+            // Close to the precision limit of a double
+            2 => (1u64 << 53).wrapping_add(small),
+            // Close to the range that CVT.S.L and CVT.D.L can handle
+            3 => (1u64 << 55).wrapping_add(small),
+            // Close to the limits
+            4 => (1u64 << (bits - 1)).wrapping_add(small),
+            _ => random.rand_u64(),
+        };
+        let negate = (random.rand_u64() & 1) != 0;
+        let value = if negate { value.wrapping_neg() } else { value };
+        if bits == 32 { value as u32 as u64 } else { value }
+    }
+
+    match format {
+        Format::S => float(random, 8, 23),
+        Format::D => float(random, 11, 52),
+        Format::W => integer(random, 32),
+        Format::L => integer(random, 64),
+    }
+}
+
+/// Runs the given unary instruction with $f0 as input and returns $f4. For 32 bit results, only the
+/// lower 32 bit are returned
+fn unary<const FINSTRUCTION: u32, const OUTPUT64: bool>(input: u64) -> u64 {
+    let output: f64;
+    unsafe {
+        asm!("
+            .set noat
+            .set noreorder
+            .word {FINSTRUCTION}
+            nop
+        ",
+        FINSTRUCTION = const FINSTRUCTION,
+        in("$f0") f64::from_bits(input),
+        out("$f4") output,
+        options(nostack, nomem));
+    }
+    if OUTPUT64 { output.to_bits() } else { output.to_bits() & 0xFFFF_FFFF }
+}
+
+/// Runs the given compare with $f0 and $f2 as inputs. The result is in FCSR, which is part of the hash
+fn compare<const FINSTRUCTION: u32>(input1: u64, input2: u64) -> u64 {
+    unsafe {
+        asm!("
+            .set noat
+            .set noreorder
+            .word {FINSTRUCTION}
+            nop
+        ",
+        FINSTRUCTION = const FINSTRUCTION,
+        in("$f0") f64::from_bits(input1),
+        in("$f2") f64::from_bits(input2),
+        options(nostack, nomem));
+    }
+    0
+}
+
+/// Runs the operation 2000 times, toggling flush-denorm-to-zero and the rounding mode like randomized_test does.
+/// The result and FCSR of every iteration are compared against the reference, which returns the expected
+/// flags, result and condition bit (or Err(()) if Unimplemented Operation is expected to fire)
+fn biased_test<FINPUTS: FnMut() -> (u64, u64), FPERFORM: Fn(u64, u64) -> u64, FREFERENCE: Fn(u64, u64, FCSRRoundingMode, bool) -> Result<(FCSRFlags, u64, bool), ()>>(name: &str, mut inputs: FINPUTS, perform: FPERFORM, reference: FREFERENCE) -> Result<(), String> {
+    for i in 0..2000 {
+        let (input1, input2) = inputs();
+        let ftz = (i & 1) != 0;
+        let rounding_mode = FCSRRoundingMode::new_with_raw_value(u2::extract_u32(i, 1));
+        let fcsr_before = FCSR::new().with_flush_denorm_to_zero(ftz).with_rounding_mode(rounding_mode);
+
+        let mut result = 0u64;
+        let mut result_fcsr = FCSR::new();
+        set_fcsr(fcsr_before);
+        let maybe_exception = expect_exception(CauseException::FPE, 1, || {
+            result = perform(input1, input2);
+            result_fcsr = fcsr();
+
+            Ok(())
+        });
+
+        let context = || format!("{} of 0x{:x}, 0x{:x} with rounding mode {:?} and flush-denorm-to-zero {}", name, input1, input2, rounding_mode, ftz);
+        match (maybe_exception, reference(input1, input2, rounding_mode, ftz)) {
+            (Ok(exception), Err(())) => {
+                soft_assert_eq2(exception.fcsr, fcsr_before.with_cause_unimplemented_operation(true), || format!("FCSR after {}", context()))?;
+            }
+            (Err(_), Ok((flags, expected, condition))) => {
+                soft_assert_eq2(result, expected, || format!("Result of {}", context()))?;
+                soft_assert_eq2(result_fcsr, fcsr_before.with_condition(condition).with_flags(flags).with_maskable_causes(flags), || format!("FCSR after {}", context()))?;
+            }
+            (Ok(exception), Ok(_)) => return Err(format!("{}: Unexpected exception. FCSR: {:?}", context(), exception.fcsr)),
+            (Err(_), Err(())) => return Err(format!("{}: Expected Unimplemented Operation, but got 0x{:x}. FCSR: {:?}", context(), result, result_fcsr)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Name, input format, output format, fixed rounding mode (None for CVT, which uses the one in FCSR), operation
+const CONVERSIONS: [(&str, Format, Format, Option<FCSRRoundingMode>, fn(u64) -> u64); 26] = [
+    ("CVT.D.S", Format::S, Format::D, None, unary::<{ Assembler::make_cvt_d(FR::F4, FR::F0).s() }, true>),
+    ("CVT.D.W", Format::W, Format::D, None, unary::<{ Assembler::make_cvt_d(FR::F4, FR::F0).w() }, true>),
+    ("CVT.D.L", Format::L, Format::D, None, unary::<{ Assembler::make_cvt_d(FR::F4, FR::F0).l() }, true>),
+    ("CVT.S.D", Format::D, Format::S, None, unary::<{ Assembler::make_cvt_s(FR::F4, FR::F0).d() }, false>),
+    ("CVT.S.W", Format::W, Format::S, None, unary::<{ Assembler::make_cvt_s(FR::F4, FR::F0).w() }, false>),
+    ("CVT.S.L", Format::L, Format::S, None, unary::<{ Assembler::make_cvt_s(FR::F4, FR::F0).l() }, false>),
+    ("CVT.W.S", Format::S, Format::W, None, unary::<{ Assembler::make_cvt_w(FR::F4, FR::F0).s() }, false>),
+    ("CVT.W.D", Format::D, Format::W, None, unary::<{ Assembler::make_cvt_w(FR::F4, FR::F0).d() }, false>),
+    ("CVT.L.S", Format::S, Format::L, None, unary::<{ Assembler::make_cvt_l(FR::F4, FR::F0).s() }, true>),
+    ("CVT.L.D", Format::D, Format::L, None, unary::<{ Assembler::make_cvt_l(FR::F4, FR::F0).d() }, true>),
+    ("ROUND.W.S", Format::S, Format::W, Some(FCSRRoundingMode::Nearest), unary::<{ Assembler::make_round_w(FR::F4, FR::F0).s() }, false>),
+    ("ROUND.W.D", Format::D, Format::W, Some(FCSRRoundingMode::Nearest), unary::<{ Assembler::make_round_w(FR::F4, FR::F0).d() }, false>),
+    ("ROUND.L.S", Format::S, Format::L, Some(FCSRRoundingMode::Nearest), unary::<{ Assembler::make_round_l(FR::F4, FR::F0).s() }, true>),
+    ("ROUND.L.D", Format::D, Format::L, Some(FCSRRoundingMode::Nearest), unary::<{ Assembler::make_round_l(FR::F4, FR::F0).d() }, true>),
+    ("TRUNC.W.S", Format::S, Format::W, Some(FCSRRoundingMode::Zero), unary::<{ Assembler::make_trunc_w(FR::F4, FR::F0).s() }, false>),
+    ("TRUNC.W.D", Format::D, Format::W, Some(FCSRRoundingMode::Zero), unary::<{ Assembler::make_trunc_w(FR::F4, FR::F0).d() }, false>),
+    ("TRUNC.L.S", Format::S, Format::L, Some(FCSRRoundingMode::Zero), unary::<{ Assembler::make_trunc_l(FR::F4, FR::F0).s() }, true>),
+    ("TRUNC.L.D", Format::D, Format::L, Some(FCSRRoundingMode::Zero), unary::<{ Assembler::make_trunc_l(FR::F4, FR::F0).d() }, true>),
+    ("FLOOR.W.S", Format::S, Format::W, Some(FCSRRoundingMode::NegativeInfinity), unary::<{ Assembler::make_floor_w(FR::F4, FR::F0).s() }, false>),
+    ("FLOOR.W.D", Format::D, Format::W, Some(FCSRRoundingMode::NegativeInfinity), unary::<{ Assembler::make_floor_w(FR::F4, FR::F0).d() }, false>),
+    ("FLOOR.L.S", Format::S, Format::L, Some(FCSRRoundingMode::NegativeInfinity), unary::<{ Assembler::make_floor_l(FR::F4, FR::F0).s() }, true>),
+    ("FLOOR.L.D", Format::D, Format::L, Some(FCSRRoundingMode::NegativeInfinity), unary::<{ Assembler::make_floor_l(FR::F4, FR::F0).d() }, true>),
+    ("CEIL.W.S", Format::S, Format::W, Some(FCSRRoundingMode::PositiveInfinity), unary::<{ Assembler::make_ceil_w(FR::F4, FR::F0).s() }, false>),
+    ("CEIL.W.D", Format::D, Format::W, Some(FCSRRoundingMode::PositiveInfinity), unary::<{ Assembler::make_ceil_w(FR::F4, FR::F0).d() }, false>),
+    ("CEIL.L.S", Format::S, Format::L, Some(FCSRRoundingMode::PositiveInfinity), unary::<{ Assembler::make_ceil_l(FR::F4, FR::F0).s() }, true>),
+    ("CEIL.L.D", Format::D, Format::L, Some(FCSRRoundingMode::PositiveInfinity), unary::<{ Assembler::make_ceil_l(FR::F4, FR::F0).d() }, true>),
+];
+
+/// Name, input format, operation, reference
+const ABS_NEG_MOV: [(&str, Format, fn(u64) -> u64, fn(Format, u64) -> FPUResult); 6] = [
+    ("ABS.S", Format::S, unary::<{ Assembler::make_abs(FR::F4, FR::F0).s() }, false>, fpu::abs),
+    ("ABS.D", Format::D, unary::<{ Assembler::make_abs(FR::F4, FR::F0).d() }, true>, fpu::abs),
+    ("NEG.S", Format::S, unary::<{ Assembler::make_neg(FR::F4, FR::F0).s() }, false>, fpu::neg),
+    ("NEG.D", Format::D, unary::<{ Assembler::make_neg(FR::F4, FR::F0).d() }, true>, fpu::neg),
+    ("MOV.S", Format::S, unary::<{ Assembler::make_mov(FR::F4, FR::F0).s() }, false>, fpu::mov),
+    ("MOV.D", Format::D, unary::<{ Assembler::make_mov(FR::F4, FR::F0).d() }, true>, fpu::mov),
+];
+
+/// Name, input format, condition, operation
+const COMPARES: [(&str, Format, u8, fn(u64, u64) -> u64); 32] = [
+    ("C.F.S", Format::S, Cop1Condition::F as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::F, FR::F0, FR::F2).s() }>),
+    ("C.F.D", Format::D, Cop1Condition::F as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::F, FR::F0, FR::F2).d() }>),
+    ("C.UN.S", Format::S, Cop1Condition::UN as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::UN, FR::F0, FR::F2).s() }>),
+    ("C.UN.D", Format::D, Cop1Condition::UN as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::UN, FR::F0, FR::F2).d() }>),
+    ("C.EQ.S", Format::S, Cop1Condition::EQ as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::EQ, FR::F0, FR::F2).s() }>),
+    ("C.EQ.D", Format::D, Cop1Condition::EQ as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::EQ, FR::F0, FR::F2).d() }>),
+    ("C.UEQ.S", Format::S, Cop1Condition::UEQ as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::UEQ, FR::F0, FR::F2).s() }>),
+    ("C.UEQ.D", Format::D, Cop1Condition::UEQ as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::UEQ, FR::F0, FR::F2).d() }>),
+    ("C.OLT.S", Format::S, Cop1Condition::OLT as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::OLT, FR::F0, FR::F2).s() }>),
+    ("C.OLT.D", Format::D, Cop1Condition::OLT as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::OLT, FR::F0, FR::F2).d() }>),
+    ("C.ULT.S", Format::S, Cop1Condition::ULT as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::ULT, FR::F0, FR::F2).s() }>),
+    ("C.ULT.D", Format::D, Cop1Condition::ULT as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::ULT, FR::F0, FR::F2).d() }>),
+    ("C.OLE.S", Format::S, Cop1Condition::OLE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::OLE, FR::F0, FR::F2).s() }>),
+    ("C.OLE.D", Format::D, Cop1Condition::OLE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::OLE, FR::F0, FR::F2).d() }>),
+    ("C.ULE.S", Format::S, Cop1Condition::ULE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::ULE, FR::F0, FR::F2).s() }>),
+    ("C.ULE.D", Format::D, Cop1Condition::ULE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::ULE, FR::F0, FR::F2).d() }>),
+    ("C.SF.S", Format::S, Cop1Condition::SF as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::SF, FR::F0, FR::F2).s() }>),
+    ("C.SF.D", Format::D, Cop1Condition::SF as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::SF, FR::F0, FR::F2).d() }>),
+    ("C.NGLE.S", Format::S, Cop1Condition::NGLE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::NGLE, FR::F0, FR::F2).s() }>),
+    ("C.NGLE.D", Format::D, Cop1Condition::NGLE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::NGLE, FR::F0, FR::F2).d() }>),
+    ("C.SEQ.S", Format::S, Cop1Condition::SEQ as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::SEQ, FR::F0, FR::F2).s() }>),
+    ("C.SEQ.D", Format::D, Cop1Condition::SEQ as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::SEQ, FR::F0, FR::F2).d() }>),
+    ("C.NGL.S", Format::S, Cop1Condition::NGL as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::NGL, FR::F0, FR::F2).s() }>),
+    ("C.NGL.D", Format::D, Cop1Condition::NGL as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::NGL, FR::F0, FR::F2).d() }>),
+    ("C.LT.S", Format::S, Cop1Condition::LT as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::LT, FR::F0, FR::F2).s() }>),
+    ("C.LT.D", Format::D, Cop1Condition::LT as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::LT, FR::F0, FR::F2).d() }>),
+    ("C.NGE.S", Format::S, Cop1Condition::NGE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::NGE, FR::F0, FR::F2).s() }>),
+    ("C.NGE.D", Format::D, Cop1Condition::NGE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::NGE, FR::F0, FR::F2).d() }>),
+    ("C.LE.S", Format::S, Cop1Condition::LE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::LE, FR::F0, FR::F2).s() }>),
+    ("C.LE.D", Format::D, Cop1Condition::LE as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::LE, FR::F0, FR::F2).d() }>),
+    ("C.NGT.S", Format::S, Cop1Condition::NGT as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::NGT, FR::F0, FR::F2).s() }>),
+    ("C.NGT.D", Format::D, Cop1Condition::NGT as u8, compare::<{ Assembler::make_c_cond(Cop1Condition::NGT, FR::F0, FR::F2).d() }>),
+];
+
+fn index_values(count: usize) -> Vec<Box<dyn Any>> {
+    (0..count as u32).map(|i| -> Box<dyn Any> { Box::new(i) }).collect()
+}
+
+pub struct BiasedConversions;
+
+impl Test for BiasedConversions {
+    fn name(&self) -> &str { "CVT/ROUND/TRUNC/FLOOR/CEIL (randomized with special values)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { index_values(CONVERSIONS.len()) }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, input, output, fixed_rounding_mode, perform) = CONVERSIONS[*(*value).downcast_ref::<u32>().unwrap() as usize];
+        let mut random = Rand64::new(0);
+        biased_test(
+            name,
+            || (biased_random(&mut random, input), 0),
+            |input1, _| perform(input1),
+            |input1, _, rounding_mode, ftz| {
+                let (flags, result) = fpu::convert(input, output, input1, fixed_rounding_mode.unwrap_or(rounding_mode), ftz)?;
+                Ok((flags, result, false))
+            })
+    }
+}
+
+pub struct BiasedAbsNegMov;
+
+impl Test for BiasedAbsNegMov {
+    fn name(&self) -> &str { "ABS/NEG/MOV (randomized with special values)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { index_values(ABS_NEG_MOV.len()) }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, format, perform, reference) = ABS_NEG_MOV[*(*value).downcast_ref::<u32>().unwrap() as usize];
+        let mut random = Rand64::new(0);
+        biased_test(
+            name,
+            || (biased_random(&mut random, format), 0),
+            |input1, _| perform(input1),
+            |input1, _, _, _| {
+                let (flags, result) = reference(format, input1)?;
+                Ok((flags, result, false))
+            })
+    }
+}
+
+pub struct BiasedCompares;
+
+impl Test for BiasedCompares {
+    fn name(&self) -> &str { "C.cond (randomized with special values)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { index_values(COMPARES.len()) }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, format, condition, perform) = COMPARES[*(*value).downcast_ref::<u32>().unwrap() as usize];
+        let mut random = Rand64::new(0);
+        biased_test(
+            name,
+            || {
+                let input1 = biased_random(&mut random, format);
+                // Equal inputs are rare otherwise
+                let input2 = if random.rand_range(0..4) == 0 { input1 } else { biased_random(&mut random, format) };
+                (input1, input2)
+            },
+            perform,
+            |input1, input2, _, _| {
+                let (flags, result) = fpu::compare(format, condition, input1, input2);
+                Ok((flags, 0, result))
+            })
+    }
+}
+
 pub struct StresstestAddS;
 
 impl Test for StresstestAddS {
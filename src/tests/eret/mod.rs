@@ -0,0 +1,217 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::arch::asm;
+use arbitrary_int::u2;
+
+use crate::cop0::{Cause, CauseException, preset_cause_to_copindex2, RegisterIndex, Status, StatusKSU};
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq;
+use crate::user_mode;
+
+// ERET:
+// - If Status.ERL is set, it returns to ErrorEPC and clears ERL (EXL stays untouched)
+// - Otherwise it returns to EPC and clears EXL. This also happens if neither bit is set
+// - ERET doesn't have a delay slot
+// - ERET clears the LLbit, so that a following SC fails
+// - Outside of kernel mode, ERET needs COP0 to be usable
+
+const ERET: u32 = 0x4200_0018;
+
+/// Sets up EPC and Status (with EXL or ERL set) and executes ERET. The instructions right after ERET
+/// would increment $2, which is expected to never happen. Returns ($2, Status after ERET)
+fn eret_with_status(status: Status) -> (u32, u32) {
+    let skipped: u32;
+    let status_after_eret: u32;
+    unsafe {
+        asm!("
+            .set noat
+            .set noreorder
+            la $2, 2f
+            dmtc0 $2, ${ExceptPC}
+            la $2, 3f
+            dmtc0 $2, ${ErrorEPC}
+            ori $2, $0, 0
+            mtc0 {status}, ${Status}
+            nop
+            nop
+            eret
+            addiu $2, $2, 1
+            addiu $2, $2, 1
+            addiu $2, $2, 1
+            addiu $2, $2, 1
+
+            // ERET is supposed to either go to 2 (EPC) or to 3 (ErrorEPC). Write 0x100 or 0x200 into $2 so we know
+        2:
+            b 4f
+            addiu $2, $2, 0x100
+        3:
+            addiu $2, $2, 0x200
+        4:
+            mfc0 $3, ${Status}
+            mtc0 {default_status}, ${Status}
+            nop
+            nop
+        ", status = in(reg) status.raw_value(),
+            default_status = in(reg) Status::DEFAULT.raw_value(),
+            ExceptPC = const RegisterIndex::ExceptPC as u32,
+            ErrorEPC = const RegisterIndex::ErrorEPC as u32,
+            Status = const RegisterIndex::Status as u32,
+            out("$2") skipped, out("$3") status_after_eret)
+    }
+
+    (skipped, status_after_eret)
+}
+
+pub struct EretWithEXL {}
+
+impl Test for EretWithEXL {
+    fn name(&self) -> &str { "ERET (EXL)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let (target, status) = eret_with_status(Status::DEFAULT.with_exl(true));
+
+        soft_assert_eq(target, 0x100, "ERET is expected to return to EPC")?;
+        soft_assert_eq(status, Status::DEFAULT.raw_value(), "Status after ERET (EXL is expected to be cleared)")?;
+
+        Ok(())
+    }
+}
+
+pub struct EretWithERL {}
+
+impl Test for EretWithERL {
+    fn name(&self) -> &str { "ERET (ERL)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { vec! { Box::new(false), Box::new(true) } }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let exl = *(*value).downcast_ref::<bool>().unwrap();
+        let (target, status) = eret_with_status(Status::DEFAULT.with_erl(true).with_exl(exl));
+
+        soft_assert_eq(target, 0x200, "ERET is expected to return to ErrorEPC")?;
+        soft_assert_eq(status, Status::DEFAULT.with_exl(exl).raw_value(), "Status after ERET (ERL is expected to be cleared, EXL is expected to be unchanged)")?;
+
+        Ok(())
+    }
+}
+
+pub struct EretWithoutEXLOrERL {}
+
+impl Test for EretWithoutEXLOrERL {
+    fn name(&self) -> &str { "ERET (neither EXL nor ERL)" }
+
+    // The manual doesn't say what happens here and this hasn't been confirmed on hardware yet. The expectation
+    // below assumes that ERET returns to EPC and leaves Status as it is
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let (target, status) = eret_with_status(Status::DEFAULT);
+
+        soft_assert_eq(target, 0x100, "ERET is expected to return to EPC")?;
+        soft_assert_eq(status, Status::DEFAULT.raw_value(), "Status after ERET")?;
+
+        Ok(())
+    }
+}
+
+pub struct EretNoDelaySlot {}
+
+impl Test for EretNoDelaySlot {
+    fn name(&self) -> &str { "ERET (no delay slot)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // Same as above, but look at the lower bits which count the instructions that were executed after ERET
+        let (target, _) = eret_with_status(Status::DEFAULT.with_exl(true));
+
+        soft_assert_eq(target & 0xFF, 0, "Instructions after ERET were executed, but ERET has no delay slot")?;
+
+        Ok(())
+    }
+}
+
+pub struct EretClearsLLBit {}
+
+impl Test for EretClearsLLBit {
+    fn name(&self) -> &str { "ERET (clears LLbit)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let mut memory = 0x1234_5678u32;
+        let sc_without_eret: u32;
+        let sc_after_eret: u32;
+        unsafe {
+            asm!("
+                .set noat
+                .set noreorder
+                // Sanity check: LL followed by SC succeeds
+                ll $2, 0({memory})
+                ori $2, $0, 0x5555
+                sc $2, 0({memory})
+                or $4, $2, $0
+
+                la $2, 2f
+                dmtc0 $2, ${ExceptPC}
+                mtc0 {status}, ${Status}
+                nop
+                nop
+                ll $2, 0({memory})
+                eret
+            2:
+                ori $3, $0, 0x6666
+                sc $3, 0({memory})
+            ", memory = in(reg) &mut memory,
+                status = in(reg) Status::DEFAULT.with_exl(true).raw_value(),
+                ExceptPC = const RegisterIndex::ExceptPC as u32,
+                Status = const RegisterIndex::Status as u32,
+                out("$2") _, out("$3") sc_after_eret, out("$4") sc_without_eret)
+        }
+
+        soft_assert_eq(sc_without_eret, 1, "SC after LL (without ERET) is expected to succeed")?;
+        soft_assert_eq(sc_after_eret, 0, "SC after LL and ERET is expected to fail")?;
+        soft_assert_eq(memory, 0x5555, "Memory after failed SC")?;
+
+        Ok(())
+    }
+}
+
+pub struct EretInUserModeWithoutCOP0 {}
+
+impl Test for EretInUserModeWithoutCOP0 {
+    fn name(&self) -> &str { "ERET (user mode, COP0 unusable)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        preset_cause_to_copindex2()?;
+
+        let status = Status::DEFAULT.with_ksu(StatusKSU::User);
+        let exception_context = user_mode::run_in_mode(status, &[ERET], [0; 4], CauseException::CopUnusable)?;
+
+        soft_assert_eq(exception_context.k0_exception_vector, 0xFFFFFFFF_80000180, "Exception Vector")?;
+        soft_assert_eq(exception_context.exceptpc, user_mode::CODE_VIRTUAL_ADDRESS as u64, "ExceptPC")?;
+        soft_assert_eq(exception_context.cause, Cause::new().with_exception(CauseException::CopUnusable).with_coprocessor_error(u2::new(0)), "Cause")?;
+        soft_assert_eq(exception_context.status, status.with_exl(true).raw_value(), "Status")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,368 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use arbitrary_int::{u2, u27, u5};
+
+use crate::assembler::{Assembler, GPR, Opcode, SpecialOpcode};
+use crate::cop0;
+use crate::cop0::{Cause, CauseException, make_entry_hi, make_entry_lo, Status, StatusKSU};
+use crate::exception_handler::{expect_exception, expect_exception_returning_to_kernel, ExceptionContext};
+use crate::MemoryMap;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+use crate::tests::tlb::exceptions::setup_tlb_page;
+use crate::tests::uncached_code;
+use crate::uncached_memory::UncachedHeapMemory;
+use crate::user_mode;
+
+// When more than one exception condition applies to a single instruction, only the one with the highest
+// priority is taken. From highest to lowest (leaving out reset, NMI and cache errors):
+// - Address error (instruction fetch)
+// - TLB refill/invalid (instruction fetch)
+// - Bus error (instruction fetch)
+// - System call, Breakpoint, Coprocessor unusable, Reserved instruction, Trap, Overflow, FPU exception
+// - Address error (data)
+// - TLB refill/invalid (data)
+// - TLB modification
+// - Watch
+// - Bus error (data)
+// - Interrupt
+// In short: Everything that can be decided by looking at the instruction word wins over everything that
+// depends on the address, and an interrupt only wins if nothing else applies.
+//
+// Each row sets up two (or more) conditions and runs a single instruction, either by itself or in the delay
+// slot of a BEQ. $4 is the base address that comes from the setup, $5 is an additional input.
+//
+// To make an interrupt pending on a specific instruction, a software interrupt (IP0 or IP1) is raised
+// through Cause while interrupts are still disabled through Status.EXL. The code is then entered through
+// ERET, which makes the interrupt pending on the very first instruction.
+
+#[derive(Copy, Clone)]
+enum Mode {
+    Kernel,
+    KernelWithoutCOP1,
+    /// Kernel mode with 64 bit addressing
+    Kernel64,
+    /// User mode with 32 bit addressing (so 64 bit instructions are reserved)
+    User,
+}
+
+#[derive(Copy, Clone)]
+enum Setup {
+    /// $4 is 0
+    None,
+
+    /// $4 points to a kuseg address that isn't mapped
+    Unmapped,
+
+    /// $4 points to an uncached doubleword (kseg1) which is also being watched (loads and stores)
+    WatchedKseg1,
+
+    /// $4 points to a TLB mapped page which isn't dirty. Its physical address is also being watched
+    ReadOnlyWatched,
+
+    /// $4 points to a TLB mapped page which isn't valid. Its physical address is also being watched
+    InvalidWatched,
+
+    /// $4 points to a kuseg address that isn't mapped. The same address is also being watched (a physical
+    /// address doesn't exist without a TLB entry, but an implementation might compare the virtual address)
+    UnmappedWatched,
+
+    /// $4 points to unmapped RCP space (which raises a bus error, see bus_errors) through kseg1. The
+    /// address is also being watched
+    WatchedBusError,
+}
+
+const fn special(op: SpecialOpcode, rd: u8, rs: u8, rt: u8) -> u32 {
+    Assembler::make_special(op, u5::new(0), u5::new(rd), u5::new(rs), u5::new(rt))
+}
+
+const UNMAPPED_ADDRESS: u64 = 0x0020_1230;
+
+/// Unmapped RCP space, see bus_errors
+const BUS_ERROR_ADDRESS: usize = 0x0490_0000;
+
+/// Name, mode, setup, instruction, $5, expected exception
+const DATA_ACCESSES: [(&str, Mode, Setup, u32, u64, CauseException); 16] = [
+    ("LW: Address error over TLB miss", Mode::Kernel, Setup::Unmapped, Assembler::make_main_immediate(Opcode::LW, GPR::A1, GPR::A0, 1), 0, CauseException::AdEL),
+    ("SW: Address error over TLB miss", Mode::Kernel, Setup::Unmapped, Assembler::make_main_immediate(Opcode::SW, GPR::R0, GPR::A0, 2), 0, CauseException::AdES),
+    ("LW: Address error over Watch", Mode::Kernel, Setup::WatchedKseg1, Assembler::make_main_immediate(Opcode::LW, GPR::A1, GPR::A0, 2), 0, CauseException::AdEL),
+    ("SH: Address error over Watch", Mode::Kernel, Setup::WatchedKseg1, Assembler::make_main_immediate(Opcode::SH, GPR::R0, GPR::A0, 1), 0, CauseException::AdES),
+    ("LW: TLB invalid over Watch", Mode::Kernel, Setup::InvalidWatched, Assembler::make_main_immediate(Opcode::LW, GPR::A1, GPR::A0, 0), 0, CauseException::TLBL),
+    ("SW: TLB miss over Watch", Mode::Kernel, Setup::UnmappedWatched, Assembler::make_main_immediate(Opcode::SW, GPR::R0, GPR::A0, 0), 0, CauseException::TLBS),
+    ("SW: Mod over Watch", Mode::Kernel, Setup::ReadOnlyWatched, Assembler::make_main_immediate(Opcode::SW, GPR::R0, GPR::A0, 0), 0, CauseException::Mod),
+    ("LW: Watch over bus error", Mode::Kernel, Setup::WatchedBusError, Assembler::make_main_immediate(Opcode::LW, GPR::A1, GPR::A0, 0), 0, CauseException::Watch),
+    ("SW: Watch over bus error", Mode::Kernel, Setup::WatchedBusError, Assembler::make_main_immediate(Opcode::SW, GPR::R0, GPR::A0, 0), 0, CauseException::Watch),
+    ("LWC1: CopUnusable over address error", Mode::KernelWithoutCOP1, Setup::WatchedKseg1, Assembler::make_main_immediate(Opcode::LWC1, GPR::R0, GPR::A0, 1), 0, CauseException::CopUnusable),
+    ("SWC1: CopUnusable over TLB miss", Mode::KernelWithoutCOP1, Setup::Unmapped, Assembler::make_main_immediate(Opcode::SWC1, GPR::R0, GPR::A0, 0), 0, CauseException::CopUnusable),
+    ("LD: Reserved instruction over address error", Mode::User, Setup::Unmapped, Assembler::make_main_immediate(Opcode::LD, GPR::A1, GPR::A0, 1), 0, CauseException::RI),
+    ("SD: Reserved instruction over TLB miss", Mode::User, Setup::Unmapped, Assembler::make_main_immediate(Opcode::SD, GPR::R0, GPR::A0, 0), 0, CauseException::RI),
+    ("DADD: Reserved instruction over overflow", Mode::User, Setup::None, special(SpecialOpcode::DADD, 2, 5, 5), 0x7FFFFFFF_FFFFFFFF, CauseException::RI),
+    ("DADDI: Reserved instruction over overflow", Mode::User, Setup::None, Assembler::make_main_immediate(Opcode::DADDI, GPR::V0, GPR::A1, 1), 0x7FFFFFFF_FFFFFFFF, CauseException::RI),
+    ("COP1 (reserved format): CopUnusable over reserved instruction", Mode::KernelWithoutCOP1, Setup::None, ((Opcode::COP1 as u32) << 26) | (9 << 21), 0, CauseException::CopUnusable),
+];
+
+const SYSCALL: u32 = 0x0000_000C;
+
+/// Name, mode, setup, software interrupt (0 or 1), instruction, $5, expected exception
+const INTERRUPTS: [(&str, Mode, Setup, u8, u32, u64, CauseException); 8] = [
+    ("NOP: Interrupt (IP0)", Mode::Kernel, Setup::None, 0, NOP, 0, CauseException::Int),
+    ("NOP: Interrupt (IP1)", Mode::User, Setup::None, 1, NOP, 0, CauseException::Int),
+    ("SYSCALL: System call over interrupt", Mode::Kernel, Setup::None, 0, SYSCALL, 0, CauseException::Sys),
+    ("ADD: Overflow over interrupt", Mode::Kernel, Setup::None, 1, special(SpecialOpcode::ADD, 2, 5, 5), 0x7FFF_FFFF, CauseException::Ov),
+    ("LD: Reserved instruction over interrupt", Mode::User, Setup::None, 0, Assembler::make_main_immediate(Opcode::LD, GPR::A1, GPR::A0, 0), 0, CauseException::RI),
+    ("LW: Address error over interrupt", Mode::Kernel, Setup::Unmapped, 1, Assembler::make_main_immediate(Opcode::LW, GPR::A1, GPR::A0, 1), 0, CauseException::AdEL),
+    ("SW: TLB miss over interrupt", Mode::User, Setup::Unmapped, 0, Assembler::make_main_immediate(Opcode::SW, GPR::R0, GPR::A0, 0), 0, CauseException::TLBS),
+    ("LW: Watch over interrupt", Mode::Kernel, Setup::WatchedKseg1, 1, Assembler::make_main_immediate(Opcode::LW, GPR::A1, GPR::A0, 0), 0, CauseException::Watch),
+];
+
+/// Name, mode, setup, offset added to $4, expected exception
+const FETCHES: [(&str, Mode, Setup, u64, CauseException); 2] = [
+    ("Fetch: Address error over TLB miss", Mode::Kernel, Setup::Unmapped, 2, CauseException::AdEL),
+    ("Fetch: TLB miss", Mode::Kernel, Setup::Unmapped, 0, CauseException::TLBL),
+];
+
+/// Name, mode, first address after the mapped page that holds the branch, expected exception.
+/// The branch is the last word of the mapped page, so its delay slot is fetched from the given address
+const FETCHES_IN_DELAY_SLOT: [(&str, Mode, u64, CauseException); 2] = [
+    ("Fetch (delay slot): Address error past the end of xkuseg", Mode::Kernel64, 0x0000_0100_0000_0000, CauseException::AdEL),
+    ("Fetch (delay slot): TLB miss", Mode::Kernel, 0x0020_2000, CauseException::TLBL),
+];
+
+const NOP: u32 = 0;
+
+fn status_for_mode(mode: Mode) -> Status {
+    match mode {
+        Mode::Kernel => Status::DEFAULT,
+        Mode::KernelWithoutCOP1 => Status::DEFAULT.with_cop1usable(false),
+        Mode::Kernel64 => Status::ADDRESSING_MODE_64_BIT,
+        Mode::User => Status::DEFAULT.with_ksu(StatusKSU::User),
+    }
+}
+
+/// Applies the setup and returns $4 as well as memory that needs to be kept alive until the test is done
+fn apply_setup(setup: Setup) -> (u64, Option<UncachedHeapMemory<u64>>) {
+    unsafe { cop0::clear_tlb(); }
+    match setup {
+        Setup::None => (0, None),
+        Setup::Unmapped => (UNMAPPED_ADDRESS, None),
+        Setup::WatchedKseg1 => {
+            let mut memory = UncachedHeapMemory::<u64>::new(1);
+            let physical = memory.start_phyiscal();
+            unsafe {
+                cop0::set_watchhi(0);
+                cop0::set_watchlo((physical as u32 & 0xFFFFFFF8) | 0b11);
+            }
+            (MemoryMap::physical_to_uncached_mut::<u64>(physical) as u32 as i32 as u64, Some(memory))
+        }
+        Setup::ReadOnlyWatched => {
+            // setup_tlb_page maps its page to HEAP_END
+            let virtual_page_base = setup_tlb_page(0, true, false);
+            unsafe {
+                cop0::set_watchhi(0);
+                cop0::set_watchlo((MemoryMap::HEAP_END as u32 & 0xFFFFFFF8) | 0b11);
+            }
+            (virtual_page_base as u64, None)
+        }
+        Setup::InvalidWatched => {
+            let virtual_page_base = setup_tlb_page(0, false, false);
+            unsafe {
+                cop0::set_watchhi(0);
+                cop0::set_watchlo((MemoryMap::HEAP_END as u32 & 0xFFFFFFF8) | 0b11);
+            }
+            (virtual_page_base as u64, None)
+        }
+        Setup::UnmappedWatched => {
+            unsafe {
+                cop0::set_watchhi(0);
+                cop0::set_watchlo((UNMAPPED_ADDRESS as u32 & 0xFFFFFFF8) | 0b11);
+            }
+            (UNMAPPED_ADDRESS, None)
+        }
+        Setup::WatchedBusError => {
+            unsafe {
+                cop0::set_watchhi(0);
+                cop0::set_watchlo((BUS_ERROR_ADDRESS as u32 & 0xFFFFFFF8) | 0b11);
+            }
+            (MemoryMap::physical_to_uncached_mut::<u32>(BUS_ERROR_ADDRESS) as u32 as i32 as u64, None)
+        }
+    }
+}
+
+/// Maps a page of code in front of first_unmapped (which has to be 8k aligned). The page is the odd half
+/// of its TLB entry, so the address after it needs an entry of its own. Returns the code as well as the
+/// virtual address of the last word of the page
+fn map_code_in_front_of(first_unmapped: u64) -> (UncachedHeapMemory<u32>, u64) {
+    assert_eq!(first_unmapped & 0x1FFF, 0);
+    let mut code = UncachedHeapMemory::<u32>::new_with_align(1024, 4096);
+    unsafe {
+        cop0::clear_tlb();
+        cop0::write_tlb(
+            0,
+            0,
+            make_entry_lo(true, false, false, 0, 0),
+            make_entry_lo(true, true, true, 2, (code.start_phyiscal() >> 12) as u32),
+            make_entry_hi(0, u27::extract_u64(first_unmapped - 0x2000, 13), u2::new(0)));
+    }
+    (code, first_unmapped - 4)
+}
+
+fn undo_setup() {
+    unsafe {
+        cop0::set_watchlo(0);
+        cop0::clear_tlb();
+    }
+}
+
+fn run_data_access(value: &Box<dyn Any>) -> Result<(), String> {
+    let (delay, index) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+    let (name, mode, setup, instruction, a1, expected) = DATA_ACCESSES[index as usize];
+    let status = status_for_mode(mode);
+
+    let (a0, _memory) = apply_setup(setup);
+
+    let result: Result<(ExceptionContext, u64), String> = match mode {
+        Mode::User => {
+            let instructions = [Assembler::make_beq(GPR::R0, GPR::R0, 1), instruction];
+            user_mode::run_in_mode(status, if delay { &instructions } else { &instructions[1..] }, [a0, a1, 0, 0], expected)
+                .map(|exception_context| (exception_context, user_mode::CODE_VIRTUAL_ADDRESS as u64))
+        }
+        Mode::Kernel | Mode::KernelWithoutCOP1 | Mode::Kernel64 => {
+            let mut code = uncached_code::write_instruction(instruction, delay);
+            let code_address = uncached_code::address(&mut code);
+            expect_exception(expected, if delay { 2 } else { 1 }, || {
+                uncached_code::call_with_status(status, code_address, [a0, a1]);
+                Ok(())
+            }).map(|exception_context| (exception_context, code_address))
+        }
+    };
+
+    undo_setup();
+
+    let (exception_context, code_address) = result.map_err(|error| format!("{}: {}", name, error))?;
+
+    soft_assert_eq2(exception_context.exceptpc, code_address, || format!("{}: ExceptPC", name))?;
+    soft_assert_eq2(exception_context.cause.branch_delay(), delay, || format!("{}: Cause.BD", name))?;
+    soft_assert_eq2(exception_context.status, status.with_exl(true).raw_value(), || format!("{}: Status", name))?;
+
+    Ok(())
+}
+
+fn delay_values(count: usize) -> Vec<Box<dyn Any>> {
+    let mut result: Vec<Box<dyn Any>> = Vec::new();
+    for i in 0..count {
+        result.push(Box::new((false, i as u32)));
+        result.push(Box::new((true, i as u32)));
+    }
+    result
+}
+
+pub struct DataAccessPriority {}
+
+impl Test for DataAccessPriority {
+    fn name(&self) -> &str { "Exception priority (loads, stores and arithmetic)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { delay_values(DATA_ACCESSES.len()) }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { run_data_access(value) }
+}
+
+/// Sets up the given software interrupt through Cause and returns the Status that enables it
+fn raise_software_interrupt(status: Status, interrupt: u8) -> Status {
+    unsafe { cop0::set_cause(Cause::new().with_interrupt_sw1(interrupt == 0).with_interrupt_sw2(interrupt == 1)); }
+    // The interrupt mask bits are read-only in the Status bitfield, so set them through the raw value
+    Status::new_with_raw_value(status.raw_value() | (1 << (8 + interrupt))).with_ie(true)
+}
+
+pub struct InterruptPriority {}
+
+impl Test for InterruptPriority {
+    fn name(&self) -> &str { "Exception priority (interrupts)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        (0..INTERRUPTS.len() as u32).map(|i| -> Box<dyn Any> { Box::new(i) }).collect()
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        // run_in_mode enters the code through ERET with EXL=1, so the interrupt becomes pending exactly on the
+        // first instruction. The exception handler clears IE when returning, so it isn't taken again afterwards
+        let (name, mode, setup, interrupt, instruction, a1, expected) = INTERRUPTS[*(*value).downcast_ref::<u32>().unwrap() as usize];
+
+        let (a0, _memory) = apply_setup(setup);
+        let status = raise_software_interrupt(status_for_mode(mode), interrupt);
+
+        let result = user_mode::run_in_mode(status, &[instruction], [a0, a1, 0, 0], expected);
+
+        unsafe { cop0::set_cause(Cause::new()); }
+        undo_setup();
+
+        let exception_context = result.map_err(|error| format!("{}: {}", name, error))?;
+
+        soft_assert_eq2(exception_context.exceptpc, user_mode::CODE_VIRTUAL_ADDRESS as u64, || format!("{}: ExceptPC", name))?;
+        soft_assert_eq2(exception_context.cause.branch_delay(), false, || format!("{}: Cause.BD", name))?;
+        soft_assert_eq2(exception_context.cause.interrupt_sw1(), interrupt == 0, || format!("{}: Cause.IP0", name))?;
+        soft_assert_eq2(exception_context.cause.interrupt_sw2(), interrupt == 1, || format!("{}: Cause.IP1", name))?;
+        soft_assert_eq2(exception_context.status, status.with_exl(true).raw_value(), || format!("{}: Status", name))?;
+
+        Ok(())
+    }
+}
+
+pub struct FetchPriority {}
+
+impl Test for FetchPriority {
+    fn name(&self) -> &str { "Exception priority (instruction fetch)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        let mut result: Vec<Box<dyn Any>> = Vec::new();
+        for i in 0..FETCHES.len() {
+            result.push(Box::new((false, i as u32)));
+        }
+        for i in 0..FETCHES_IN_DELAY_SLOT.len() {
+            result.push(Box::new((true, i as u32)));
+        }
+        result
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        // Either the jump target itself is bad (so the exception happens on the target), or the jump target is a
+        // branch at the end of a mapped page whose delay slot is bad.
+        // The exception handler returns to RA (which was set by the JALR)
+        let (delay, index) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+        let (name, status, target, bad_address, expected, _code) = if delay {
+            let (name, mode, first_unmapped, expected) = FETCHES_IN_DELAY_SLOT[index as usize];
+            let (mut code, target) = map_code_in_front_of(first_unmapped);
+            code.write(code.count() - 1, Assembler::make_beq(GPR::R0, GPR::R0, 1));
+            (name, status_for_mode(mode), target, first_unmapped, expected, Some(code))
+        } else {
+            let (name, mode, setup, offset, expected) = FETCHES[index as usize];
+            // None of the fetch setups need memory
+            let (a0, _) = apply_setup(setup);
+            (name, status_for_mode(mode), a0 + offset, a0 + offset, expected, None)
+        };
+
+        let result = expect_exception_returning_to_kernel(expected, || {
+            uncached_code::call_with_status(status, target, [0, 0]);
+            Ok(())
+        });
+
+        undo_setup();
+
+        let exception_context = result.map_err(|error| format!("{}: {}", name, error))?;
+
+        soft_assert_eq2(exception_context.exceptpc, target, || format!("{}: ExceptPC", name))?;
+        soft_assert_eq2(exception_context.badvaddr, bad_address, || format!("{}: BadVAddr", name))?;
+        soft_assert_eq2(exception_context.cause, Cause::new().with_exception(expected).with_branch_delay(delay), || format!("{}: Cause", name))?;
+        soft_assert_eq2(exception_context.status, status.with_exl(true).raw_value(), || format!("{}: Status", name))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::arch::asm;
+
+use crate::assembler::{Assembler, GPR};
+use crate::cop0::{Cause, CauseException};
+use crate::exception_handler::{expect_exception, expect_exception_returning_to_kernel};
+use crate::MemoryMap;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq;
+
+// All other tests execute from RDRAM. The CPU can also execute from other parts of the address space,
+// as long as it goes through KSEG1 (uncached):
+// - SP DMEM/IMEM: Works. Some homebrew does this
+// - Cartridge ROM: Works (the bootcode does this). Only 32 bit reads are possible, which is what
+//   an uncached fetch does
+// - PIF RAM: Expected to work, but slow. This hasn't been confirmed on hardware yet, so that test is
+//   PoorlyUnderstoodQuirk. PIF RAM is also used to talk to the PIF, so its previous contents are restored
+// - PIF ROM (after boot) and unmapped RCP space: Not well understood. These are expected to raise a
+//   bus error (IBE). If they don't, the CPU executes whatever the bus returns. To avoid that, the fetch
+//   is only attempted if a data read from the same address raises a bus error (DBE). These tests are
+//   PoorlyUnderstoodQuirk as well
+
+/// Sets $2 to 0x1235 and returns
+const ROUTINE: [u32; 3] = [
+    Assembler::make_addiu(GPR::V0, GPR::R0, 0x1234),
+    Assembler::make_jr(GPR::RA),
+    Assembler::make_addiu(GPR::V0, GPR::V0, 1),
+];
+
+/// Same as above, but in rodata, so that it can also be found in ROM
+static ROUTINE_IN_ROM: [u32; 3] = ROUTINE;
+
+const EXPECTED_RESULT: u32 = 0x1235;
+
+/// Calls the routine at the given address and returns $2
+fn call(address: usize) -> u32 {
+    let result: u32;
+    unsafe {
+        asm!("
+            .set noat
+            .set noreorder
+            ADDIU $2, $0, 0
+            DADDIU $24, $31, 0  // Stash RA in $24
+            JALR $25
+            NOP
+            DADDIU $31, $24, 0  // Restore original RA
+        ", in("$25") address, out("$2") result, out("$24") _)
+    }
+    result
+}
+
+fn copy_routine(target: *mut u32) {
+    for (i, instruction) in ROUTINE.iter().enumerate() {
+        unsafe { target.add(i).write_volatile(*instruction); }
+    }
+}
+
+pub struct ExecuteFromDMEM {}
+
+impl Test for ExecuteFromDMEM {
+    fn name(&self) -> &str { "Instruction fetch: SP DMEM" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let target = MemoryMap::uncached_spmem_address::<u32>(0x0F00);
+        copy_routine(target);
+
+        soft_assert_eq(call(target as usize), EXPECTED_RESULT, "Result of routine in DMEM")?;
+
+        Ok(())
+    }
+}
+
+pub struct ExecuteFromIMEM {}
+
+impl Test for ExecuteFromIMEM {
+    fn name(&self) -> &str { "Instruction fetch: SP IMEM" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let target = MemoryMap::uncached_spmem_address::<u32>(0x1F00);
+        copy_routine(target);
+
+        soft_assert_eq(call(target as usize), EXPECTED_RESULT, "Result of routine in IMEM")?;
+
+        Ok(())
+    }
+}
+
+pub struct ExecuteFromCartridge {}
+
+impl Test for ExecuteFromCartridge {
+    fn name(&self) -> &str { "Instruction fetch: Cartridge ROM" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let target = MemoryMap::uncached_cart_address(&ROUTINE_IN_ROM[0] as *const u32);
+        soft_assert_eq(unsafe { target.read_volatile() }, ROUTINE[0], "Routine read from ROM (sanity check)")?;
+
+        soft_assert_eq(call(target as usize), EXPECTED_RESULT, "Result of routine in ROM")?;
+
+        Ok(())
+    }
+}
+
+pub struct ExecuteFromPIFRAM {}
+
+impl Test for ExecuteFromPIFRAM {
+    fn name(&self) -> &str { "Instruction fetch: PIF RAM" }
+
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let target = MemoryMap::uncached_pifram_address::<u32>(0x0);
+        let saved: Vec<u32> = (0..ROUTINE.len()).map(|i| unsafe { target.add(i).read_volatile() }).collect();
+        copy_routine(target);
+
+        let result = call(target as usize);
+
+        for (i, word) in saved.iter().enumerate() {
+            unsafe { target.add(i).write_volatile(*word); }
+        }
+
+        soft_assert_eq(result, EXPECTED_RESULT, "Result of routine in PIF RAM")?;
+
+        Ok(())
+    }
+}
+
+fn expect_instruction_bus_error(target: usize) -> Result<(), String> {
+    // If a data read doesn't raise a bus error, an instruction fetch likely won't either. Don't execute
+    // whatever the bus returns in that case
+    let mut value_read: u32 = 0;
+    expect_exception(CauseException::DBE, 1, || {
+        unsafe {
+            asm!("
+                .set noat
+                .set noreorder
+                LW {value_read}, 0({target})
+            ", target = in(reg) target, value_read = inout(reg) value_read)
+        }
+        Ok(())
+    }).map_err(|error| format!("Not fetching from 0x{:08X}, as a data read from there didn't raise a bus error but returned 0x{:08X} ({})", target, value_read, error))?;
+
+    let exception_context = expect_exception_returning_to_kernel(CauseException::IBE, || {
+        call(target);
+        Ok(())
+    })?;
+
+    soft_assert_eq(exception_context.k0_exception_vector, 0xFFFFFFFF_80000180, "Exception Vector")?;
+    soft_assert_eq(exception_context.exceptpc, target as i32 as i64 as u64, "ExceptPC")?;
+    soft_assert_eq(exception_context.cause, Cause::new().with_exception(CauseException::IBE), "Cause")?;
+    soft_assert_eq(exception_context.status, 0x24000002, "Status")?;
+
+    Ok(())
+}
+
+pub struct FetchFromUnmappedRCP {}
+
+impl Test for FetchFromUnmappedRCP {
+    fn name(&self) -> &str { "Instruction fetch: Unmapped RCP space" }
+
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        expect_instruction_bus_error(MemoryMap::physical_to_uncached_mut::<u32>(0x0490_0000) as usize)
+    }
+}
+
+pub struct FetchFromPIFROM {}
+
+impl Test for FetchFromPIFROM {
+    fn name(&self) -> &str { "Instruction fetch: PIF ROM" }
+
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        expect_instruction_bus_error(MemoryMap::physical_to_uncached_mut::<u32>(0x1FC0_0000) as usize)
+    }
+}
@@ -12,28 +12,40 @@ use crate::{FramebufferConsole, print, println};
 use crate::cop1::{FCSR, FCSRFlags, FCSRRoundingMode, set_fcsr};
 use crate::isviewer::text_out;
 use crate::math::soft_float::{SoftF32, SoftF64};
+use crate::rsp::dmem_writer::DMEMWriter;
+use crate::rsp::rsp::RSP;
+use crate::rsp::rsp_disassembler::disassemble_imem;
 use crate::tests::cop1::compares::FPUSpecialNumber;
 use crate::tests::traps::Immediate;
 
 mod arithmetic;
 mod address_error_exception;
+mod bus_errors;
 mod cart_memory;
 mod cop_unusable;
 mod cop0;
 mod cop1;
+mod eret;
 mod exception_instructions;
+mod exception_priority;
+mod instruction_fetch;
 mod jumps;
+mod operating_modes;
 mod overflow_exception;
 mod pif_memory;
 mod rdp;
+mod reserved_instructions;
 mod rsp;
 mod startup;
 mod soft_asserts;
 mod sp_memory;
 mod testlist;
+mod timing;
 mod tlb;
 mod tlb64;
 mod traps;
+mod uncached_code;
+mod watchpoints;
 
 mod configuration {
     pub const BASE: bool = cfg!(feature = "base");
@@ -41,6 +53,7 @@ mod configuration {
     pub const CYCLE: bool = cfg!(feature = "cycle");
     pub const COP0HAZARD: bool = cfg!(feature = "cop0hazard");
     pub const POORLY_UNDERSTOOD_QUIRK: bool = cfg!(feature = "poorly_understood_quirk");
+    pub const DUMP_RSP_ON_FAILURE: bool = cfg!(feature = "dump_rsp_on_failure");
 }
 
 /// The importance level of a [test](Test).
@@ -372,6 +385,19 @@ pub fn run() {
             set_fcsr(FCSR::DEFAULT);
         }
 
+        // If the test fails after running something on the RSP, the program might be of interest. The dump uses MIPS
+        // operand order, so vector instructions read "vd, vs, vt[e]" while RSPAssembler::write_v* take (vd, vt, vs, e)
+        fn dump_rsp_program() {
+            let assembled_length = DMEMWriter::take_written_end();
+            if configuration::DUMP_RSP_ON_FAILURE && RSP::take_started() {
+                text_out("RSP program in IMEM:\n");
+                text_out(&disassemble_imem(assembled_length));
+                text_out("\n");
+            }
+        }
+
+        RSP::take_started();
+        DMEMWriter::take_written_end();
         let counter_before = crate::cop0::count();
         let test_result = test.run(&value);
         let counter_after = crate::cop0::count();
@@ -387,6 +413,7 @@ pub fn run() {
                     Ok(e) => println!("Test '{}'{} failed with exception: {:?}\n", test.name(), value_desc(value), e),
                     Err(e) => println!("Test '{}'{} failed with unknown exception: {:?}\n", test.name(), value_desc(value), e),
                 }
+                dump_rsp_program();
 
                 *failed += 1;
             }
@@ -397,6 +424,7 @@ pub fn run() {
                     }
                     Err(error) => {
                         println!("Test '{}'{} failed: {}\n", test.name(), value_desc(value), error);
+                        dump_rsp_program();
                         *failed += 1;
                     }
                 }
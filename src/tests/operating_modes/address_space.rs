@@ -0,0 +1,89 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::cop0;
+use crate::cop0::{Cause, CauseException, Status, StatusKSU};
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+use crate::user_mode;
+
+// Which segments are accessible depends on the operating mode:
+// - User mode: Only kuseg (32 bit: 0x00000000-0x7FFFFFFF; 64 bit: the lower 40 bits)
+// - Supervisor mode: suseg (same as kuseg) and sseg (32 bit: 0xC0000000-0xDFFFFFFF; 64 bit: xsseg and csseg)
+// Everything else raises an address error. Accessible segments are still mapped through the TLB, so
+// unmapped addresses raise a TLB miss instead.
+// In all cases, the exception handler runs in kernel mode (because of EXL), while Status.KSU keeps the
+// previous mode.
+
+const LW_V0_A0: u32 = 0x8C82_0000;
+const SW_R0_A0: u32 = 0xAC80_0000;
+
+const CODE: u64 = user_mode::CODE_VIRTUAL_ADDRESS as u64;
+
+/// Name, mode, 64 bit addressing, address, store, expected exception (Sys means success) and its vector
+const ACCESSES: [(&str, StatusKSU, bool, u64, bool, CauseException, u64); 22] = [
+    ("user: kuseg load", StatusKSU::User, false, CODE, false, CauseException::Sys, 0xFFFFFFFF_80000180),
+    ("user: kuseg store", StatusKSU::User, false, CODE + 0x100, true, CauseException::Sys, 0xFFFFFFFF_80000180),
+    ("user: kseg0 load", StatusKSU::User, false, 0xFFFFFFFF_80000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("user: kseg0 store", StatusKSU::User, false, 0xFFFFFFFF_80000000, true, CauseException::AdES, 0xFFFFFFFF_80000180),
+    ("user: kseg1 load", StatusKSU::User, false, 0xFFFFFFFF_A0000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("user: ksseg load", StatusKSU::User, false, 0xFFFFFFFF_C0000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("user: kseg3 load", StatusKSU::User, false, 0xFFFFFFFF_E0000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("user (64 bit): xkuseg load", StatusKSU::User, true, CODE, false, CauseException::Sys, 0xFFFFFFFF_80000180),
+    ("user (64 bit): beyond xkuseg load", StatusKSU::User, true, 0x00000100_00000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("user (64 bit): xsseg load", StatusKSU::User, true, 0x40000000_00000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("user (64 bit): kseg0 load", StatusKSU::User, true, 0xFFFFFFFF_80000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("supervisor: suseg load", StatusKSU::Supervisor, false, CODE, false, CauseException::Sys, 0xFFFFFFFF_80000180),
+    ("supervisor: sseg load", StatusKSU::Supervisor, false, 0xFFFFFFFF_C0000000, false, CauseException::TLBL, 0xFFFFFFFF_80000000),
+    ("supervisor: kseg0 load", StatusKSU::Supervisor, false, 0xFFFFFFFF_80000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("supervisor: kseg1 store", StatusKSU::Supervisor, false, 0xFFFFFFFF_A0000000, true, CauseException::AdES, 0xFFFFFFFF_80000180),
+    ("supervisor: kseg3 load", StatusKSU::Supervisor, false, 0xFFFFFFFF_E0000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("supervisor (64 bit): xsuseg load", StatusKSU::Supervisor, true, CODE, false, CauseException::Sys, 0xFFFFFFFF_80000180),
+    ("supervisor (64 bit): beyond xsuseg load", StatusKSU::Supervisor, true, 0x00000100_00000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("supervisor (64 bit): xsseg load", StatusKSU::Supervisor, true, 0x40000000_00000000, false, CauseException::TLBL, 0xFFFFFFFF_80000080),
+    ("supervisor (64 bit): csseg load", StatusKSU::Supervisor, true, 0xFFFFFFFF_C0000000, false, CauseException::TLBL, 0xFFFFFFFF_80000080),
+    ("supervisor (64 bit): xkphys load", StatusKSU::Supervisor, true, 0x90000000_00000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+    ("supervisor (64 bit): kseg0 load", StatusKSU::Supervisor, true, 0xFFFFFFFF_80000000, false, CauseException::AdEL, 0xFFFFFFFF_80000180),
+];
+
+pub struct AddressSpace {}
+
+impl Test for AddressSpace {
+    fn name(&self) -> &str { "Address space (user and supervisor mode)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        (0..ACCESSES.len() as u32).map(|i| -> Box<dyn Any> { Box::new(i) }).collect()
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, ksu, extended, address, store, expected, vector) = ACCESSES[*(*value).downcast_ref::<u32>().unwrap() as usize];
+
+        unsafe { cop0::clear_tlb(); }
+
+        let status = Status::DEFAULT
+            .with_ksu(ksu)
+            .with_ux(extended && matches!(ksu, StatusKSU::User))
+            .with_sx(extended && matches!(ksu, StatusKSU::Supervisor));
+        let exception_context = user_mode::run_in_mode(status, &[if store { SW_R0_A0 } else { LW_V0_A0 }], [address, 0, 0, 0], expected)?;
+
+        soft_assert_eq2(exception_context.k0_exception_vector, vector, || format!("{}: Exception Vector", name))?;
+        soft_assert_eq2(exception_context.cause, Cause::new().with_exception(expected), || format!("{}: Cause", name))?;
+        soft_assert_eq2(exception_context.status, status.with_exl(true).raw_value(), || format!("{}: Status (KSU is expected to be preserved)", name))?;
+        if expected == CauseException::Sys {
+            soft_assert_eq2(exception_context.exceptpc, CODE + 4, || format!("{}: ExceptPC", name))?;
+        } else {
+            soft_assert_eq2(exception_context.exceptpc, CODE, || format!("{}: ExceptPC", name))?;
+            soft_assert_eq2(exception_context.badvaddr, address, || format!("{}: BadVAddr", name))?;
+        }
+
+        // We're back in kernel mode, so COP0 can be used without restrictions again
+        soft_assert_eq2(cop0::status().raw_value(), Status::DEFAULT.raw_value(), || format!("{}: Status after returning", name))?;
+
+        Ok(())
+    }
+}
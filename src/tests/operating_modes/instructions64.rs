@@ -0,0 +1,138 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use arbitrary_int::u5;
+
+use crate::assembler::{Assembler, GPR, Opcode, SpecialOpcode};
+use crate::cop0::{Cause, CauseException, Status, StatusKSU};
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+use crate::user_mode;
+
+// 64 bit instructions (DADD, DSLL, LD etc) are only available in user and supervisor mode if UX/SX is set.
+// Otherwise they raise a Reserved Instruction exception. In kernel mode they are always available, no
+// matter what KX says.
+//
+// Every instruction below uses the following inputs and writes its result into $2:
+// $4: 0x00000000_7FFFFFFF
+// $5: 1
+// $6: Address of the code (the code page is used as scratch memory)
+// $7: 0x80000000_00000000
+
+const ARGS: [u64; 4] = [0x7FFF_FFFF, 1, user_mode::CODE_VIRTUAL_ADDRESS as u64, 0x8000_0000_0000_0000];
+
+const SYSCALL: u32 = 0x0000_000C;
+
+const fn special(op: SpecialOpcode, rd: u8, rs: u8, rt: u8, sa: u8) -> u32 {
+    Assembler::make_special(op, u5::new(sa), u5::new(rd), u5::new(rs), u5::new(rt))
+}
+
+const LD_V0: u32 = Assembler::make_main_immediate(Opcode::LD, GPR::V0, GPR::R2, 0);
+const LWU_V0: u32 = Assembler::make_main_immediate(Opcode::LWU, GPR::V0, GPR::R2, 0);
+
+/// Name, instructions, expected value in $2
+const INSTRUCTIONS: [(&str, &[u32], u64); 27] = [
+    ("DADD", &[special(SpecialOpcode::DADD, 2, 4, 5, 0)], 0x00000000_80000000),
+    ("DADDU", &[special(SpecialOpcode::DADDU, 2, 4, 5, 0)], 0x00000000_80000000),
+    ("DSUB", &[special(SpecialOpcode::DSUB, 2, 0, 4, 0)], 0xFFFFFFFF_80000001),
+    ("DSUBU", &[special(SpecialOpcode::DSUBU, 2, 0, 4, 0)], 0xFFFFFFFF_80000001),
+    ("DADDI", &[Assembler::make_main_immediate(Opcode::DADDI, GPR::V0, GPR::A0, 1)], 0x00000000_80000000),
+    ("DADDIU", &[Assembler::make_main_immediate(Opcode::DADDIU, GPR::V0, GPR::A0, 1)], 0x00000000_80000000),
+    ("DSLL", &[special(SpecialOpcode::DSLL, 2, 0, 4, 4)], 0x00000007_FFFFFFF0),
+    ("DSRL", &[special(SpecialOpcode::DSRL, 2, 0, 7, 4)], 0x08000000_00000000),
+    ("DSRA", &[special(SpecialOpcode::DSRA, 2, 0, 7, 4)], 0xF8000000_00000000),
+    ("DSLL32", &[special(SpecialOpcode::DSLL32, 2, 0, 4, 0)], 0x7FFFFFFF_00000000),
+    ("DSRL32", &[special(SpecialOpcode::DSRL32, 2, 0, 7, 0)], 0x00000000_80000000),
+    ("DSRA32", &[special(SpecialOpcode::DSRA32, 2, 0, 7, 0)], 0xFFFFFFFF_80000000),
+    ("DSLLV", &[special(SpecialOpcode::DSLLV, 2, 5, 4, 0)], 0x00000000_FFFFFFFE),
+    ("DSRLV", &[special(SpecialOpcode::DSRLV, 2, 5, 7, 0)], 0x40000000_00000000),
+    ("DSRAV", &[special(SpecialOpcode::DSRAV, 2, 5, 7, 0)], 0xC0000000_00000000),
+    ("DMULT", &[special(SpecialOpcode::DMULT, 0, 4, 4, 0), special(SpecialOpcode::MFLO, 2, 0, 0, 0)], 0x3FFFFFFF_00000001),
+    ("DMULTU", &[special(SpecialOpcode::DMULTU, 0, 4, 4, 0), special(SpecialOpcode::MFLO, 2, 0, 0, 0)], 0x3FFFFFFF_00000001),
+    ("DDIV", &[special(SpecialOpcode::DDIV, 0, 7, 5, 0), special(SpecialOpcode::MFLO, 2, 0, 0, 0)], 0x80000000_00000000),
+    ("DDIVU", &[special(SpecialOpcode::DDIVU, 0, 7, 5, 0), special(SpecialOpcode::MFLO, 2, 0, 0, 0)], 0x80000000_00000000),
+    // Loads read the code itself, which is the instruction followed by the SYSCALL
+    ("LD", &[LD_V0], ((LD_V0 as u64) << 32) | (SYSCALL as u64)),
+    ("LDL", &[Assembler::make_main_immediate(Opcode::LDL, GPR::V0, GPR::R2, 0)], ((Assembler::make_main_immediate(Opcode::LDL, GPR::V0, GPR::R2, 0) as u64) << 32) | (SYSCALL as u64)),
+    ("LDR", &[Assembler::make_main_immediate(Opcode::LDR, GPR::V0, GPR::R2, 7)], ((Assembler::make_main_immediate(Opcode::LDR, GPR::V0, GPR::R2, 7) as u64) << 32) | (SYSCALL as u64)),
+    ("LLD", &[Assembler::make_main_immediate(Opcode::LLD, GPR::V0, GPR::R2, 0)], ((Assembler::make_main_immediate(Opcode::LLD, GPR::V0, GPR::R2, 0) as u64) << 32) | (SYSCALL as u64)),
+    ("LWU", &[LWU_V0], LWU_V0 as u64),
+    // Stores write behind the code, which is then read back through LD
+    ("SD", &[Assembler::make_main_immediate(Opcode::SD, GPR::A0, GPR::R2, 16), Assembler::make_main_immediate(Opcode::LD, GPR::V0, GPR::R2, 16)], 0x00000000_7FFFFFFF),
+    ("SDL", &[Assembler::make_main_immediate(Opcode::SDL, GPR::A0, GPR::R2, 16), Assembler::make_main_immediate(Opcode::LD, GPR::V0, GPR::R2, 16)], 0x00000000_7FFFFFFF),
+    ("SDR", &[Assembler::make_main_immediate(Opcode::SDR, GPR::A0, GPR::R2, 23), Assembler::make_main_immediate(Opcode::LD, GPR::V0, GPR::R2, 16)], 0x00000000_7FFFFFFF),
+];
+
+fn instruction_values() -> Vec<Box<dyn Any>> {
+    let mut result: Vec<Box<dyn Any>> = Vec::new();
+    for i in 0..INSTRUCTIONS.len() {
+        result.push(Box::new((false, i as u32)));
+        result.push(Box::new((true, i as u32)));
+    }
+    result
+}
+
+fn test_instruction(status: Status, expect_reserved_instruction: bool, index: u32) -> Result<(), String> {
+    let (name, instructions, expected) = INSTRUCTIONS[index as usize];
+
+    if expect_reserved_instruction {
+        let exception_context = user_mode::run_in_mode(status, instructions, ARGS, CauseException::RI)?;
+        soft_assert_eq2(exception_context.exceptpc, user_mode::CODE_VIRTUAL_ADDRESS as u64, || format!("{}: ExceptPC", name))?;
+        soft_assert_eq2(exception_context.cause, Cause::new().with_exception(CauseException::RI), || format!("{}: Cause", name))?;
+        soft_assert_eq2(exception_context.status, status.with_exl(true).raw_value(), || format!("{}: Status", name))?;
+    } else {
+        let exception_context = user_mode::run_in_mode(status, instructions, ARGS, CauseException::Sys)?;
+        soft_assert_eq2(exception_context.exceptpc, user_mode::CODE_VIRTUAL_ADDRESS as u64 + (instructions.len() as u64) * 4, || format!("{}: ExceptPC", name))?;
+        soft_assert_eq2(exception_context.v0, expected, || format!("{}: Result", name))?;
+    }
+
+    Ok(())
+}
+
+pub struct Instructions64UserMode {}
+
+impl Test for Instructions64UserMode {
+    fn name(&self) -> &str { "64 bit instructions in user mode (UX)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { instruction_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (ux, index) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+        test_instruction(Status::DEFAULT.with_ksu(StatusKSU::User).with_ux(ux), !ux, index)
+    }
+}
+
+pub struct Instructions64SupervisorMode {}
+
+impl Test for Instructions64SupervisorMode {
+    fn name(&self) -> &str { "64 bit instructions in supervisor mode (SX)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { instruction_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (sx, index) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+        test_instruction(Status::DEFAULT.with_ksu(StatusKSU::Supervisor).with_sx(sx), !sx, index)
+    }
+}
+
+pub struct Instructions64KernelMode {}
+
+impl Test for Instructions64KernelMode {
+    fn name(&self) -> &str { "64 bit instructions in kernel mode (KX)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { instruction_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        // No matter what KX says, 64 bit instructions are always available in kernel mode
+        let (kx, index) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+        test_instruction(Status::DEFAULT.with_kx(kx), false, index)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod address_space;
+pub mod instructions64;
@@ -12,17 +12,21 @@ use crate::rdp::rdp::{DP_SET_STATUS_CLEAR_FREEZE, DP_SET_STATUS_CLEAR_XBUS, DP_S
 use crate::rdp::rdp_assembler::{RDPAssembler, RDPRectangle};
 use crate::rsp::rsp::RSP;
 use crate::tests::{Level, Test};
-use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2};
+use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2, soft_assert_greater_or_equal, soft_assert_less};
 use crate::uncached_memory::UncachedHeapMemory;
 
 pub mod filled_triangle;
-
+pub mod rsp_cop0;
+
+// FREEZE and double buffering:
+//  - While FREEZE is set, the RDP doesn't execute the command list until it is unfrozen
+//  - CURRENT still advances up to START+240 while frozen, as the commands are dma'ed into the command buffer
+//  - A second START/END pair can be written while the first list is still pending. START_VALID and END_VALID
+//    stay set until the RDP moves on to the second list
+//
 // TODO:
-//  - Make a test that uses FREEZE. It should not execute the RDP list until the RDP is unfrozen
-//  - For freeze test perfection: CURRENT should advance up to START+240 as the commands are
-//    dma'ed even if the RDP is frozen
-//  - It is possible to DMA two command lists to the RDP. Do a test for double-buffering
-//  - Similar to RSP side, it is possible to set and clear bits at the same time. Write a test to see what happens
+//  - Set and clear bits at the same time (see SetAndClearAtOnce, which is only a guess so far)
+//  - Whether START_VALID/END_VALID are cleared for the first list while the RDP is frozen
 
 fn wait_for_status(goal: u32) -> Result<(), String> {
     for _ in 0..10_000 {
@@ -34,6 +38,41 @@ fn wait_for_status(goal: u32) -> Result<(), String> {
     Err(format!("Time out waiting for RDP status 0x{:x}. RDP status at timeout: 0x{:x}", goal, RDP::status()))
 }
 
+fn wait_for_current(goal: u32) -> Result<(), String> {
+    for _ in 0..100_000 {
+        if RDP::current() == goal {
+            return Ok(());
+        }
+    }
+
+    Err(format!("Time out waiting for RDP current 0x{:x}. RDP current at timeout: 0x{:x}", goal, RDP::current()))
+}
+
+/// Waits until the RDP is done with a command list that ends at end. CURRENT reaches END as soon as the last
+/// command has been fetched, which can be before it was executed. So also wait for STATUS to become idle_status
+fn wait_for_completion(end: u32, idle_status: u32) -> Result<(), String> {
+    wait_for_current(end)?;
+    wait_for_status(idle_status)
+}
+
+/// Assembles a command list that fills the framebuffer with the given color. The rectangle is filled
+/// repeatedly so that the command list can be made longer than what the RDP prefetches
+fn assemble_fill(framebuffer: &mut UncachedHeapMemory<RGBA5551>, width: usize, height: usize, color: RGBA5551, repeat: usize) -> RDPAssembler {
+    let mut assembler = RDPAssembler::new();
+    let rect = RDPRectangle::new(U10_2::from_u32(0), U10_2::from_u32(0), U10_2::from_u32(width as u32 - 1), U10_2::from_u32(height as u32 - 1));
+    assembler.set_framebuffer_image(Format::RGBA, PixelSize::Bits16, u12::new((width - 1).try_into().unwrap()), framebuffer);
+    assembler.set_scissor(&rect);
+    assembler.set_othermode(Othermode::new()
+        .with_cycle_type(CycleType::Fill));
+    assembler.set_fillcolor16(color, color);
+    for _ in 0..repeat {
+        assembler.filled_rectangle(&rect);
+    }
+    assembler.sync_pipe();
+    assembler.sync_full();
+    assembler
+}
+
 pub struct StartAndEndMasking {}
 
 impl Test for StartAndEndMasking {
@@ -287,4 +326,172 @@ impl Test for RunFromDMEMOverflow {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_from_dmem_test(|length| (0xFF0, 0xFF0 + length))
     }
-}
\ No newline at end of file
+}
+
+pub struct FreezeHoldsExecution {}
+
+impl Test for FreezeHoldsExecution {
+    fn name(&self) -> &str { "RDP STATUS: Freeze holds execution" }
+
+    fn level(&self) -> Level { Level::RDPBasic }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const WIDTH: usize = 8;
+        const HEIGHT: usize = 8;
+        let mut framebuffer = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        let mut assembler = assemble_fill(&mut framebuffer, WIDTH, HEIGHT, RGBA5551::GREEN, 1);
+        let start = assembler.start() as u32;
+        let end = assembler.end() as u32;
+
+        unsafe { RDP::set_status(DP_SET_STATUS_SET_FREEZE); }
+        unsafe { RDP::start_running(start as usize, end as usize); }
+
+        // Give the RDP plenty of time to (not) do anything
+        for _ in 0..10_000 {
+            RDP::status();
+        }
+        let status_frozen = RDP::status();
+        let current_frozen = RDP::current();
+        let framebuffer_frozen = framebuffer.read(0);
+
+        unsafe { RDP::set_status(DP_SET_STATUS_CLEAR_FREEZE); }
+        wait_for_completion(end, DP_STATUS_COMMAND_BUFFER_READY)?;
+
+        soft_assert_eq(status_frozen & DP_STATUS_FREEZE, DP_STATUS_FREEZE, "DP STATUS should have FREEZE set while frozen")?;
+        soft_assert_greater_or_equal(current_frozen, start, "DP CURRENT while frozen should be at least START")?;
+        soft_assert_greater_or_equal(end, current_frozen, "DP CURRENT while frozen should be at most END")?;
+        soft_assert_eq(framebuffer_frozen, RGBA5551::BLACK, "Framebuffer should be untouched while the RDP is frozen")?;
+
+        soft_assert_eq(RDP::start(), start, "DP START after unfreezing")?;
+        soft_assert_eq(framebuffer.read(0), RGBA5551::GREEN, "Framebuffer should be filled with GREEN after unfreezing")?;
+        soft_assert_eq(framebuffer.read(WIDTH * HEIGHT - 1), RGBA5551::GREEN, "Framebuffer should be filled with GREEN after unfreezing")?;
+
+        Ok(())
+    }
+}
+
+pub struct FreezeCurrentAdvancesWhilePrefetching {}
+
+impl Test for FreezeCurrentAdvancesWhilePrefetching {
+    fn name(&self) -> &str { "RDP STATUS: Freeze (CURRENT advances while prefetching)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const WIDTH: usize = 8;
+        const HEIGHT: usize = 8;
+        let mut framebuffer = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        // Much longer than the 240 bytes that are prefetched
+        let mut assembler = assemble_fill(&mut framebuffer, WIDTH, HEIGHT, RGBA5551::BLUE, 64);
+        let start = assembler.start() as u32;
+        let end = assembler.end() as u32;
+
+        unsafe { RDP::set_status(DP_SET_STATUS_SET_FREEZE); }
+        unsafe { RDP::start_running(start as usize, end as usize); }
+
+        for _ in 0..10_000 {
+            RDP::status();
+        }
+        let current_frozen = RDP::current();
+
+        unsafe { RDP::set_status(DP_SET_STATUS_CLEAR_FREEZE); }
+        wait_for_completion(end, DP_STATUS_COMMAND_BUFFER_READY)?;
+
+        soft_assert_less(start, current_frozen, "DP CURRENT should advance while frozen as commands are prefetched")?;
+        soft_assert_less(current_frozen, start + 240 + 1, "DP CURRENT should advance by at most 240 bytes while frozen")?;
+        soft_assert_eq(framebuffer.read(0), RGBA5551::BLUE, "Framebuffer should be filled with BLUE after unfreezing")?;
+
+        Ok(())
+    }
+}
+
+pub struct DoubleBufferedCommandLists {}
+
+impl Test for DoubleBufferedCommandLists {
+    fn name(&self) -> &str { "RDP STATUS: Double buffered command lists" }
+
+    fn level(&self) -> Level { Level::RDPBasic }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const WIDTH: usize = 8;
+        const HEIGHT: usize = 8;
+        let mut framebuffer1 = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        let mut framebuffer2 = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        let mut assembler1 = assemble_fill(&mut framebuffer1, WIDTH, HEIGHT, RGBA5551::RED, 1);
+        let mut assembler2 = assemble_fill(&mut framebuffer2, WIDTH, HEIGHT, RGBA5551::BLUE, 1);
+        let start1 = assembler1.start() as u32;
+        let end1 = assembler1.end() as u32;
+        let start2 = assembler2.start() as u32;
+        let end2 = assembler2.end() as u32;
+
+        // Freeze so that the first list is still pending when the second one is queued
+        unsafe { RDP::set_status(DP_SET_STATUS_SET_FREEZE); }
+        RDP::set_start(start1);
+        RDP::set_end(end1);
+        let status_first = RDP::status();
+
+        RDP::set_start(start2);
+        let status_second_start = RDP::status();
+        RDP::set_end(end2);
+        let status_second_end = RDP::status();
+        let framebuffer2_frozen = framebuffer2.read(0);
+
+        unsafe { RDP::set_status(DP_SET_STATUS_CLEAR_FREEZE); }
+        wait_for_completion(end2, DP_STATUS_COMMAND_BUFFER_READY)?;
+
+        // Whether the first START/END pair is taken over while frozen (which would clear the VALID bits in between)
+        // hasn't been measured, so only check that the bits of the second pair are set
+        soft_assert_eq2(status_second_start & DP_STATUS_START_VALID, DP_STATUS_START_VALID, || format!("DP STATUS after writing the second START. DP STATUS after the first START/END: 0x{:x}", status_first))?;
+        soft_assert_eq(status_second_end & (DP_STATUS_START_VALID | DP_STATUS_END_VALID), DP_STATUS_START_VALID | DP_STATUS_END_VALID, "DP STATUS after writing the second END while the first list is pending")?;
+        soft_assert_eq(framebuffer2_frozen, RGBA5551::BLACK, "Second framebuffer should be untouched while the RDP is frozen")?;
+
+        soft_assert_eq(RDP::start(), start2, "DP START after both lists")?;
+        soft_assert_eq(RDP::end(), end2, "DP END after both lists")?;
+        soft_assert_eq(framebuffer1.read(0), RGBA5551::RED, "First framebuffer should be filled with RED")?;
+        soft_assert_eq(framebuffer2.read(0), RGBA5551::BLUE, "Second framebuffer should be filled with BLUE")?;
+
+        Ok(())
+    }
+}
+
+pub struct SetAndClearAtOnce {}
+
+impl Test for SetAndClearAtOnce {
+    fn name(&self) -> &str { "RDP STATUS: Set and clear bits at once" }
+
+    // On the RSP side, setting and clearing a bit at the same time keeps the bit unchanged. The RDP is
+    // assumed to behave the same, but this hasn't been verified on hardware yet
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let mut results = [0u32; 4];
+        for (i, (initial, flag, set_and_clear)) in [
+            (DP_SET_STATUS_CLEAR_FREEZE, DP_STATUS_FREEZE, DP_SET_STATUS_SET_FREEZE | DP_SET_STATUS_CLEAR_FREEZE),
+            (DP_SET_STATUS_SET_FREEZE, DP_STATUS_FREEZE, DP_SET_STATUS_SET_FREEZE | DP_SET_STATUS_CLEAR_FREEZE),
+            (DP_SET_STATUS_CLEAR_XBUS, DP_STATUS_XBUS, DP_SET_STATUS_SET_XBUS | DP_SET_STATUS_CLEAR_XBUS),
+            (DP_SET_STATUS_SET_XBUS, DP_STATUS_XBUS, DP_SET_STATUS_SET_XBUS | DP_SET_STATUS_CLEAR_XBUS),
+        ].iter().enumerate() {
+            unsafe { RDP::set_status(*initial); }
+            unsafe { RDP::set_status(*set_and_clear); }
+            results[i] = RDP::status() & *flag;
+        }
+
+        // Leave the RDP in a normal state before asserting
+        unsafe { RDP::set_status(DP_SET_STATUS_CLEAR_FREEZE | DP_SET_STATUS_CLEAR_XBUS); }
+
+        soft_assert_eq(results[0], 0, "FREEZE after setting and clearing it at once (while clear)")?;
+        soft_assert_eq(results[1], DP_STATUS_FREEZE, "FREEZE after setting and clearing it at once (while set)")?;
+        soft_assert_eq(results[2], 0, "XBUS after setting and clearing it at once (while clear)")?;
+        soft_assert_eq(results[3], DP_STATUS_XBUS, "XBUS after setting and clearing it at once (while set)")?;
+
+        Ok(())
+    }
+}
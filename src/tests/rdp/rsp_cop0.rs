@@ -0,0 +1,289 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::graphics::color::{Color, RGBA5551};
+use crate::rdp::rdp::{DP_SET_STATUS_CLEAR_FREEZE, DP_SET_STATUS_CLEAR_XBUS, DP_SET_STATUS_SET_FREEZE, DP_SET_STATUS_SET_XBUS, DP_STATUS_COMMAND_BUFFER_READY, DP_STATUS_FREEZE, DP_STATUS_PIPE_BUSY, DP_STATUS_XBUS, RDP};
+use crate::rsp::rsp::RSP;
+use crate::rsp::rsp_assembler::{CP0Register, GPR, RSPAssembler};
+use crate::rsp::spmem::SPMEM;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2, soft_assert_greater_or_equal, soft_assert_neq};
+use crate::uncached_memory::UncachedHeapMemory;
+
+use super::{assemble_fill, wait_for_completion};
+
+// The RSP sees the DP registers as COP0 registers 8..15:
+// - START, END, CURRENT and STATUS return the same values the CPU sees
+// - CLOCK, BUFBUSY, PIPEBUSY and TMEM are counters. They only ever go up (unless cleared through STATUS), so a
+//   value read by the CPU after the RSP can't be smaller
+// - The RSP can write START, END and STATUS through MTC0, which is how microcode kicks off display lists
+
+/// Order of the values returned by read_dp_registers_on_rsp
+const DP_REGISTER_NAMES: [&str; 8] = ["START", "END", "CURRENT", "STATUS", "CLOCK", "BUFBUSY", "PIPEBUSY", "TMEM"];
+
+/// Reads all DP registers through MFC0 on the RSP
+fn read_dp_registers_on_rsp() -> [u32; 8] {
+    let mut assembler = RSPAssembler::new(0);
+    assembler.write_mfc0(CP0Register::DPStart, GPR::S0);
+    assembler.write_mfc0(CP0Register::DPEnd, GPR::S1);
+    assembler.write_mfc0(CP0Register::DPCurrent, GPR::S2);
+    assembler.write_mfc0(CP0Register::DPStatus, GPR::S3);
+    assembler.write_mfc0(CP0Register::DPClock, GPR::S4);
+    assembler.write_mfc0(CP0Register::DPBufBusy, GPR::S5);
+    assembler.write_mfc0(CP0Register::DPPipeBusy, GPR::S6);
+    assembler.write_mfc0(CP0Register::DPTmem, GPR::S7);
+    assembler.write_sw(GPR::S0, GPR::R0, 0x00);
+    assembler.write_sw(GPR::S1, GPR::R0, 0x04);
+    assembler.write_sw(GPR::S2, GPR::R0, 0x08);
+    assembler.write_sw(GPR::S3, GPR::R0, 0x0C);
+    assembler.write_sw(GPR::S4, GPR::R0, 0x10);
+    assembler.write_sw(GPR::S5, GPR::R0, 0x14);
+    assembler.write_sw(GPR::S6, GPR::R0, 0x18);
+    assembler.write_sw(GPR::S7, GPR::R0, 0x1C);
+    assembler.write_break();
+
+    RSP::clear_broke();
+    RSP::run_and_wait(0);
+
+    let mut result = [0u32; 8];
+    for (i, value) in result.iter_mut().enumerate() {
+        *value = SPMEM::read(i * 4);
+    }
+    result
+}
+
+fn read_dp_registers_on_cpu() -> [u32; 8] {
+    [RDP::start(), RDP::end(), RDP::current(), RDP::status(), RDP::clock(), RDP::buf_busy(), RDP::pipe_busy(), RDP::tmem()]
+}
+
+/// What the RDP is doing while the DP registers are read
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Situation {
+    Idle,
+    Frozen,
+    Running,
+}
+
+/// Compares the counters. While idle, they have to match exactly. Otherwise they keep counting between the two
+/// reads, so the CPU value has to be at least the RSP one
+fn compare_counters(situation: Situation, rsp: &[u32; 8], cpu: &[u32; 8]) -> Result<(), String> {
+    for i in 4..8 {
+        if situation == Situation::Idle {
+            soft_assert_eq2(cpu[i], rsp[i], || format!("{:?}: DP {} (read via CPU) should match the value read via RSP MFC0", situation, DP_REGISTER_NAMES[i]))?;
+        } else {
+            soft_assert_greater_or_equal(cpu[i], rsp[i], format!("{:?}: DP {} (read via CPU) should be at least the value read via RSP MFC0", situation, DP_REGISTER_NAMES[i]).as_str())?;
+        }
+    }
+    if situation == Situation::Running {
+        // A fill doesn't touch TMEM, so only the other counters are guaranteed to be moving
+        for i in 4..7 {
+            soft_assert_neq(rsp[i], 0, format!("{:?}: DP {} (read via RSP MFC0) should be counting", situation, DP_REGISTER_NAMES[i]).as_str())?;
+        }
+    }
+    Ok(())
+}
+
+pub struct DPRegistersFromRSPIdle {}
+
+impl Test for DPRegistersFromRSPIdle {
+    fn name(&self) -> &str { "RSP COP0: DP registers (idle)" }
+
+    fn level(&self) -> Level { Level::RDPBasic }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const WIDTH: usize = 8;
+        const HEIGHT: usize = 8;
+        let mut framebuffer = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        let mut assembler = assemble_fill(&mut framebuffer, WIDTH, HEIGHT, RGBA5551::RED, 1);
+        let end = assembler.end() as u32;
+        unsafe { RDP::start_running(assembler.start(), end as usize); }
+        wait_for_completion(end, DP_STATUS_COMMAND_BUFFER_READY)?;
+
+        let rsp = read_dp_registers_on_rsp();
+        let cpu = read_dp_registers_on_cpu();
+
+        for i in 0..4 {
+            soft_assert_eq2(rsp[i], cpu[i], || format!("Idle: DP {} (read via RSP MFC0) should match the CPU", DP_REGISTER_NAMES[i]))?;
+        }
+        soft_assert_eq(rsp[2], end, "Idle: DP CURRENT (read via RSP MFC0) should be END")?;
+        compare_counters(Situation::Idle, &rsp, &cpu)?;
+
+        Ok(())
+    }
+}
+
+pub struct DPRegistersFromRSPFrozen {}
+
+impl Test for DPRegistersFromRSPFrozen {
+    fn name(&self) -> &str { "RSP COP0: DP registers (frozen)" }
+
+    fn level(&self) -> Level { Level::RDPBasic }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const WIDTH: usize = 8;
+        const HEIGHT: usize = 8;
+        let mut framebuffer = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        let mut assembler = assemble_fill(&mut framebuffer, WIDTH, HEIGHT, RGBA5551::GREEN, 1);
+        let start = assembler.start() as u32;
+        let end = assembler.end() as u32;
+
+        unsafe { RDP::set_status(DP_SET_STATUS_SET_FREEZE); }
+        unsafe { RDP::start_running(start as usize, end as usize); }
+
+        let rsp = read_dp_registers_on_rsp();
+        let cpu = read_dp_registers_on_cpu();
+        let framebuffer_while_frozen = framebuffer.read(0);
+
+        unsafe { RDP::set_status(DP_SET_STATUS_CLEAR_FREEZE); }
+        wait_for_completion(end, DP_STATUS_COMMAND_BUFFER_READY)?;
+
+        soft_assert_eq(rsp[0], start, "Frozen: DP START (read via RSP MFC0)")?;
+        soft_assert_eq(rsp[1], end, "Frozen: DP END (read via RSP MFC0)")?;
+        soft_assert_eq(rsp[0], cpu[0], "Frozen: DP START (read via RSP MFC0) should match the CPU")?;
+        soft_assert_eq(rsp[1], cpu[1], "Frozen: DP END (read via RSP MFC0) should match the CPU")?;
+        soft_assert_eq(rsp[3], cpu[3], "Frozen: DP STATUS (read via RSP MFC0) should match the CPU")?;
+        soft_assert_eq(rsp[3] & DP_STATUS_FREEZE, DP_STATUS_FREEZE, "Frozen: DP STATUS (read via RSP MFC0) should have FREEZE set")?;
+        // CURRENT might advance while frozen as commands are being prefetched, but it can't go past END
+        soft_assert_greater_or_equal(rsp[2], start, "Frozen: DP CURRENT (read via RSP MFC0) should be at least START")?;
+        soft_assert_greater_or_equal(end, rsp[2], "Frozen: DP CURRENT (read via RSP MFC0) should be at most END")?;
+        soft_assert_greater_or_equal(cpu[2], rsp[2], "Frozen: DP CURRENT (read via CPU) should be at least the value read via RSP MFC0")?;
+        compare_counters(Situation::Frozen, &rsp, &cpu)?;
+
+        soft_assert_eq(framebuffer_while_frozen, RGBA5551::BLACK, "Framebuffer should be untouched while the RDP is frozen")?;
+        soft_assert_eq(framebuffer.read(0), RGBA5551::GREEN, "Framebuffer should be filled with GREEN after unfreezing")?;
+
+        Ok(())
+    }
+}
+
+pub struct DPRegistersFromRSPRunning {}
+
+impl Test for DPRegistersFromRSPRunning {
+    fn name(&self) -> &str { "RSP COP0: DP registers (running)" }
+
+    fn level(&self) -> Level { Level::RDPBasic }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // Large enough that the RDP is still busy filling while the RSP looks at the registers
+        const WIDTH: usize = 320;
+        const HEIGHT: usize = 240;
+        let mut framebuffer = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        let mut assembler = assemble_fill(&mut framebuffer, WIDTH, HEIGHT, RGBA5551::BLUE, 1);
+        let start = assembler.start() as u32;
+        let end = assembler.end() as u32;
+
+        unsafe { RDP::start_running(start as usize, end as usize); }
+        let rsp = read_dp_registers_on_rsp();
+        let cpu = read_dp_registers_on_cpu();
+        wait_for_completion(end, DP_STATUS_COMMAND_BUFFER_READY)?;
+
+        soft_assert_eq(rsp[0], start, "Running: DP START (read via RSP MFC0)")?;
+        soft_assert_eq(rsp[1], end, "Running: DP END (read via RSP MFC0)")?;
+        soft_assert_eq(rsp[0], cpu[0], "Running: DP START (read via RSP MFC0) should match the CPU")?;
+        soft_assert_eq(rsp[1], cpu[1], "Running: DP END (read via RSP MFC0) should match the CPU")?;
+        soft_assert_eq(rsp[3] & DP_STATUS_PIPE_BUSY, DP_STATUS_PIPE_BUSY, "Running: DP STATUS (read via RSP MFC0) should have PIPE_BUSY set")?;
+        soft_assert_greater_or_equal(rsp[2], start, "Running: DP CURRENT (read via RSP MFC0) should be at least START")?;
+        soft_assert_greater_or_equal(end, rsp[2], "Running: DP CURRENT (read via RSP MFC0) should be at most END")?;
+        soft_assert_greater_or_equal(cpu[2], rsp[2], "Running: DP CURRENT (read via CPU) should be at least the value read via RSP MFC0")?;
+        compare_counters(Situation::Running, &rsp, &cpu)?;
+
+        soft_assert_eq(framebuffer.read(WIDTH * HEIGHT - 1), RGBA5551::BLUE, "Framebuffer should be filled with BLUE after the run")?;
+
+        Ok(())
+    }
+}
+
+pub struct RSPStartsDisplayList {}
+
+impl Test for RSPStartsDisplayList {
+    fn name(&self) -> &str { "RSP COP0: Start display list (RDRAM)" }
+
+    fn level(&self) -> Level { Level::RDPBasic }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const WIDTH: usize = 8;
+        const HEIGHT: usize = 8;
+        let mut framebuffer = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        let mut display_list = assemble_fill(&mut framebuffer, WIDTH, HEIGHT, RGBA5551::RED, 1);
+        let start = display_list.start() as u32;
+        let end = display_list.end() as u32;
+
+        let mut assembler = RSPAssembler::new(0);
+        assembler.write_li(GPR::S0, DP_SET_STATUS_CLEAR_XBUS);
+        assembler.write_li(GPR::S1, start);
+        assembler.write_li(GPR::S2, end);
+        assembler.write_mtc0(CP0Register::DPStatus, GPR::S0);
+        assembler.write_mtc0(CP0Register::DPStart, GPR::S1);
+        assembler.write_mtc0(CP0Register::DPEnd, GPR::S2);
+        assembler.write_break();
+
+        RSP::clear_broke();
+        RSP::run_and_wait(0);
+        wait_for_completion(end, DP_STATUS_COMMAND_BUFFER_READY)?;
+
+        soft_assert_eq(RDP::start(), start, "DP START after being written via RSP MTC0")?;
+        soft_assert_eq(RDP::end(), end, "DP END after being written via RSP MTC0")?;
+        soft_assert_eq(RDP::status() & DP_STATUS_XBUS, 0, "DP STATUS: XBUS should be cleared via RSP MTC0")?;
+        soft_assert_eq(framebuffer.read(0), RGBA5551::RED, "Framebuffer should be filled with RED")?;
+
+        Ok(())
+    }
+}
+
+pub struct RSPStartsDisplayListFromDMEM {}
+
+impl Test for RSPStartsDisplayListFromDMEM {
+    fn name(&self) -> &str { "RSP COP0: Start display list (DMEM, xbus)" }
+
+    fn level(&self) -> Level { Level::RDPBasic }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const WIDTH: usize = 8;
+        const HEIGHT: usize = 8;
+        const DMEM_START: u32 = 0x800;
+        let mut framebuffer = UncachedHeapMemory::<RGBA5551>::new_with_init_value(WIDTH * HEIGHT, RGBA5551::BLACK);
+        let mut display_list = assemble_fill(&mut framebuffer, WIDTH, HEIGHT, RGBA5551::GREEN, 1);
+        let length = (display_list.end() - display_list.start()) as u32;
+        let dmem_end = DMEM_START + length;
+
+        RSP::start_dma_cpu_to_sp(display_list.start() as *const u8, DMEM_START, length - 1);
+        RSP::wait_until_dma_completed();
+
+        let mut assembler = RSPAssembler::new(0);
+        assembler.write_li(GPR::S0, DP_SET_STATUS_SET_XBUS);
+        assembler.write_li(GPR::S1, DMEM_START);
+        assembler.write_li(GPR::S2, dmem_end);
+        assembler.write_mtc0(CP0Register::DPStatus, GPR::S0);
+        assembler.write_mtc0(CP0Register::DPStart, GPR::S1);
+        assembler.write_mtc0(CP0Register::DPEnd, GPR::S2);
+        assembler.write_break();
+
+        RSP::clear_broke();
+        RSP::run_and_wait(0);
+        let result = wait_for_completion(dmem_end, DP_STATUS_COMMAND_BUFFER_READY | DP_STATUS_XBUS);
+        let status = RDP::status();
+
+        unsafe { RDP::set_status(DP_SET_STATUS_CLEAR_XBUS); }
+        result?;
+
+        soft_assert_eq(status & DP_STATUS_XBUS, DP_STATUS_XBUS, "DP STATUS: XBUS should be set via RSP MTC0")?;
+        soft_assert_eq(RDP::start(), DMEM_START, "DP START after being written via RSP MTC0")?;
+        soft_assert_eq(RDP::end(), dmem_end, "DP END after being written via RSP MTC0")?;
+        soft_assert_eq(framebuffer.read(0), RGBA5551::GREEN, "Framebuffer should be filled with GREEN")?;
+
+        Ok(())
+    }
+}
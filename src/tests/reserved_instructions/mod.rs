@@ -0,0 +1,139 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::assembler::Opcode;
+use crate::cop0::{Cause, CauseException, preset_cause_to_copindex2};
+use crate::exception_handler::expect_exception;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq;
+use crate::tests::uncached_code;
+
+// Every encoding that isn't a valid instruction is expected to raise a Reserved Instruction exception
+// (instead of being silently treated as a NOP). The slots below are the ones that are missing from the
+// Opcode, SpecialOpcode, RegimmOpcode and Cop1Opcode enums (plus COP0, which doesn't have an enum).
+// The COP2 load/stores (LWC2 etc.) are covered by cop_unusable instead.
+//
+// Each instruction is run through uncached_code.
+
+const RESERVED_PRIMARY_OPCODES: [u32; 6] = [28, 29, 30, 31, 51, 59];
+
+const RESERVED_SPECIAL_FUNCTIONS: [u32; 12] = [1, 5, 10, 11, 14, 21, 40, 41, 53, 55, 57, 61];
+
+const RESERVED_REGIMM_RT: [u32; 18] = [4, 5, 6, 7, 13, 15, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31];
+
+/// COP0 rs slots. 0/1/4/5 are (D)MFC0/(D)MTC0, 8 is BC0 and 16 and up are the CO instructions
+const RESERVED_COP0_RS: [u32; 11] = [2, 3, 6, 7, 9, 10, 11, 12, 13, 14, 15];
+
+/// COP0 CO functions that exist: TLBR, TLBWI, TLBWR, TLBP, ERET
+const VALID_COP0_FUNCTIONS: [u32; 5] = [1, 2, 6, 8, 24];
+
+/// COP1 rs slots. DCFC1/DCTC1 (3/7) are excluded, as they raise an unimplemented operation exception instead
+const RESERVED_COP1_RS: [u32; 19] = [9, 10, 11, 12, 13, 14, 15, 18, 19, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31];
+
+fn with_and_without_delay(instructions: impl Iterator<Item=u32>) -> Vec<Box<dyn Any>> {
+    let mut result: Vec<Box<dyn Any>> = Vec::new();
+    for instruction in instructions {
+        result.push(Box::new((false, instruction)));
+        result.push(Box::new((true, instruction)));
+    }
+    result
+}
+
+/// Executes the instruction (either by itself or within the delay slot of a BEQ) and ensures that it
+/// causes a Reserved Instruction exception
+fn test_reserved_instruction(value: &Box<dyn Any>) -> Result<(), String> {
+    let (delay, instruction) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+
+    let mut code = uncached_code::write_instruction(instruction, delay);
+    let address = uncached_code::address(&mut code);
+
+    preset_cause_to_copindex2()?;
+
+    let exception_context = expect_exception(CauseException::RI, if delay { 2 } else { 1 }, || {
+        uncached_code::call(address, [0, 0]);
+        Ok(())
+    })?;
+
+    soft_assert_eq(exception_context.k0_exception_vector, 0xFFFFFFFF_80000180, "Exception Vector")?;
+    soft_assert_eq(exception_context.exceptpc, address, "ExceptPC")?;
+    soft_assert_eq(exception_context.cause, Cause::new().with_exception(CauseException::RI).with_branch_delay(delay), "Cause")?;
+    soft_assert_eq(exception_context.status, 0x24000002, "Status")?;
+
+    Ok(())
+}
+
+pub struct ReservedPrimaryOpcodes {}
+
+impl Test for ReservedPrimaryOpcodes {
+    fn name(&self) -> &str { "Reserved instructions (primary opcodes)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        with_and_without_delay(RESERVED_PRIMARY_OPCODES.iter().map(|op| op << 26))
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_reserved_instruction(value) }
+}
+
+pub struct ReservedSpecialFunctions {}
+
+impl Test for ReservedSpecialFunctions {
+    fn name(&self) -> &str { "Reserved instructions (SPECIAL)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        with_and_without_delay(RESERVED_SPECIAL_FUNCTIONS.iter().map(|function| ((Opcode::SPECIAL as u32) << 26) | function))
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_reserved_instruction(value) }
+}
+
+pub struct ReservedRegimm {}
+
+impl Test for ReservedRegimm {
+    fn name(&self) -> &str { "Reserved instructions (REGIMM)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        with_and_without_delay(RESERVED_REGIMM_RT.iter().map(|rt| ((Opcode::REGIMM as u32) << 26) | (rt << 16)))
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_reserved_instruction(value) }
+}
+
+pub struct ReservedCOP0 {}
+
+impl Test for ReservedCOP0 {
+    fn name(&self) -> &str { "Reserved instructions (COP0)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        let rs_slots = RESERVED_COP0_RS.iter().map(|rs| ((Opcode::COP0 as u32) << 26) | (rs << 21));
+        let co_functions = (0..64u32)
+            .filter(|function| !VALID_COP0_FUNCTIONS.contains(function))
+            .map(|function| ((Opcode::COP0 as u32) << 26) | (1 << 25) | function);
+        with_and_without_delay(rs_slots.chain(co_functions))
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_reserved_instruction(value) }
+}
+
+pub struct ReservedCOP1 {}
+
+impl Test for ReservedCOP1 {
+    fn name(&self) -> &str { "Reserved instructions (COP1)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        with_and_without_delay(RESERVED_COP1_RS.iter().map(|rs| ((Opcode::COP1 as u32) << 26) | (rs << 21)))
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_reserved_instruction(value) }
+}
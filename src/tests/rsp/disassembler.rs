@@ -0,0 +1,52 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::rsp::rsp_assembler::{CP0Register, E, Element, GPR, RSPAssembler, VR, VSARAccumulator};
+use crate::rsp::rsp_disassembler::disassemble;
+use crate::rsp::spmem::SPMEM;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+
+/// Assembles a few instructions and checks that the disassembler (used to dump RSP programs of failing tests)
+/// turns them back into the expected text. The RSP isn't started
+pub struct Disassembler {}
+
+impl Test for Disassembler {
+    fn name(&self) -> &str { "RSP disassembler" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // Each instruction is written at 4 * its index. Note that vector instructions are printed as "vd, vs, vt[e]"
+        // while RSPAssembler takes them as (vd, vt, vs, e)
+        let instructions: [(&str, fn(&mut RSPAssembler)); 9] = [
+            ("ADDIU S0, S1, -4", |assembler| assembler.write_addiu(GPR::S0, GPR::S1, -4)),
+            ("BEQ A1, A0, 0x014", |assembler| assembler.write_beq(GPR::A0, GPR::A1, 3)),
+            ("MFC0 T0, DmaBusy", |assembler| assembler.write_mfc0(CP0Register::DmaBusy, GPR::T0)),
+            ("MTC0 T1, SPAddress", |assembler| assembler.write_mtc0(CP0Register::SPAddress, GPR::T1)),
+            ("LQV V3[_0], -0x20(S0)", |assembler| assembler.write_lqv(VR::V3, E::_0, -0x20, GPR::S0)),
+            ("SQV V4[_8], -0x10(T2)", |assembler| assembler.write_sqv(VR::V4, E::_8, -0x10, GPR::T2)),
+            ("VMULF V1, V3, V2[_5]", |assembler| assembler.write_vmulf(VR::V1, VR::V2, VR::V3, Element::_5)),
+            ("VRCP V5[2], V6[_3]", |assembler| assembler.write_vrcp(VR::V5, VR::V6, VR::V2, Element::_3)),
+            ("VSAR V7, Mid", |assembler| assembler.write_vsar(VR::V7, VSARAccumulator::Mid)),
+        ];
+
+        let mut assembler = RSPAssembler::new(0);
+        for (_, write) in instructions.iter() {
+            write(&mut assembler);
+        }
+
+        for (i, (expected, _)) in instructions.iter().enumerate() {
+            let pc = i << 2;
+            let instruction = SPMEM::read(0x1000 | pc);
+            soft_assert_eq2(disassemble(pc, instruction).as_str(), *expected, || format!("Disassembly of 0x{:08X} at 0x{:03X}", instruction, pc))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::rsp::rsp::RSP;
+use crate::rsp::rsp_assembler::{CP0Register, GPR, RSPAssembler};
+use crate::rsp::spmem::SPMEM;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2};
+use crate::tests::sp_memory::dma::{create_source, fill_spmem, RDRAM_UNTOUCHED, SPMEM_UNTOUCHED};
+use crate::uncached_memory::UncachedHeapMemory;
+
+// DMAs started by the RSP itself (as microcode does it):
+// - SPAddress, DRAMAddress and ReadLength/WriteLength are written through MTC0. Writing the length starts the DMA
+// - The RSP keeps running while the DMA is in flight and can poll DmaBusy through MFC0
+// - The CPU and the RSP share the same DMA engine. A DMA started by the RSP while a CPU DMA is running is
+//   queued behind it (and vice versa)
+
+/// The RSP program is placed at the start of IMEM. Everything the tests DMA stays clear of it
+const PROGRAM_START: usize = 0x000;
+
+/// Writes the code that starts a DMA from the RSP. Uses S0..S2
+fn write_start_dma(assembler: &mut RSPAssembler, spmem: u32, dram: u32, length_register: CP0Register, length: u32) {
+    assembler.write_li(GPR::S0, spmem);
+    assembler.write_li(GPR::S1, dram);
+    assembler.write_li(GPR::S2, length);
+    assembler.write_mtc0(CP0Register::SPAddress, GPR::S0);
+    assembler.write_mtc0(CP0Register::DRAMAddress, GPR::S1);
+    assembler.write_mtc0(length_register, GPR::S2);
+}
+
+/// Writes a loop that spins until DmaBusy is clear. Uses T0
+fn write_wait_for_dma(assembler: &mut RSPAssembler) {
+    let wait_loop = assembler.get_jump_target();
+    assembler.write_mfc0(CP0Register::DmaBusy, GPR::T0);
+    assembler.write_bgtz_backwards(GPR::T0, &wait_loop);
+    assembler.write_nop();
+}
+
+/// Writes the code that stores SPAddress and DRAMAddress into DMEM. Uses T1 and T2
+fn write_store_addresses(assembler: &mut RSPAssembler, dmem: i16) {
+    assembler.write_mfc0(CP0Register::SPAddress, GPR::T1);
+    assembler.write_mfc0(CP0Register::DRAMAddress, GPR::T2);
+    assembler.write_sw(GPR::T1, GPR::R0, dmem);
+    assembler.write_sw(GPR::T2, GPR::R0, dmem + 4);
+}
+
+pub struct RSPDMAIntoDMEM {}
+
+impl Test for RSPDMAIntoDMEM {
+    fn name(&self) -> &str { "RSP: DMA RDRAM -> DMEM (started by RSP)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const SPMEM_TARGET: u32 = 0x100;
+        const WORDS: usize = 0x40;
+        let mut source = create_source(WORDS, 0x3C000000);
+        let source_address = source.start_phyiscal() as u32;
+        fill_spmem(0..0x1000, SPMEM_UNTOUCHED);
+
+        let mut assembler = RSPAssembler::new(PROGRAM_START);
+        write_start_dma(&mut assembler, SPMEM_TARGET, source_address, CP0Register::ReadLength, (WORDS * 4 - 1) as u32);
+        write_wait_for_dma(&mut assembler);
+        write_store_addresses(&mut assembler, 0x0);
+        // Read back the first and last word through the RSP to ensure it sees the data right away
+        assembler.write_lw(GPR::T3, GPR::R0, SPMEM_TARGET as i16);
+        assembler.write_lw(GPR::T4, GPR::R0, (SPMEM_TARGET as usize + (WORDS - 1) * 4) as i16);
+        assembler.write_sw(GPR::T3, GPR::R0, 0x8);
+        assembler.write_sw(GPR::T4, GPR::R0, 0xC);
+        assembler.write_break();
+
+        RSP::clear_broke();
+        RSP::run_and_wait(PROGRAM_START);
+
+        soft_assert_eq(SPMEM::read(0x0), SPMEM_TARGET + (WORDS * 4) as u32, "SP address after DMA (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(0x4), source_address + (WORDS * 4) as u32, "DRAM address after DMA (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(0x8), 0x3C000000, "First word after DMA (read via RSP LW)")?;
+        soft_assert_eq(SPMEM::read(0xC), 0x3C000000 | (WORDS as u32 - 1), "Last word after DMA (read via RSP LW)")?;
+
+        for offset in (0x10..0x1000).step_by(4) {
+            let expected = if (offset >= SPMEM_TARGET as usize) && (offset < SPMEM_TARGET as usize + WORDS * 4) {
+                0x3C000000 | ((offset - SPMEM_TARGET as usize) >> 2) as u32
+            } else {
+                SPMEM_UNTOUCHED
+            };
+            soft_assert_eq2(SPMEM::read(offset), expected, || format!("DMEM[0x{:03X}] after DMA", offset))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct RSPDMAIntoIMEM {}
+
+impl Test for RSPDMAIntoIMEM {
+    fn name(&self) -> &str { "RSP: DMA RDRAM -> IMEM (started by RSP)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // Far away from the program at the start of IMEM
+        const SPMEM_TARGET: u32 = 0x1800;
+        const WORDS: usize = 0x20;
+        let mut source = create_source(WORDS, 0x4D000000);
+        let source_address = source.start_phyiscal() as u32;
+        fill_spmem(0x1800..0x1900, SPMEM_UNTOUCHED);
+
+        let mut assembler = RSPAssembler::new(PROGRAM_START);
+        write_start_dma(&mut assembler, SPMEM_TARGET, source_address, CP0Register::ReadLength, (WORDS * 4 - 1) as u32);
+        write_wait_for_dma(&mut assembler);
+        write_store_addresses(&mut assembler, 0x0);
+        assembler.write_break();
+
+        RSP::clear_broke();
+        RSP::run_and_wait(PROGRAM_START);
+
+        soft_assert_eq(SPMEM::read(0x0), SPMEM_TARGET + (WORDS * 4) as u32, "SP address after DMA (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(0x4), source_address + (WORDS * 4) as u32, "DRAM address after DMA (read via RSP MFC0)")?;
+
+        for offset in (0x1800..0x1900).step_by(4) {
+            let expected = if offset < SPMEM_TARGET as usize + WORDS * 4 {
+                0x4D000000 | ((offset - SPMEM_TARGET as usize) >> 2) as u32
+            } else {
+                SPMEM_UNTOUCHED
+            };
+            soft_assert_eq2(SPMEM::read(offset), expected, || format!("IMEM[0x{:03X}] after DMA", offset - 0x1000))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct RSPDMAFromDMEM {}
+
+impl Test for RSPDMAFromDMEM {
+    fn name(&self) -> &str { "RSP: DMA DMEM -> RDRAM (started by RSP)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        const SPMEM_SOURCE: u32 = 0x200;
+        const WORDS: usize = 0x40;
+        // Two extra words to ensure the DMA doesn't write past its end
+        let mut target = UncachedHeapMemory::<u32>::new_with_init_value(WORDS + 2, RDRAM_UNTOUCHED);
+        let target_address = target.start_phyiscal() as u32;
+
+        let mut assembler = RSPAssembler::new(PROGRAM_START);
+        // The RSP generates the data itself before sending it out
+        assembler.write_li(GPR::S3, 0x5E000000);
+        assembler.write_li(GPR::S4, SPMEM_SOURCE);
+        assembler.write_li(GPR::S5, WORDS as u32);
+        let fill_loop = assembler.get_jump_target();
+        assembler.write_sw(GPR::S3, GPR::S4, 0);
+        assembler.write_addiu(GPR::S3, GPR::S3, 1);
+        assembler.write_addiu(GPR::S5, GPR::S5, -1);
+        assembler.write_bgtz_backwards(GPR::S5, &fill_loop);
+        assembler.write_addiu(GPR::S4, GPR::S4, 4);
+
+        write_start_dma(&mut assembler, SPMEM_SOURCE, target_address, CP0Register::WriteLength, (WORDS * 4 - 1) as u32);
+        write_wait_for_dma(&mut assembler);
+        write_store_addresses(&mut assembler, 0x0);
+        assembler.write_break();
+
+        RSP::clear_broke();
+        RSP::run_and_wait(PROGRAM_START);
+
+        soft_assert_eq(SPMEM::read(0x0), SPMEM_SOURCE + (WORDS * 4) as u32, "SP address after DMA (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(0x4), target_address + (WORDS * 4) as u32, "DRAM address after DMA (read via RSP MFC0)")?;
+
+        for i in 0..WORDS + 2 {
+            let expected = if i < WORDS { 0x5E000000 | (i as u32) } else { RDRAM_UNTOUCHED };
+            soft_assert_eq2(target.read(i), expected, || format!("RDRAM word {} after DMA", i))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct RSPDMAWithConcurrentCPUDMA {}
+
+impl Test for RSPDMAWithConcurrentCPUDMA {
+    fn name(&self) -> &str { "RSP: DMA started by RSP while a CPU DMA is running" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // The CPU starts a large DMA and then immediately starts the RSP, which starts its own DMA. The CPU DMA is
+        // large enough to still be running at that point, so the RSP DMA is queued behind it (which the RSP sees
+        // through DmaFull). Both DMAs arrive and the address registers point to the end of the RSP DMA
+        const CPU_SPMEM: u32 = 0x000;
+        const CPU_WORDS: usize = 0x200;
+        const RSP_SPMEM: u32 = 0x800;
+        const RSP_WORDS: usize = 0x40;
+        const RESULTS: i16 = 0xF00;
+        let mut cpu_source = create_source(CPU_WORDS, 0x6F000000);
+        let mut rsp_source = create_source(RSP_WORDS, 0x70000000);
+        let rsp_source_address = rsp_source.start_phyiscal() as u32;
+        fill_spmem(0..0x1000, SPMEM_UNTOUCHED);
+
+        let mut assembler = RSPAssembler::new(PROGRAM_START);
+        write_start_dma(&mut assembler, RSP_SPMEM, rsp_source_address, CP0Register::ReadLength, (RSP_WORDS * 4 - 1) as u32);
+        // Record the flags right after queueing the RSP DMA
+        assembler.write_mfc0(CP0Register::DmaFull, GPR::T3);
+        assembler.write_mfc0(CP0Register::DmaBusy, GPR::T4);
+        assembler.write_sw(GPR::T3, GPR::R0, RESULTS + 8);
+        assembler.write_sw(GPR::T4, GPR::R0, RESULTS + 12);
+        write_wait_for_dma(&mut assembler);
+        write_store_addresses(&mut assembler, RESULTS);
+        assembler.write_break();
+
+        RSP::clear_broke();
+        RSP::start_dma_cpu_to_sp(cpu_source.start_phyiscal() as *const u8, CPU_SPMEM, (CPU_WORDS * 4 - 1) as u32);
+        RSP::start_running(PROGRAM_START);
+        RSP::wait_until_rsp_is_halted_and_dma_completed();
+
+        soft_assert_eq(SPMEM::read(RESULTS as usize), RSP_SPMEM + (RSP_WORDS * 4) as u32, "SP address after both DMAs (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(RESULTS as usize + 4), rsp_source_address + (RSP_WORDS * 4) as u32, "DRAM address after both DMAs (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(RESULTS as usize + 8), 1, "DMA_FULL after the RSP queued its DMA behind the CPU one (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(RESULTS as usize + 12), 1, "DMA_BUSY after the RSP queued its DMA behind the CPU one (read via RSP MFC0)")?;
+        soft_assert_eq(RSP::dma_busy(), 0, "DMA_BUSY after both DMAs")?;
+
+        for offset in (0..RESULTS as usize).step_by(4) {
+            let expected = if offset < CPU_SPMEM as usize + CPU_WORDS * 4 {
+                0x6F000000 | ((offset - CPU_SPMEM as usize) >> 2) as u32
+            } else if (offset >= RSP_SPMEM as usize) && (offset < RSP_SPMEM as usize + RSP_WORDS * 4) {
+                0x70000000 | ((offset - RSP_SPMEM as usize) >> 2) as u32
+            } else {
+                SPMEM_UNTOUCHED
+            };
+            soft_assert_eq2(SPMEM::read(offset), expected, || format!("DMEM[0x{:03X}] after DMAs", offset))?;
+        }
+
+        Ok(())
+    }
+}
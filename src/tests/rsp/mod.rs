@@ -10,6 +10,8 @@ use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_neq};
 
 pub mod registers;
+pub mod disassembler;
+pub mod dma;
 pub mod op_addi;
 pub mod op_addiu;
 pub mod op_and;
@@ -63,8 +65,10 @@ pub mod op_vrndp;
 pub mod op_vsar;
 pub mod op_xor;
 pub mod op_xori;
+pub mod randomized;
 pub mod stresstests;
 pub mod stresstests_div;
+pub mod timing;
 pub mod wrap_around;
 
 /// Ensure that the PC reg is properly masked with 0xFFC when being written to
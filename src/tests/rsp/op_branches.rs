@@ -7,6 +7,7 @@ use core::any::Any;
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{GPR, RSPAssembler};
 use crate::rsp::spmem::SPMEM;
+use crate::rsp_asm;
 use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::soft_assert_eq;
 
@@ -394,3 +395,77 @@ impl Test for BGEZALTestRA {
         }
     }
 }
+
+pub struct BGTZBackwardsLoop {}
+
+impl Test for BGTZBackwardsLoop {
+    fn name(&self) -> &str { "RSP BGTZ (loop back to an earlier label)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // The label is defined before the branch that uses it, so the branch offset has to be negative
+        let mut assembler = RSPAssembler::new(0);
+        rsp_asm!(assembler,
+            li S1, 4;
+            li S2, 0;
+            li S3, 0;
+          loop_start:
+            addiu S2, S2, 3;
+            addiu S1, S1, -1;
+            bgtz S1, loop_start;
+            addiu S3, S3, 1;
+
+            sw S1, 0x0(R0);
+            sw S2, 0x4(R0);
+            sw S3, 0x8(R0);
+            break;
+        );
+
+        RSP::run_and_wait(0);
+
+        soft_assert_eq(SPMEM::read(0x0), 0, "Loop counter after the loop")?;
+        soft_assert_eq(SPMEM::read(0x4), 12, "Sum of the loop body (4 iterations)")?;
+        soft_assert_eq(SPMEM::read(0x8), 4, "Delay slot executions")?;
+
+        Ok(())
+    }
+}
+
+pub struct BEQForwardLabel {}
+
+impl Test for BEQForwardLabel {
+    fn name(&self) -> &str { "RSP BEQ (forward to a later label)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // The label is defined after the branches that use it, so they have to be patched once it is known
+        let mut assembler = RSPAssembler::new(0);
+        rsp_asm!(assembler,
+            li S1, 0;
+            li S2, 1;
+            bne S2, R0, skip;
+            addiu S1, S1, 1;
+            addiu S1, S1, 2;
+          skip:
+            beq R0, R0, done;
+            addiu S1, S1, 4;
+            addiu S1, S1, 8;
+            addiu S1, S1, 16;
+          done:
+            sw S1, 0x0(R0);
+            break;
+        );
+
+        RSP::run_and_wait(0);
+
+        soft_assert_eq(SPMEM::read(0x0), 5, "Only the delay slots are expected to run, the instructions until the labels are skipped")?;
+
+        Ok(())
+    }
+}
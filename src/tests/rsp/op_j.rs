@@ -6,6 +6,7 @@ use core::any::Any;
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{GPR, RSPAssembler};
 use crate::rsp::spmem::SPMEM;
+use crate::rsp_asm;
 use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::soft_assert_eq;
 
@@ -55,3 +56,39 @@ impl Test for J {
         Ok(())
     }
 }
+
+pub struct JToLabel {}
+
+impl Test for JToLabel {
+    fn name(&self) -> &str { "RSP J (forward to a later label)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let mut assembler = RSPAssembler::new(0);
+        rsp_asm!(assembler,
+            // 0x000:
+            ori RA, R0, 0;
+            ori A0, R0, 1;
+            // 0x008: The jump target is only known when the block ends
+            j target;
+            ori A0, A0, 2;
+            // 0x010: This is skipped
+            ori A0, A0, 4;
+          target:
+            ori A0, A0, 8;
+            sw A0, 0x0(R0);
+            sw RA, 0x4(R0);
+            break;
+        );
+
+        RSP::run_and_wait(0);
+
+        soft_assert_eq(SPMEM::read(0x0), 11, "J is expected to handle the delay slot in 0x00C and jump to 0x014, skipping 0x010")?;
+        soft_assert_eq(SPMEM::read(0x4), 0, "J is not expected to change the RA register")?;
+
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use alloc::vec::Vec;
 use core::any::Any;
 
 use crate::math::vector::Vector;
+use crate::math::vector_unit::VectorUnit;
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{CP2FlagsRegister, E, Element, GPR, RSPAssembler, VR, VSARAccumulator};
 use crate::rsp::spmem::SPMEM;
@@ -53,11 +54,109 @@ impl EmulationRegisters {
     }
 }
 
+/// The instruction on the reference model in math::vector_unit, with arguments in the same order as in RSPAssembler
+type ModelInstruction = fn(&mut VectorUnit, VR, VR, VR, Element);
+
 trait TestCase {
     fn emit(&self, assembler: &mut RSPAssembler, target: VR, source1: VR, source2: VR, e: Element);
     fn emulate(&self, registers: &mut VectorElements);
 }
 
+/// An instruction that is also implemented by the reference model. The RSP test checks the hardware against the
+/// emulation while VectorUnitModel runs the same inputs through the model instead
+struct ModelledCase {
+    test_case: Box<dyn TestCase>,
+    model: ModelInstruction,
+    vector1: Vector,
+    vector2: Vector,
+    /// Whether to run a second time with vector2 set to all zeroes
+    vector2_variations: bool,
+}
+
+impl ModelledCase {
+    fn run_on_rsp(&self) -> Result<(), String> {
+        if self.vector2_variations {
+            run_test_with_emulation_all_flags_and_elements_vector2_variations(self.test_case.as_ref(), self.vector1, self.vector2)
+        } else {
+            run_test_with_emulation_all_flags_and_elements(self.test_case.as_ref(), self.vector1, self.vector2)
+        }
+    }
+
+    fn run_on_model(&self) -> Result<(), String> {
+        run_model_with_emulation_all_flags_and_elements(self.test_case.as_ref(), self.model, self.vector1, self.vector2)?;
+        if self.vector2_variations {
+            run_model_with_emulation_all_flags_and_elements(self.test_case.as_ref(), self.model, self.vector1, self.vector2.copy_with_broadcast_16(0))?;
+        }
+
+        Ok(())
+    }
+}
+
+// The multiplication at the start of run_test_with_emulation_whole_reg leaves the accumulators as follows:
+//    high  mid  low
+// 0: 3FFF 4000 0001
+// 1: FFFF FFFF 8001
+// 2: 0007 FFF7 FFF0
+// 3: 0000 0000 0000
+// 4: FFFF FFFF FFFF
+// 5: 0000 0000 0001
+// 6: 3FFF 4000 0001
+// 7: 3FFF C000 0000
+const ACC_HIGH: Vector = Vector::from_u16([0x3FFF, 0xFFFF, 0x0007, 0x0000, 0xFFFF, 0x0000, 0x3FFF, 0x3FFF]);
+const ACC_MID: Vector = Vector::from_u16([0x4000, 0xFFFF, 0xFFF7, 0x0000, 0xFFFF, 0x0000, 0x4000, 0xC000]);
+const ACC_LOW_DEFAULT: Vector = Vector::from_u16([0x0001, 0x8001, 0xFFF0, 0x0000, 0xFFFF, 0x0001, 0x0001, 0x0000]);
+
+// What the target register holds before the instruction runs. This is only accurate when V2 is the target reg
+const TARGET_REGISTER_DEFAULT: Vector = Vector::from_u16([0xFFFF, 0x8001, 0xFFFF, 0, 0xFFFF, 0x0001, 0xFFFF, 0xFFFF]);
+
+const REGISTER_CONFIGURATIONS: [(i16, VR, VR, VR); 5] = [
+    // all three different
+    (0x90 + 80 * 0, VR::V2, VR::V4, VR::V5),
+    // target == source1
+    (0x90 + 80 * 1, VR::V6, VR::V6, VR::V7),
+    // target == source2
+    (0x90 + 80 * 2, VR::V8, VR::V9, VR::V8),
+    // source1==source2
+    (0x90 + 80 * 3, VR::V10, VR::V11, VR::V11),
+    // all three the same
+    (0x90 + 80 * 4, VR::V12, VR::V12, VR::V12),
+];
+
+/// Runs the emulation twice: Once with the two input vectors and once with vector1 as both inputs (which is what
+/// happens when source1==source2)
+fn emulate_inputs<FEmulate: Fn(Element, &mut EmulationRegisters)>(
+    vco: u16, vcc: u16, vce: u8,
+    e: Element,
+    emulate: &FEmulate,
+    vector1: Vector, vector2: Vector) -> (EmulationRegisters, EmulationRegisters) {
+
+    let mut emulation_registers = EmulationRegisters {
+        source_register1: vector1,
+        source_register2: vector2,
+        target_register: TARGET_REGISTER_DEFAULT,
+        accum_0_16: ACC_LOW_DEFAULT,
+        vco,
+        vcc,
+        vce,
+    };
+
+    emulate(e, &mut emulation_registers);
+
+    let mut emulation_registers_same_input = EmulationRegisters {
+        source_register1: vector1,
+        source_register2: vector1,
+        target_register: TARGET_REGISTER_DEFAULT,
+        accum_0_16: ACC_LOW_DEFAULT,
+        vco,
+        vcc,
+        vce,
+    };
+
+    emulate(e, &mut emulation_registers_same_input);
+
+    (emulation_registers, emulation_registers_same_input)
+}
+
 fn run_test_with_emulation_whole_reg<FEmit: Fn(&mut RSPAssembler, VR, VR, VR, Element), FEmulate: Fn(Element, &mut EmulationRegisters)>(
     vco: u16, vcc: u16, vce: u8,
     e: Element,
@@ -79,38 +178,12 @@ fn run_test_with_emulation_whole_reg<FEmit: Fn(&mut RSPAssembler, VR, VR, VR, El
     // Assemble RSP program
     let mut assembler = RSPAssembler::new(0);
 
-    // Do a multiplication to ensure that the accumulator bits are set
+    // Do a multiplication to ensure that the accumulator bits are set (to ACC_HIGH, ACC_MID and ACC_LOW_DEFAULT)
     assembler.write_lqv(VR::V0, E::_0, 0x000, GPR::R0);
     assembler.write_lqv(VR::V1, E::_0, 0x010, GPR::R0);
     assembler.write_vmudh(VR::V2, VR::V0, VR::V1, Element::All);
     assembler.write_vmadn(VR::V2, VR::V0, VR::V1, Element::All);
 
-    // The accumulators will now be as follows:
-    //    high  mid  low
-    // 0: 3FFF 4000 0001
-    // 1: FFFF FFFF 8001
-    // 2: 0007 FFF7 FFF0
-    // 3: 0000 0000 0000
-    // 4: FFFF FFFF FFFF
-    // 5: 0000 0000 0001
-    // 6: 3FFF 4000 0001
-    // 7: 3FFF C000 0000
-    const ACC_HIGH: Vector = Vector::from_u16([0x3FFF, 0xFFFF, 0x0007, 0x0000, 0xFFFF, 0x0000, 0x3FFF, 0x3FFF]);
-    const ACC_MID: Vector = Vector::from_u16([0x4000, 0xFFFF, 0xFFF7, 0x0000, 0xFFFF, 0x0000, 0x4000, 0xC000]);
-
-    const REGISTER_CONFIGURATIONS: [(i16, VR, VR, VR); 5] = [
-        // all three different
-        (0x90 + 80 * 0, VR::V2, VR::V4, VR::V5),
-        // target == source1
-        (0x90 + 80 * 1, VR::V6, VR::V6, VR::V7),
-        // target == source2
-        (0x90 + 80 * 2, VR::V8, VR::V9, VR::V8),
-        // source1==source2
-        (0x90 + 80 * 3, VR::V10, VR::V11, VR::V11),
-        // all three the same
-        (0x90 + 80 * 4, VR::V12, VR::V12, VR::V12),
-    ];
-
     // We'll run the test several times with different source/target configurations (so that source and target are also the same).
     for (result_address, target, source1, source2) in REGISTER_CONFIGURATIONS {
         // Set flags
@@ -155,30 +228,7 @@ fn run_test_with_emulation_whole_reg<FEmit: Fn(&mut RSPAssembler, VR, VR, VR, El
     // somewhat expensive so we're making some assumptions:
     // - The first three can be treated the same way (with the exception of complete NOPs, which are handled in the result checker)
     // -
-    const TARGET_REGISTER_DEFAULT: Vector = Vector::from_u16([0xFFFF, 0x8001, 0xFFFF, 0, 0xFFFF, 0x0001, 0xFFFF, 0xFFFF]);
-    let mut emulation_registers = EmulationRegisters {
-        source_register1: vector1,
-        source_register2: vector2,
-        target_register: TARGET_REGISTER_DEFAULT,
-        accum_0_16: Vector::from_u16([0x0001, 0x8001, 0xFFF0, 0x0000, 0xFFFF, 0x0001, 0x0001, 0x0000]),
-        vco,
-        vcc,
-        vce,
-    };
-
-    emulate(e, &mut emulation_registers);
-
-    let mut emulation_registers_same_input = EmulationRegisters {
-        source_register1: vector1,
-        source_register2: vector1,
-        target_register: TARGET_REGISTER_DEFAULT,
-        accum_0_16: Vector::from_u16([0x0001, 0x8001, 0xFFF0, 0x0000, 0xFFFF, 0x0001, 0x0001, 0x0000]),
-        vco,
-        vcc,
-        vce,
-    };
-
-    emulate(e, &mut emulation_registers_same_input);
+    let (emulation_registers, emulation_registers_same_input) = emulate_inputs(vco, vcc, vce, e, &emulate, vector1, vector2);
 
     RSP::wait_until_rsp_is_halted();
 
@@ -200,42 +250,53 @@ fn run_test_with_emulation_whole_reg<FEmit: Fn(&mut RSPAssembler, VR, VR, VR, El
     Ok(())
 }
 
-fn run_test_with_emulation_all_flags_and_elements(
-    test_case: &dyn TestCase,
+/// Same as run_test_with_emulation_whole_reg, but the reference model takes the place of the RSP
+fn run_model_with_emulation_whole_reg<FEmulate: Fn(Element, &mut EmulationRegisters)>(
+    vco: u16, vcc: u16, vce: u8,
+    e: Element,
+    model: ModelInstruction,
+    emulate: FEmulate,
     vector1: Vector, vector2: Vector) -> Result<(), String> {
+
+    let (emulation_registers, emulation_registers_same_input) = emulate_inputs(vco, vcc, vce, e, &emulate, vector1, vector2);
+
+    for (_, target, source1, source2) in REGISTER_CONFIGURATIONS {
+        let expected_result = if source1 == source2 { &emulation_registers_same_input } else { &emulation_registers };
+
+        let mut unit = VectorUnit::new();
+        unit.set_accumulator_to(ACC_HIGH, ACC_MID, ACC_LOW_DEFAULT);
+        unit.set_vco(vco);
+        unit.set_vcc(vcc);
+        unit.set_vce(vce);
+        unit.set_register(target, TARGET_REGISTER_DEFAULT);
+        unit.set_register(source1, vector1);
+        if source1 != source2 {
+            unit.set_register(source2, vector2);
+        }
+        model(&mut unit, target, source1, source2, e);
+
+        if (target == VR::V2) || (expected_result.target_register != TARGET_REGISTER_DEFAULT) {
+            soft_assert_eq_vector(unit.register(target), expected_result.target_register, || format!("Output register (main calculation result) for {:?},{:?},{:?}[{:?}]", target, source1, source2, e))?;
+        }
+        soft_assert_eq2(unit.vco(), expected_result.vco, || format!("VCO after calculation for {:?},{:?},{:?}[{:?}]", target, source1, source2, e))?;
+        soft_assert_eq2(unit.vcc(), expected_result.vcc, || format!("VCC after calculation for {:?},{:?},{:?}[{:?}]", target, source1, source2, e))?;
+        soft_assert_eq2(unit.vce(), expected_result.vce, || format!("VCE after calculation for {:?},{:?},{:?}[{:?}]", target, source1, source2, e))?;
+        soft_assert_eq_vector(unit.accumulator_low(), expected_result.accum_0_16, || format!("Acc[0..16] after calculation for {:?},{:?},{:?}[{:?}]", target, source1, source2, e))?;
+        soft_assert_eq_vector(unit.accumulator_mid(), ACC_MID, || format!("Acc[16..32] after calculation for {:?},{:?},{:?}[{:?}]", target, source1, source2, e))?;
+        soft_assert_eq_vector(unit.accumulator_high(), ACC_HIGH, || format!("Acc[32..48] after calculation for {:?},{:?},{:?}[{:?}]", target, source1, source2, e))?;
+    }
+
+    Ok(())
+}
+
+/// Calls check for every combination of flags and elements
+fn for_all_flags_and_elements<FCheck: FnMut(u16, u16, u8, Element) -> Result<(), String>>(mut check: FCheck) -> Result<(), String> {
     for e in Element::range() {
         // There are five flags: VCO.low, VCO.high, VCC.low, VCC.high, VCE. We can set the bits in a way that four tests are enough to get through all combinations
         // For VCC and VCE, the first bitmask is the one that should test all combinations for a given vector. Throw in two extras to also have some other cases
         for vco in [0x0000, 0x00FF, 0xFF00, 0xFFFF] {
             for (vcc, vce) in [(0b00001111_00110011, 0b10101001), (0, 0), (0xFFFF, 0xFF), (0xFFFF, 0)] {
-                run_test_with_emulation_whole_reg(
-                    vco, vcc, vce, e,
-                    |assembler, target, source1, source2, e| test_case.emit(assembler, target, source1, source2, e),
-                    |e, registers| {
-                        for i in 0..8 {
-                            let mut vector_elements = VectorElements {
-                                source1: registers.source_register1.get16(e.get_effective_element_index(i)),
-                                source2: registers.source_register2.get16(i),
-                                target: registers.target_register.get16(i),
-                                accum_0_16: registers.accum_0_16.get16(i),
-                                vco_low: ((registers.vco >> i) & 1) != 0,
-                                vco_high: ((registers.vco >> (8 + i)) & 1) != 0,
-                                vcc_low: ((registers.vcc >> i) & 1) != 0,
-                                vcc_high: ((registers.vcc >> (8 + i)) & 1) != 0,
-                                vce: ((registers.vce >> i) & 1) != 0,
-                            };
-                            test_case.emulate(&mut vector_elements);
-                            registers.source_register1.set16(i, vector_elements.source1);
-                            registers.source_register2.set16(i, vector_elements.source2);
-                            registers.target_register.set16(i, vector_elements.target);
-                            registers.accum_0_16.set16(i, vector_elements.accum_0_16);
-                            registers.set_vcc_low(i, vector_elements.vcc_low);
-                            registers.set_vcc_high(i, vector_elements.vcc_high);
-                            registers.set_vco_low(i, vector_elements.vco_low);
-                            registers.set_vco_high(i, vector_elements.vco_high);
-                            registers.set_vce(i, vector_elements.vce);
-                        }
-                    }, vector1, vector2)?;
+                check(vco, vcc, vce, e)?;
             }
         }
     }
@@ -243,6 +304,58 @@ fn run_test_with_emulation_all_flags_and_elements(
     Ok(())
 }
 
+/// Applies the per-element emulation of the test case to every element of the registers
+fn emulate_elements(test_case: &dyn TestCase, e: Element, registers: &mut EmulationRegisters) {
+    for i in 0..8 {
+        let mut vector_elements = VectorElements {
+            source1: registers.source_register1.get16(e.get_effective_element_index(i)),
+            source2: registers.source_register2.get16(i),
+            target: registers.target_register.get16(i),
+            accum_0_16: registers.accum_0_16.get16(i),
+            vco_low: ((registers.vco >> i) & 1) != 0,
+            vco_high: ((registers.vco >> (8 + i)) & 1) != 0,
+            vcc_low: ((registers.vcc >> i) & 1) != 0,
+            vcc_high: ((registers.vcc >> (8 + i)) & 1) != 0,
+            vce: ((registers.vce >> i) & 1) != 0,
+        };
+        test_case.emulate(&mut vector_elements);
+        registers.source_register1.set16(i, vector_elements.source1);
+        registers.source_register2.set16(i, vector_elements.source2);
+        registers.target_register.set16(i, vector_elements.target);
+        registers.accum_0_16.set16(i, vector_elements.accum_0_16);
+        registers.set_vcc_low(i, vector_elements.vcc_low);
+        registers.set_vcc_high(i, vector_elements.vcc_high);
+        registers.set_vco_low(i, vector_elements.vco_low);
+        registers.set_vco_high(i, vector_elements.vco_high);
+        registers.set_vce(i, vector_elements.vce);
+    }
+}
+
+fn run_test_with_emulation_all_flags_and_elements(
+    test_case: &dyn TestCase,
+    vector1: Vector, vector2: Vector) -> Result<(), String> {
+    for_all_flags_and_elements(|vco, vcc, vce, e| {
+        run_test_with_emulation_whole_reg(
+            vco, vcc, vce, e,
+            |assembler, target, source1, source2, e| test_case.emit(assembler, target, source1, source2, e),
+            |e, registers| emulate_elements(test_case, e, registers),
+            vector1, vector2)
+    })
+}
+
+fn run_model_with_emulation_all_flags_and_elements(
+    test_case: &dyn TestCase,
+    model: ModelInstruction,
+    vector1: Vector, vector2: Vector) -> Result<(), String> {
+    for_all_flags_and_elements(|vco, vcc, vce, e| {
+        run_model_with_emulation_whole_reg(
+            vco, vcc, vce, e,
+            model,
+            |e, registers| emulate_elements(test_case, e, registers),
+            vector1, vector2)
+    })
+}
+
 fn run_test_with_emulation_all_flags_and_elements_vector2_variations(
     test_case: &dyn TestCase,
     vector1: Vector, vector2: Vector) -> Result<(), String> {
@@ -301,8 +414,14 @@ impl Test for VADD {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VADD {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vadd(target, source1, source2, e); },
                 |elements| {
                     let unclamped = (elements.source1 as i16 as i32) + (elements.source2 as i16 as i32) + elements.vco_low as i32;
@@ -311,10 +430,12 @@ impl Test for VADD {
                     elements.accum_0_16 = unclamped as u16;
                     elements.vco_low = false;
                     elements.vco_high = false;
-                }
-            ).as_ref(),
-            Vector::from_u16([0, 1, 0x8000, 0xFFFF, 0x7fff, 0x8001, 0x8000, 0x0001]),
-            Vector::from_u16([0, 2, 0x7FFF, 0x7FFF, 0x7fff, 0x8001, 0xFFFF, 0xFFFF]))
+                }),
+            model: VectorUnit::vadd,
+            vector1: Vector::from_u16([0, 1, 0x8000, 0xFFFF, 0x7fff, 0x8001, 0x8000, 0x0001]),
+            vector2: Vector::from_u16([0, 2, 0x7FFF, 0x7FFF, 0x7fff, 0x8001, 0xFFFF, 0xFFFF]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -328,8 +449,14 @@ impl Test for VSUB {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VSUB {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vsub(target, source1, source2, e); },
                 |elements| {
                     let unclamped = (elements.source2 as i16 as i32) - (elements.source1 as i16 as i32) - elements.vco_low as i32;
@@ -338,10 +465,12 @@ impl Test for VSUB {
                     elements.accum_0_16 = unclamped as u16;
                     elements.vco_low = false;
                     elements.vco_high = false;
-                }
-            ).as_ref(),
-            Vector::from_u16([0, 1, 0x0010, 0xFFFF, 0x7FFF, 0x7FFF, 0x7FFF, 0x8000]),
-            Vector::from_u16([0, 2, 0x7FFF, 0x7FFF, 0x0000, 0xFFFF, 0xFFFE, 0x7FFF]))
+                }),
+            model: VectorUnit::vsub,
+            vector1: Vector::from_u16([0, 1, 0x0010, 0xFFFF, 0x7FFF, 0x7FFF, 0x7FFF, 0x8000]),
+            vector2: Vector::from_u16([0, 2, 0x7FFF, 0x7FFF, 0x0000, 0xFFFF, 0xFFFE, 0x7FFF]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -355,8 +484,14 @@ impl Test for VABS {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VABS {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vabs(target, source1, source2, e); },
                 |elements| {
                     if (elements.source2 as i16) < 0 {
@@ -374,10 +509,12 @@ impl Test for VABS {
                         elements.accum_0_16 = elements.source1;
                         elements.target = elements.source1;
                     }
-                }
-            ).as_ref(),
-            Vector::from_u16([0x1234, 0x1234, 0x8765, 0x0001, 0xFFFF, 0x0000, 0x7FFF, 0x8000]),
-            Vector::from_u16([0x0000, 0x0002, 0x0002, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF]))
+                }),
+            model: VectorUnit::vabs,
+            vector1: Vector::from_u16([0x1234, 0x1234, 0x8765, 0x0001, 0xFFFF, 0x0000, 0x7FFF, 0x8000]),
+            vector2: Vector::from_u16([0x0000, 0x0002, 0x0002, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -391,8 +528,14 @@ impl Test for VADDC {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VADDC {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vaddc(target, source1, source2, e); },
                 |elements| {
                     let sum32 = (elements.source1 as u32) + (elements.source2 as u32);
@@ -401,9 +544,12 @@ impl Test for VADDC {
                     elements.vco_high = false;
                     elements.target = sum16;
                     elements.accum_0_16 = sum16;
-                }).as_ref(),
-            Vector::from_u16([0x0001, 0x7FFF, 0xF000, 0xF000, 0xFFFF, 0x8000, 0xFFFF, 0xFFFF]),
-            Vector::from_u16([0x0001, 0x7FFF, 0x1000, 0xF001, 0xFFFF, 0xFFFF, 0x8000, 0x0001]))
+                }),
+            model: VectorUnit::vaddc,
+            vector1: Vector::from_u16([0x0001, 0x7FFF, 0xF000, 0xF000, 0xFFFF, 0x8000, 0xFFFF, 0xFFFF]),
+            vector2: Vector::from_u16([0x0001, 0x7FFF, 0x1000, 0xF001, 0xFFFF, 0xFFFF, 0x8000, 0x0001]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -417,8 +563,14 @@ impl Test for VSUBC {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VSUBC {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vsubc(target, source1, source2, e); },
                 |elements| {
                     let result32 = (elements.source2 as i32) - (elements.source1 as i32);
@@ -427,9 +579,12 @@ impl Test for VSUBC {
                     elements.vco_low = result32 < 0;
                     elements.target = result16;
                     elements.accum_0_16 = result16;
-                }).as_ref(),
-            Vector::from_u16([0x0001, 0x0002, 0xFFFF, 0x0000, 0xFFFF, 0x0050, 0x0050, 0x0050]),
-            Vector::from_u16([0x0003, 0x0003, 0x0000, 0xFFFF, 0xFFFF, 0x004F, 0x0050, 0x0051]))
+                }),
+            model: VectorUnit::vsubc,
+            vector1: Vector::from_u16([0x0001, 0x0002, 0xFFFF, 0x0000, 0xFFFF, 0x0050, 0x0050, 0x0050]),
+            vector2: Vector::from_u16([0x0003, 0x0003, 0x0000, 0xFFFF, 0xFFFF, 0x004F, 0x0050, 0x0051]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -563,8 +718,14 @@ impl Test for VLT {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VLT {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vlt(target, source1, source2, e); },
                 |elements| {
                     elements.vcc_high = false;
@@ -574,9 +735,12 @@ impl Test for VLT {
                     elements.vco_high = false;
                     elements.target = if elements.vcc_low { elements.source2 } else { elements.source1 };
                     elements.accum_0_16 = elements.target;
-                }).as_ref(),
-            Vector::from_u16([0x1234, 0x1234, 0x1234, 0xF234, 0xF234, 0xF234, 0xF234, 0x1234]),
-            Vector::from_u16([0x1234, 0x1233, 0x1235, 0xF233, 0xF234, 0xF235, 0x1234, 0xF234]))
+                }),
+            model: VectorUnit::vlt,
+            vector1: Vector::from_u16([0x1234, 0x1234, 0x1234, 0xF234, 0xF234, 0xF234, 0xF234, 0x1234]),
+            vector2: Vector::from_u16([0x1234, 0x1233, 0x1235, 0xF233, 0xF234, 0xF235, 0x1234, 0xF234]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -590,8 +754,14 @@ impl Test for VEQ {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VEQ {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_veq(target, source1, source2, e); },
                 |elements| {
                     elements.vcc_high = false;
@@ -600,9 +770,12 @@ impl Test for VEQ {
                     elements.vco_high = false;
                     elements.target = elements.source1;
                     elements.accum_0_16 = elements.source1;
-                }).as_ref(),
-            Vector::from_u16([0x1234, 0x1234, 0x1234, 0xF234, 0xF234, 0xF234, 0xF234, 0x1234]),
-            Vector::from_u16([0x1234, 0x1233, 0x1235, 0xF233, 0xF234, 0xF235, 0x1234, 0xF234]))
+                }),
+            model: VectorUnit::veq,
+            vector1: Vector::from_u16([0x1234, 0x1234, 0x1234, 0xF234, 0xF234, 0xF234, 0xF234, 0x1234]),
+            vector2: Vector::from_u16([0x1234, 0x1233, 0x1235, 0xF233, 0xF234, 0xF235, 0x1234, 0xF234]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -616,8 +789,14 @@ impl Test for VNE {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VNE {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vne(target, source1, source2, e); },
                 |elements| {
                     elements.vcc_high = false;
@@ -626,9 +805,12 @@ impl Test for VNE {
                     elements.vco_high = false;
                     elements.target = elements.source2;
                     elements.accum_0_16 = elements.source2;
-                }).as_ref(),
-            Vector::from_u16([0x1234, 0x1234, 0x1234, 0xF234, 0xF234, 0xF234, 0xF234, 0x1234]),
-            Vector::from_u16([0x1234, 0x1233, 0x1235, 0xF233, 0xF234, 0xF235, 0x1234, 0xF234]))
+                }),
+            model: VectorUnit::vne,
+            vector1: Vector::from_u16([0x1234, 0x1234, 0x1234, 0xF234, 0xF234, 0xF234, 0xF234, 0x1234]),
+            vector2: Vector::from_u16([0x1234, 0x1233, 0x1235, 0xF233, 0xF234, 0xF235, 0x1234, 0xF234]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -642,8 +824,14 @@ impl Test for VGE {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VGE {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vge(target, source1, source2, e); },
                 |elements| {
                     elements.vcc_high = false;
@@ -653,9 +841,12 @@ impl Test for VGE {
                     elements.vco_high = false;
                     elements.target = if elements.vcc_low { elements.source2 } else { elements.source1 };
                     elements.accum_0_16 = elements.target;
-                }).as_ref(),
-            Vector::from_u16([0x1234, 0x1234, 0x1234, 0xF234, 0xF234, 0xF234, 0xF234, 0x1234]),
-            Vector::from_u16([0x1234, 0x1233, 0x1235, 0xF233, 0xF234, 0xF235, 0x1234, 0xF234]))
+                }),
+            model: VectorUnit::vge,
+            vector1: Vector::from_u16([0x1234, 0x1234, 0x1234, 0xF234, 0xF234, 0xF234, 0xF234, 0x1234]),
+            vector2: Vector::from_u16([0x1234, 0x1233, 0x1235, 0xF233, 0xF234, 0xF235, 0x1234, 0xF234]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -669,17 +860,26 @@ impl Test for VMRG {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements_vector2_variations(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VMRG {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vmrg(target, source1, source2, e); },
                 |elements| {
                     elements.target = if elements.vcc_low { elements.source2 } else { elements.source1 };
                     elements.accum_0_16 = elements.target;
                     elements.vco_low = false;
                     elements.vco_high = false;
-                }).as_ref(),
-            Vector::from_u16([0x1111, 0x2222, 0x3333, 0x4444, 0x5555, 0x6666, 0x7777, 0x8888]),
-            Vector::from_u16([0xAAAA, 0xBBBB, 0xCCCC, 0xDDDD, 0xEEEE, 0xFFFF, 0xEFEF, 0xEFEF]))
+                }),
+            model: VectorUnit::vmrg,
+            vector1: Vector::from_u16([0x1111, 0x2222, 0x3333, 0x4444, 0x5555, 0x6666, 0x7777, 0x8888]),
+            vector2: Vector::from_u16([0xAAAA, 0xBBBB, 0xCCCC, 0xDDDD, 0xEEEE, 0xFFFF, 0xEFEF, 0xEFEF]),
+            vector2_variations: true,
+        }
     }
 }
 
@@ -693,15 +893,24 @@ impl Test for VAND {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VAND {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vand(target, source1, source2, e); },
                 |elements| {
                     elements.target = elements.source1 & elements.source2;
                     elements.accum_0_16 = elements.target;
-                }).as_ref(),
-            Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
-            Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]))
+                }),
+            model: VectorUnit::vand,
+            vector1: Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
+            vector2: Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]),
+            vector2_variations: false,
+        }
     }
 }
 
@@ -715,15 +924,24 @@ impl Test for VNAND {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VNAND {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vnand(target, source1, source2, e); },
                 |elements| {
                     elements.target = !(elements.source1 & elements.source2);
                     elements.accum_0_16 = elements.target;
-                }).as_ref(),
-            Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
-            Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]))
+                }),
+            model: VectorUnit::vnand,
+            vector1: Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
+            vector2: Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]),
+            vector2_variations: false,
+        }
     }
 }
 
@@ -738,15 +956,24 @@ impl Test for VOR {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VOR {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vor(target, source1, source2, e); },
                 |elements| {
                     elements.target = elements.source1 | elements.source2;
                     elements.accum_0_16 = elements.target;
-                }).as_ref(),
-            Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
-            Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]))
+                }),
+            model: VectorUnit::vor,
+            vector1: Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
+            vector2: Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]),
+            vector2_variations: false,
+        }
     }
 }
 
@@ -760,15 +987,24 @@ impl Test for VNOR {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VNOR {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vnor(target, source1, source2, e); },
                 |elements| {
                     elements.target = !(elements.source1 | elements.source2);
                     elements.accum_0_16 = elements.target;
-                }).as_ref(),
-            Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
-            Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]))
+                }),
+            model: VectorUnit::vnor,
+            vector1: Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
+            vector2: Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]),
+            vector2_variations: false,
+        }
     }
 }
 
@@ -782,15 +1018,24 @@ impl Test for VXOR {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VXOR {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
             |assembler, target, source1, source2, e| { assembler.write_vxor(target, source1, source2, e); },
             |elements| {
                 elements.target = elements.source1 ^ elements.source2;
                 elements.accum_0_16 = elements.target;
-            }).as_ref(),
-            Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
-            Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]))
+            }),
+            model: VectorUnit::vxor,
+            vector1: Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
+            vector2: Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]),
+            vector2_variations: false,
+        }
     }
 }
 
@@ -804,15 +1049,24 @@ impl Test for VNXOR {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VNXOR {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vnxor(target, source1, source2, e); },
                 |elements| {
                     elements.target = !(elements.source1 ^ elements.source2);
                     elements.accum_0_16 = elements.target;
-                }).as_ref(),
-            Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
-            Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]))
+                }),
+            model: VectorUnit::vnxor,
+            vector1: Vector::from_u16([0x1111, 0x1245, 0x3333, 0x4444, 0xB0C5, 0x6666, 0x0000, 0xFFFF]),
+            vector2: Vector::from_u16([0xFF0F, 0xEF20, 0x0000, 0xFFFF, 0x3312, 0x0000, 0xEFEF, 0xEFEF]),
+            vector2_variations: false,
+        }
     }
 }
 
@@ -1024,8 +1278,14 @@ impl Test for VCL {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VCL {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vcl(target, source1, source2, e); },
                 |elements| {
                     if elements.vco_low {
@@ -1045,9 +1305,12 @@ impl Test for VCL {
                     elements.vco_low = false;
                     elements.vco_high = false;
                     elements.vce = false;
-                }).as_ref(),
-            Vector::from_u16([0x0000, 0x0001, 0x7FFE, 0x7FFF, 0x8000, 0xFFFE, 0xFFFF, 0x0000]),
-            Vector::from_u16([0x8000, 0xFFFE, 0xFFFF, 0x0000, 0x0000, 0x0001, 0x7FFE, 0x7FFF]))
+                }),
+            model: VectorUnit::vcl,
+            vector1: Vector::from_u16([0x0000, 0x0001, 0x7FFE, 0x7FFF, 0x8000, 0xFFFE, 0xFFFF, 0x0000]),
+            vector2: Vector::from_u16([0x8000, 0xFFFE, 0xFFFF, 0x0000, 0x0000, 0x0001, 0x7FFE, 0x7FFF]),
+            vector2_variations: false,
+        }
     }
 }
 
@@ -1061,8 +1324,14 @@ impl Test for VCH {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VCH {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vch(target, source1, source2, e); },
                 |elements| {
                     let i1 = elements.source1 as i16;
@@ -1085,9 +1354,12 @@ impl Test for VCH {
                     }
 
                     elements.accum_0_16 = elements.target;
-                }).as_ref(),
-            Vector::from_u16([0x0000, 0x0001, 0x7FFE, 0x7FFF, 0x8000, 0xFFFE, 0xFFFF, 0x0000]),
-            Vector::from_u16([0x8000, 0xFFFE, 0xFFFF, 0x0000, 0x0000, 0x0001, 0x7FFE, 0x7FFF]))
+                }),
+            model: VectorUnit::vch,
+            vector1: Vector::from_u16([0x0000, 0x0001, 0x7FFE, 0x7FFF, 0x8000, 0xFFFE, 0xFFFF, 0x0000]),
+            vector2: Vector::from_u16([0x8000, 0xFFFE, 0xFFFF, 0x0000, 0x0000, 0x0001, 0x7FFE, 0x7FFF]),
+            vector2_variations: false,
+        }
     }
 }
 
@@ -1101,8 +1373,14 @@ impl Test for VCR {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test_with_emulation_all_flags_and_elements(
-            make_test_case(
+        Self::modelled_case().run_on_rsp()
+    }
+}
+
+impl VCR {
+    fn modelled_case() -> ModelledCase {
+        ModelledCase {
+            test_case: make_test_case(
                 |assembler, target, source1, source2, e| { assembler.write_vcr(target, source1, source2, e); },
                 |elements| {
                     let i1 = elements.source1 as i16;
@@ -1122,9 +1400,53 @@ impl Test for VCR {
                     elements.vco_low = false;
                     elements.vco_high = false;
                     elements.vce = false;
-                }).as_ref(),
-            Vector::from_u16([0x0000, 0x0001, 0x7FFE, 0x7FFF, 0x8000, 0xFFFE, 0xFFFF, 0x0000]),
-            Vector::from_u16([0x8000, 0xFFFE, 0xFFFF, 0x0000, 0x0000, 0x0001, 0x7FFE, 0x7FFF]))
+                }),
+            model: VectorUnit::vcr,
+            vector1: Vector::from_u16([0x0000, 0x0001, 0x7FFE, 0x7FFF, 0x8000, 0xFFFE, 0xFFFF, 0x0000]),
+            vector2: Vector::from_u16([0x8000, 0xFFFE, 0xFFFF, 0x0000, 0x0000, 0x0001, 0x7FFE, 0x7FFF]),
+            vector2_variations: false,
+        }
     }
 }
 
+
+/// Runs the inputs of the tests above through the reference model in math::vector_unit instead of the RSP. The
+/// emulation in those tests matches the hardware, so the model has to agree with it
+pub struct VectorUnitModel {}
+
+impl Test for VectorUnitModel {
+    fn name(&self) -> &str { "RSP vector unit reference model (arithmetic)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let cases = [
+            ("VADD", VADD::modelled_case()),
+            ("VSUB", VSUB::modelled_case()),
+            ("VABS", VABS::modelled_case()),
+            ("VADDC", VADDC::modelled_case()),
+            ("VSUBC", VSUBC::modelled_case()),
+            ("VLT", VLT::modelled_case()),
+            ("VEQ", VEQ::modelled_case()),
+            ("VNE", VNE::modelled_case()),
+            ("VGE", VGE::modelled_case()),
+            ("VMRG", VMRG::modelled_case()),
+            ("VAND", VAND::modelled_case()),
+            ("VNAND", VNAND::modelled_case()),
+            ("VOR", VOR::modelled_case()),
+            ("VNOR", VNOR::modelled_case()),
+            ("VXOR", VXOR::modelled_case()),
+            ("VNXOR", VNXOR::modelled_case()),
+            ("VCL", VCL::modelled_case()),
+            ("VCH", VCH::modelled_case()),
+            ("VCR", VCR::modelled_case()),
+        ];
+        for (instruction, case) in cases.iter() {
+            case.run_on_model().map_err(|error| format!("{}: {}", instruction, error))?;
+        }
+
+        Ok(())
+    }
+}
@@ -4,7 +4,8 @@ use alloc::vec::Vec;
 use core::any::Any;
 
 use crate::rsp::rsp::RSP;
-use crate::rsp::rsp_assembler::{E, Element, GPR, RSPAssembler, VR, VSARAccumulator};
+use crate::rsp::rsp_assembler::{Element, RSPAssembler};
+use crate::rsp_asm;
 use crate::rsp::spmem::SPMEM;
 use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::soft_assert_eq;
@@ -17,33 +18,35 @@ fn run_test(e: Element, expected_result: [u16; 8], expected_acc_top: [u16; 8], e
     // Assemble RSP program. First use VMULF to set accumulator to something known, then use VMACF
     let mut assembler = RSPAssembler::new(0);
 
-    assembler.write_lqv(VR::V0, E::_0, 0x000, GPR::R0);
-    assembler.write_lqv(VR::V1, E::_0, 0x010, GPR::R0);
-    assembler.write_lqv(VR::V6, E::_0, 0x000, GPR::R0);
-    assembler.write_lqv(VR::V7, E::_0, 0x010, GPR::R0);
+    rsp_asm!(assembler,
+        lqv V0[_0], 0x000(R0);
+        lqv V1[_0], 0x010(R0);
+        lqv V6[_0], 0x000(R0);
+        lqv V7[_0], 0x010(R0);
 
-    assembler.write_vmulf(VR::V2, VR::V0, VR::V1, Element::All);
-    assembler.write_vmacf(VR::V2, VR::V0, VR::V1, e);
+        vmulf V2, V1, V0;
+        vmacf V2, V1, V0[{e}];
 
-    assembler.write_vsar(VR::V3, VSARAccumulator::High);
-    assembler.write_vsar(VR::V4, VSARAccumulator::Mid);
-    assembler.write_vsar(VR::V5, VSARAccumulator::Low);
+        vsar V3, High;
+        vsar V4, Mid;
+        vsar V5, Low;
 
-    assembler.write_sqv(VR::V2, E::_0, 0x100, GPR::R0);
-    assembler.write_sqv(VR::V3, E::_0, 0x110, GPR::R0);
-    assembler.write_sqv(VR::V4, E::_0, 0x120, GPR::R0);
-    assembler.write_sqv(VR::V5, E::_0, 0x130, GPR::R0);
+        sqv V2[_0], 0x100(R0);
+        sqv V3[_0], 0x110(R0);
+        sqv V4[_0], 0x120(R0);
+        sqv V5[_0], 0x130(R0);
 
-    // again but this time destructive by overwriting a source reg
-    assembler.write_vmulf(VR::V2, VR::V0, VR::V1, Element::All);
-    assembler.write_vmacf(VR::V6, VR::V6, VR::V1, e);
-    assembler.write_vmulf(VR::V2, VR::V0, VR::V1, Element::All);
-    assembler.write_vmacf(VR::V7, VR::V0, VR::V7, e);
+        // again but this time destructive by overwriting a source reg
+        vmulf V2, V1, V0;
+        vmacf V6, V1, V6[{e}];
+        vmulf V2, V1, V0;
+        vmacf V7, V7, V0[{e}];
 
-    assembler.write_sqv(VR::V6, E::_0, 0x140, GPR::R0);
-    assembler.write_sqv(VR::V7, E::_0, 0x150, GPR::R0);
+        sqv V6[_0], 0x140(R0);
+        sqv V7[_0], 0x150(R0);
 
-    assembler.write_break();
+        break;
+    );
 
     RSP::run_and_wait(0);
 
@@ -5,6 +5,7 @@ use alloc::vec::Vec;
 use core::any::Any;
 
 use crate::math::vector::Vector;
+use crate::math::vector_unit::vmacq_lane;
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{E, Element, GPR, RSPAssembler, VR, VSARAccumulator};
 use crate::rsp::rsp_macros::assemble_set_accumulator_to;
@@ -13,40 +14,8 @@ use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::soft_assert_eq2;
 
 fn simulate(acc_top: u16, acc_mid: u16, acc_low: u16) -> (u16, u16, u16, u16) {
-    let acc_input = (((acc_top as i64) << 48) | ((acc_mid as i64) << 32) | ((acc_low as i64) << 16)) >> 16;
-
-    // Add/Remove value, depending on bits in the accumulator
-    let should_change = (acc_input & 0x20_0000) == 0;
-    let acc_output = if should_change {
-        let upper_bits = (acc_input >> 22) as i32;
-        if upper_bits < 0 {
-            acc_input + 0x20_0000
-        } else if upper_bits > 0 {
-            acc_input - 0x20_0000
-        } else {
-            acc_input
-        }
-    } else {
-        acc_input
-    };
-
-    // We removed 0x20_0000 only if the original number was larger than 0x40_0000. Same for negative.
-    // Therefore, the sign of the result can't change and we don't need to sign-extend from 48 to 64
-
-    let clamped_and_shifted = if acc_output < 0 {
-        if ((!acc_output) >> 32) != 0 {
-            0x8000
-        } else {
-            (acc_output >> 17) as u16
-        }
-    } else {
-        if (acc_output >> 32) != 0 {
-            0x7FFF
-        } else {
-            (acc_output >> 17) as u16
-        }
-    };
-    let result = clamped_and_shifted & 0xFFF0;
+    let accumulator = ((acc_top as u64) << 32) | ((acc_mid as u64) << 16) | (acc_low as u64);
+    let (result, acc_output) = vmacq_lane(0, 0, accumulator);
     (result, (acc_output >> 32) as u16, (acc_output >> 16) as u16, acc_output as u16)
 }
 
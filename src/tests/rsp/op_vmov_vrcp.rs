@@ -6,79 +6,15 @@ use core::any::Any;
 
 use crate::{print, println};
 use crate::math::vector::Vector;
+use crate::math::vector_unit::{make_rcp_table, rcp, rsq, RSQ_DATA, VectorUnit};
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{E, Element, GPR, RSPAssembler, VR, VSARAccumulator};
 use crate::rsp::rsp_macros::assemble_set_accumulator_to;
 use crate::rsp::spmem::SPMEM;
+use crate::rsp_asm;
 use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::{soft_assert_eq2, soft_assert_eq_vector};
 
-// The generation of the RCP and RSP tables was ported from Ares: https://github.com/ares-emulator/ares/blob/acd2130a4d4c9e7208f61e0ff762895f7c9b8dc6/ares/n64/rsp/rsp.cpp#L102
-// which uses the following license:
-
-// Copyright (c) 2004-2021 ares team, Near et al
-//
-// Permission to use, copy, modify, and/or distribute this software for any
-// purpose with or without fee is hereby granted, provided that the above
-// copyright notice and this permission notice appear in all copies.
-//
-// THE SOFTWARE IS PROVIDED "AS IS" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
-// WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
-// MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
-// ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
-// WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
-// ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
-// OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
-
-const fn rcp_table_value(index: usize) -> u16 {
-    if index == 0 {
-        return 0xFFFF;
-    }
-
-    ((((1u64 << 34) / ((index as u64) + 512)) + 1) >> 8) as u16
-}
-
-const fn rsq_table_value(index: usize) -> u16 {
-    // The basic idea for this algorithm was taken from Ares, but modified:
-    // - indexes are different to match the algorithm here
-    // - using the increment loop this function was made faster; it is now fast enough to run
-    //   in a const context
-    let a = (if index < 256 { index + 256 } else { ((index - 256) << 1) + 512 }) as u64;
-    let mut b = 1u64 << 17;
-    // find the largest b where b < 1.0 / sqrt(a)
-    let mut increment = 512;
-    while increment != 0 {
-        while a * (b + increment) * (b + increment) < (1u64 << 44) {
-            b += increment;
-        }
-        increment >>= 1;
-    }
-    (b >> 1) as u16
-}
-
-const fn make_rcp_table() -> [u16; 512] {
-    let mut result = [0u16; 512];
-    let mut i = 0;
-    while i < 512 {
-        result[i] = rcp_table_value(i);
-        i += 1;
-    }
-    result
-}
-
-const fn make_rsq_table() -> [u16; 512] {
-    let mut result = [0u16; 512];
-    let mut i = 0;
-    while i < 512 {
-        result[i] = rsq_table_value(i);
-        i += 1;
-    }
-    result
-}
-
-const RCP_DATA: [u16; 512] = make_rcp_table();
-const RSQ_DATA: [u16; 512] = make_rsq_table();
-
 const TEST_VALUES_32: [u32; 32] = [
     0, 1, 2, 0x2000, 0x7FFF, 0x8000, 0x8001, 0xFFFF, 0x7FFF_FFFE, 0x7FFF_FFFF,
     0x8000_0000, 0x8000_0001, 0x8000_0002, 0x8040_0000, 0xC000_0000, 0xC000_0002,
@@ -87,41 +23,6 @@ const TEST_VALUES_32: [u32; 32] = [
     0xFFFF_FFFD, 0xFFFF_FFFE, 0xFFFF_FFFF
 ];
 
-pub const fn rcp(value: u32) -> u32 {
-    if value == 0 {
-        return 0x7FFF_FFFF;
-    }
-    if value == 0xFFFF_8000 {
-        return 0xFFFF_0000;
-    }
-    // After 0xFFFF_8000, everything is shifted by one. Why? No idea
-    let adjusted_value = if value > 0xFFFF_8000 { value - 1 } else { value };
-    let is_negative = (adjusted_value as i32).is_negative();
-    let positive_value = if is_negative { !adjusted_value } else { adjusted_value };
-    let shift = positive_value.leading_zeros() + 1;
-    let index = ((positive_value << shift) >> 23) as usize;
-    let positive_result = (0x4000_0000 | ((RCP_DATA[index] as u32) << 14)) >> (32 - shift);
-    if is_negative { !positive_result } else { positive_result }
-}
-
-pub fn rsq(value: u32) -> u32 {
-    if value == 0 {
-        return 0x7FFF_FFFF;
-    }
-    if value == 0xFFFF_8000 {
-        return 0xFFFF_0000;
-    }
-    // After 0xFFFF_8000, everything is offset by one. Why? No idea
-    let adjusted_value = if value > 0xFFFF_8000 { value - 1 } else { value };
-    let is_negative = (adjusted_value as i32).is_negative();
-    let positive_value = if is_negative { !adjusted_value } else { adjusted_value };
-    let shift = positive_value.leading_zeros() + 1;
-    // For uneven shifts, take the second half of the table
-    let index = (((positive_value << shift) >> 24) | ((shift & 1) << 8)) as usize;
-    let positive_result = (0x4000_0000 | ((RSQ_DATA[index] as u32) << 14)) >> ((32 - shift) >> 1);
-    if is_negative { !positive_result } else { positive_result }
-}
-
 fn run_test<FEmitter: Fn(&mut RSPAssembler, VR, VR, VR, Element), FEmulate: Fn(u16) -> u16>(apply_element_to_vt_for_result: bool, check_accumulators: bool, vt_vector: Vector, vd: VR, vs: VR, vt: VR, e: Element, emit: FEmitter, emulate: FEmulate) -> Result<(), String> {
     const INPUT_ACC_TOP: Vector = Vector::new_with_broadcast_16(0x0123);
     const INPUT_ACC_MID: Vector = Vector::new_with_broadcast_16(0x4567);
@@ -276,6 +177,46 @@ impl Test for VMOV {
     }
 }
 
+pub struct VMOVWithRSPAsm {}
+
+impl Test for VMOVWithRSPAsm {
+    fn name(&self) -> &str { "RSP VMOV (rsp_asm! lane syntax, interpolation and negative offsets)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let vd_pre_vector = Vector::from_u16([0x0000, 0x1001, 0x2002, 0x3003, 0x4004, 0x5005, 0x6006, 0x7007]);
+        let vt_vector = Vector::from_u16([0x0880, 0x0990, 0x0AA0, 0x0BB0, 0x0CC0, 0x0DD0, 0x0EE0, 0x0FF0]);
+        SPMEM::write_vector_into_dmem(0x30, &vd_pre_vector);
+        SPMEM::write_vector_into_dmem(0x40, &vt_vector);
+
+        for vd in [VR::V2, VR::V17] {
+            for lane in 0..8 {
+                let e = Element::_3;
+                let mut assembler = RSPAssembler::new(0);
+                rsp_asm!(assembler,
+                    li S0, 0x50;
+                    lqv {vd}[_0], -0x20(S0);
+                    lqv V1[_0], -0x10(S0);
+                    vmov {vd}[{lane}], V1[{e}];
+                    sqv {vd}[_0], 0xB0(S0);
+                    break;
+                );
+
+                RSP::run_and_wait(0);
+
+                let mut expected = vd_pre_vector;
+                expected.set16(lane, vt_vector.get16(3));
+                soft_assert_eq_vector(SPMEM::read_vector_from_dmem(0x100), expected, || format!("VMOV {:?}[{}], V1[{:?}]", vd, lane, e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub struct VRCPRegisterCombinations {}
 
 impl Test for VRCPRegisterCombinations {
@@ -766,6 +707,14 @@ impl Test for HighUsesOutputVRSQLTest {
     }
 }
 
+/// What VRCPHSetsInputForVRCPL reads back for a given combination of instructions: The first and second VRCPL/VRSQL
+/// and the VRCPL/VRSQL after VRCP/VRSQ
+fn hidden_register_results(i: u32) -> (u16, u16, u16) {
+    let (first, second) = if (i & 2) != 0 { (0xFFFA, 0x9E1B) } else { (0x5BC2, 0xC2FF) };
+    let third = if (i & 16) != 0 { 0x9E1B } else { 0xC2FF };
+    (first, second, third)
+}
+
 pub struct VRCPHSetsInputForVRCPL {}
 
 impl Test for VRCPHSetsInputForVRCPL {
@@ -835,18 +784,47 @@ impl Test for VRCPHSetsInputForVRCPL {
 
             RSP::run_and_wait(0);
 
-            if (i & 2) != 0 {
-                soft_assert_eq2((SPMEM::read(0x100) >> 16) as u16, 0xFFFA, || format!("{} should write the hidden register for VRCPL", if (i & 1) != 0 { "VRCPH" } else { "VRSQH" }))?;
-                soft_assert_eq2((SPMEM::read(0x110) >> 16) as u16, 0x9E1B, || format!("VRCPL clears the hidden register"))?;
-            } else {
-                soft_assert_eq2((SPMEM::read(0x100) >> 16) as u16, 0x5BC2, || format!("{} should write the hidden register for VRSQL", if (i & 1) != 0 { "VRCPH" } else { "VRSQH" }))?;
-                soft_assert_eq2((SPMEM::read(0x110) >> 16) as u16, 0xC2FF, || format!("VRSQL clears the hidden register"))?;
-            }
-            if (i & 16) != 0 {
-                soft_assert_eq2((SPMEM::read(0x120) >> 16) as u16, 0x9E1B, || format!("{} should clear the hidden register", if (i & 8) != 0 { "VRCP" } else { "VRSQ" }))?;
-            } else {
-                soft_assert_eq2((SPMEM::read(0x120) >> 16) as u16, 0xC2FF, || format!("{} should clear the hidden register", if (i & 8) != 0 { "VRCP" } else { "VRSQ" }))?;
-            }
+            let (expected_first, expected_second, expected_third) = hidden_register_results(i);
+            soft_assert_eq2((SPMEM::read(0x100) >> 16) as u16, expected_first, || format!("{} should write the hidden register for {}", if (i & 1) != 0 { "VRCPH" } else { "VRSQH" }, if (i & 2) != 0 { "VRCPL" } else { "VRSQL" }))?;
+            soft_assert_eq2((SPMEM::read(0x110) >> 16) as u16, expected_second, || format!("{} clears the hidden register", if (i & 2) != 0 { "VRCPL" } else { "VRSQL" }))?;
+            soft_assert_eq2((SPMEM::read(0x120) >> 16) as u16, expected_third, || format!("{} should clear the hidden register", if (i & 8) != 0 { "VRCP" } else { "VRSQ" }))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct VRCPHSetsInputForVRCPLReferenceModel {}
+
+impl Test for VRCPHSetsInputForVRCPLReferenceModel {
+    fn name(&self) -> &str { "RSP VRCPH sets input for VRCPL (reference model)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // Runs the same combinations as VRCPHSetsInputForVRCPL through the reference model, which has to produce the same lanes
+        for i in 0..32 {
+            let mut unit = VectorUnit::new();
+            unit.set_register(VR::V0, Vector::new_with_broadcast_16(0xE834));
+            let high = if (i & 1) != 0 { VectorUnit::vrcph } else { VectorUnit::vrsqh };
+            high(&mut unit, VR::V31, VR::V0, VR::V0, Element::_0);
+            high(&mut unit, VR::V30, VR::V0, VR::V0, Element::_0);
+            let low = if (i & 2) != 0 { VectorUnit::vrcpl } else { VectorUnit::vrsql };
+            low(&mut unit, VR::V2, VR::V0, VR::V0, Element::_0);
+            low(&mut unit, VR::V3, VR::V0, VR::V0, Element::_0);
+            let high = if (i & 4) != 0 { VectorUnit::vrcph } else { VectorUnit::vrsqh };
+            high(&mut unit, VR::V31, VR::V0, VR::V0, Element::_0);
+            let single = if (i & 8) != 0 { VectorUnit::vrcp } else { VectorUnit::vrsq };
+            single(&mut unit, VR::V31, VR::V0, VR::V0, Element::_0);
+            let low = if (i & 16) != 0 { VectorUnit::vrcpl } else { VectorUnit::vrsql };
+            low(&mut unit, VR::V4, VR::V0, VR::V0, Element::_0);
+
+            let (expected_first, expected_second, expected_third) = hidden_register_results(i);
+            soft_assert_eq2(unit.register(VR::V2).get16(0), expected_first, || format!("First VRCPL/VRSQL for combination {}", i))?;
+            soft_assert_eq2(unit.register(VR::V3).get16(0), expected_second, || format!("Second VRCPL/VRSQL for combination {}", i))?;
+            soft_assert_eq2(unit.register(VR::V4).get16(0), expected_third, || format!("VRCPL/VRSQL after VRCP/VRSQ for combination {}", i))?;
         }
 
         Ok(())
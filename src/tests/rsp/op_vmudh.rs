@@ -1,18 +1,27 @@
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::any::Any;
 
+use crate::math::vector::Vector;
+use crate::math::vector_unit::VectorUnit;
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{E, Element, GPR, RSPAssembler, VR, VSARAccumulator};
 use crate::rsp::spmem::SPMEM;
 use crate::tests::{Level, Test};
-use crate::tests::soft_asserts::soft_assert_eq;
+use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2};
+
+const INPUT1: Vector = Vector::from_u16([0x0000, 0x0000, 0x0000, 0xE000, 0x8001, 0x8000, 0x7FFF, 0x8000]);
+const INPUT2: Vector = Vector::from_u16([0x0000, 0x0001, 0xFFFF, 0xFFFF, 0x8000, 0x7FFF, 0x7FFF, 0x8000]);
+
+/// Either run_test (on the RSP) or run_model (on the reference model)
+type Runner = fn(Element, [u16; 8], [u16; 8], [u16; 8], [u16; 8]) -> Result<(), String>;
 
 fn run_test(e: Element, expected_result: [u16; 8], expected_acc_top: [u16; 8], expected_acc_mid: [u16; 8], expected_acc_low: [u16; 8]) -> Result<(), String> {
     // Prepare input data
-    SPMEM::write_vector16_into_dmem(0x00, &[0x0000, 0x0000, 0x0000, 0xE000, 0x8001, 0x8000, 0x7FFF, 0x8000]);
-    SPMEM::write_vector16_into_dmem(0x10, &[0x0000, 0x0001, 0xFFFF, 0xFFFF, 0x8000, 0x7FFF, 0x7FFF, 0x8000]);
+    SPMEM::write_vector_into_dmem(0x00, &INPUT1);
+    SPMEM::write_vector_into_dmem(0x10, &INPUT2);
 
     // Assemble RSP program. First use VMULF to set accumulator to something known, then use VMUDH
     let mut assembler = RSPAssembler::new(0);
@@ -56,6 +65,27 @@ fn run_test(e: Element, expected_result: [u16; 8], expected_acc_top: [u16; 8], e
     Ok(())
 }
 
+/// Runs the same program as run_test on the reference model in math::vector_unit
+fn run_model(e: Element, expected_result: [u16; 8], expected_acc_top: [u16; 8], expected_acc_mid: [u16; 8], expected_acc_low: [u16; 8]) -> Result<(), String> {
+    let mut unit = VectorUnit::new();
+    unit.set_register(VR::V0, INPUT1);
+    unit.set_register(VR::V1, INPUT2);
+    unit.set_register(VR::V6, INPUT1);
+    unit.set_register(VR::V7, INPUT2);
+    unit.vmulf(VR::V2, VR::V0, VR::V1, e);
+    unit.vmudh(VR::V2, VR::V0, VR::V1, e);
+    soft_assert_eq2(unit.accumulator_high(), Vector::from_u16(expected_acc_top), || format!("VMUDH Acc[32..48] for e={:?}", e))?;
+    soft_assert_eq2(unit.accumulator_mid(), Vector::from_u16(expected_acc_mid), || format!("VMUDH Acc[16..32] for e={:?}", e))?;
+    soft_assert_eq2(unit.accumulator_low(), Vector::from_u16(expected_acc_low), || format!("VMUDH Acc[0..16] for e={:?}", e))?;
+    unit.vmudh(VR::V6, VR::V6, VR::V1, e);
+    unit.vmudh(VR::V7, VR::V0, VR::V7, e);
+    soft_assert_eq2(unit.register(VR::V2), Vector::from_u16(expected_result), || format!("VMUDH result for e={:?}", e))?;
+    soft_assert_eq2(unit.register(VR::V6), Vector::from_u16(expected_result), || format!("VMUDH result when doing VMUDH V6, V6, V1 for e={:?}", e))?;
+    soft_assert_eq2(unit.register(VR::V7), Vector::from_u16(expected_result), || format!("VMUDH result when doing VMUDH V7, V0, V7 for e={:?}", e))?;
+
+    Ok(())
+}
+
 pub struct VMUDHAll {}
 
 impl Test for VMUDHAll {
@@ -66,7 +96,13 @@ impl Test for VMUDHAll {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test(
+        Self::check(run_test)
+    }
+}
+
+impl VMUDHAll {
+    fn check(run: Runner) -> Result<(), String> {
+        run(
             Element::All,
             [0, 0, 0, 0x2000, 0x7fff, 0x8000, 0x7fff, 0x7fff],
             [0, 0, 0, 0, 0x3fff, 0xc000, 0x3fff, 0x4000],
@@ -86,7 +122,13 @@ impl Test for VMUDHQ1 {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test(
+        Self::check(run_test)
+    }
+}
+
+impl VMUDHQ1 {
+    fn check(run: Runner) -> Result<(), String> {
+        run(
             Element::Q1,
             [0, 0, 0x2000, 0x2000, 0x7FFF, 0x8000, 0x8000, 0x7fff],
             [0, 0, 0, 0, 0x4000, 0xc000, 0xc000, 0x4000],
@@ -106,7 +148,13 @@ impl Test for VMUDHH0 {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test(
+        Self::check(run_test)
+    }
+}
+
+impl VMUDHH0 {
+    fn check(run: Runner) -> Result<(), String> {
+        run(
             Element::H0,
             [0, 0, 0, 0, 0x7FFF, 0x8000, 0x8000, 0x7FFF],
             [0, 0, 0, 0, 0x3FFF, 0xC000, 0xC000, 0x3FFF],
@@ -126,7 +174,13 @@ impl Test for VMUDH7 {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
-        run_test(
+        Self::check(run_test)
+    }
+}
+
+impl VMUDH7 {
+    fn check(run: Runner) -> Result<(), String> {
+        run(
             Element::_7,
             [0, 0x8000, 0x7fff, 0x7fff, 0x7fff, 0x8000, 0x8000, 0x7fff],
             [0, 0xffff, 0, 0, 0x4000, 0xc000, 0xc000, 0x4000],
@@ -135,3 +189,21 @@ impl Test for VMUDH7 {
         )
     }
 }
+
+pub struct VMUDHReferenceModel {}
+
+impl Test for VMUDHReferenceModel {
+    fn name(&self) -> &str { "RSP VMUDH (reference model)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // The expected values of the tests above are what the hardware does, so the reference model has to produce the same
+        VMUDHAll::check(run_model)?;
+        VMUDHQ1::check(run_model)?;
+        VMUDHH0::check(run_model)?;
+        VMUDH7::check(run_model)
+    }
+}
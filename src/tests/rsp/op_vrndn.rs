@@ -1,16 +1,18 @@
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::any::Any;
 use core::sync::atomic::{AtomicU16, Ordering};
 
 use crate::math::vector::Vector;
+use crate::math::vector_unit::VectorUnit;
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{E, Element, GPR, RSPAssembler, VR, VSARAccumulator};
 use crate::rsp::rsp_macros::assemble_set_accumulator_to;
 use crate::rsp::spmem::SPMEM;
 use crate::tests::{Level, Test};
-use crate::tests::soft_asserts::soft_assert_eq;
+use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2};
 
 static COUNTER: AtomicU16 = AtomicU16::new(13);
 
@@ -18,14 +20,25 @@ fn rng() -> u16 {
     COUNTER.fetch_add(1234, Ordering::Relaxed)
 }
 
-fn run_test(e: Element, vs: VR, vt: VR, expected_result: [u16; 8], expected_acc_top: [u16; 8], expected_acc_mid: [u16; 8], expected_acc_low: [u16; 8]) -> Result<(), String> {
-    // Data to pre-set accumulator
-    SPMEM::write_vector16_into_dmem(0x00, &[0x0000, 0x0001, 0x0001, 0x7FFF, 0xFFFF, 0x7FFF, 0x3FFF, 0x8000]);
-    SPMEM::write_vector16_into_dmem(0x10, &[0x0000, 0x0001, 0xFFFF, 0xFFFF, 0xFFFF, 0x7FFF, 0x7FFF, 0x7FFF]);
+// Data to pre-set accumulator
+const MULTIPLICAND1: Vector = Vector::from_u16([0x0000, 0x0001, 0x0001, 0x7FFF, 0xFFFF, 0x7FFF, 0x3FFF, 0x8000]);
+const MULTIPLICAND2: Vector = Vector::from_u16([0x0000, 0x0001, 0xFFFF, 0xFFFF, 0xFFFF, 0x7FFF, 0x7FFF, 0x7FFF]);
+
+// Data for input. The second value is ignored, so it will be filled with garbage
+const INPUT: Vector = Vector::from_u16([0x0000, 0x0001, 0x0002, 0x7FFF, 0xFFFF, 0x8000, 0x8001, 0x8002]);
+
+fn garbage() -> Vector {
+    Vector::from_u16([rng(), rng(), rng(), rng(), rng(), rng(), rng(), rng()])
+}
 
-    // Data for input. The second value is ignored, so we'll fill it with garbage
-    SPMEM::write_vector16_into_dmem(0x20, &[0x0000, 0x0001, 0x0002, 0x7FFF, 0xFFFF, 0x8000, 0x8001, 0x8002]);
-    SPMEM::write_vector16_into_dmem(0x30, &[rng(), rng(), rng(), rng(), rng(), rng(), rng(), rng()]);
+/// Either run_test (on the RSP) or run_model (on the reference model)
+type Runner = fn(Element, VR, VR, [u16; 8], [u16; 8], [u16; 8], [u16; 8]) -> Result<(), String>;
+
+fn run_test(e: Element, vs: VR, vt: VR, expected_result: [u16; 8], expected_acc_top: [u16; 8], expected_acc_mid: [u16; 8], expected_acc_low: [u16; 8]) -> Result<(), String> {
+    SPMEM::write_vector_into_dmem(0x00, &MULTIPLICAND1);
+    SPMEM::write_vector_into_dmem(0x10, &MULTIPLICAND2);
+    SPMEM::write_vector_into_dmem(0x20, &INPUT);
+    SPMEM::write_vector_into_dmem(0x30, &garbage());
 
     // Assemble RSP program
     let mut assembler = RSPAssembler::new(0);
@@ -68,6 +81,26 @@ fn run_test(e: Element, vs: VR, vt: VR, expected_result: [u16; 8], expected_acc_
     Ok(())
 }
 
+/// Runs the same program as run_test on the reference model in math::vector_unit
+fn run_model(e: Element, vs: VR, vt: VR, expected_result: [u16; 8], expected_acc_top: [u16; 8], expected_acc_mid: [u16; 8], expected_acc_low: [u16; 8]) -> Result<(), String> {
+    let mut unit = VectorUnit::new();
+    unit.set_register(VR::V0, MULTIPLICAND1);
+    unit.set_register(VR::V1, MULTIPLICAND2);
+    unit.vmudh(VR::V2, VR::V0, VR::V1, Element::All);
+    unit.vmadl(VR::V2, VR::V0, VR::V1, Element::All);
+    unit.set_register(vt, INPUT);
+    if vs != vt {
+        unit.set_register(vs, garbage());
+    }
+    unit.vrndn(VR::V2, vt, vs, e);
+    soft_assert_eq2(unit.register(VR::V2), Vector::from_u16(expected_result), || format!("Result for vs={:?}, vt={:?}, e={:?}", vs, vt, e))?;
+    soft_assert_eq2(unit.accumulator_high(), Vector::from_u16(expected_acc_top), || format!("Acc[32..48] for vs={:?}, vt={:?}, e={:?}", vs, vt, e))?;
+    soft_assert_eq2(unit.accumulator_mid(), Vector::from_u16(expected_acc_mid), || format!("Acc[16..32] for vs={:?}, vt={:?}, e={:?}", vs, vt, e))?;
+    soft_assert_eq2(unit.accumulator_low(), Vector::from_u16(expected_acc_low), || format!("Acc[0..16] for vs={:?}, vt={:?}, e={:?}", vs, vt, e))?;
+
+    Ok(())
+}
+
 pub struct VRNDNWithEvenVS {}
 
 impl Test for VRNDNWithEvenVS {
@@ -78,9 +111,15 @@ impl Test for VRNDNWithEvenVS {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        Self::check(run_test)
+    }
+}
+
+impl VRNDNWithEvenVS {
+    fn check(run: Runner) -> Result<(), String> {
         for i in (0..32).step_by(2) {
             let vt = if i == 0 { VR::V1 } else { VR::V0 };
-            run_test(
+            run(
                 Element::All,
                 VR::from_index(i).unwrap(),
                 vt,
@@ -105,9 +144,15 @@ impl Test for VRNDNWithOddVS {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        Self::check(run_test)
+    }
+}
+
+impl VRNDNWithOddVS {
+    fn check(run: Runner) -> Result<(), String> {
         for i in (1..32).step_by(2) {
             let vt = VR::V0;
-            run_test(
+            run(
                 Element::All,
                 VR::from_index(i).unwrap(),
                 vt,
@@ -132,8 +177,14 @@ impl Test for VRNDNOverwriteItselfWithElement {
     fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
 
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        Self::check(run_test)
+    }
+}
+
+impl VRNDNOverwriteItselfWithElement {
+    fn check(run: Runner) -> Result<(), String> {
         // V2 is the output, so also use it for input
-        run_test(
+        run(
             Element::H0,
             VR::V1,
             VR::V2,
@@ -246,3 +297,20 @@ impl Test for VRNDNClampNegativeAccumulator {
 		Ok(())
 	}
 }
+
+pub struct VRNDNReferenceModel {}
+
+impl Test for VRNDNReferenceModel {
+    fn name(&self) -> &str { "RSP VRNDN (reference model)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // The expected values of the tests above are what the hardware does, so the reference model has to produce the same
+        VRNDNWithEvenVS::check(run_model)?;
+        VRNDNWithOddVS::check(run_model)?;
+        VRNDNOverwriteItselfWithElement::check(run_model)
+    }
+}
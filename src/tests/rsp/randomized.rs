@@ -0,0 +1,220 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::Any;
+use oorandom::Rand32;
+
+use crate::math::vector::Vector;
+use crate::math::vector_unit::VectorUnit;
+use crate::rsp::rsp::RSP;
+use crate::rsp::rsp_assembler::{CP2FlagsRegister, E, Element, GPR, RSPAssembler, VR, VSARAccumulator};
+use crate::rsp::spmem::SPMEM;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+
+// Randomized tests for the vector instructions that aren't covered by the exhaustive stress tests. Each test
+// feeds random vectors (biased towards special values), random VCO/VCC/VCE and a random accumulator into an
+// instruction with every element specifier. Every case (the target register, the accumulator and all flags) is
+// compared against the reference model in math::vector_unit, and the first mismatch is reported with its batch,
+// case and element.
+
+/// Every batch runs one case per element specifier
+const CASES_PER_BATCH: usize = 16;
+const BATCHES: usize = 64;
+
+/// Per case: accumulator multiplicands, the two sources and the initial target register
+const INPUT_SIZE: usize = 0x50;
+const INPUT_START: usize = 0x000;
+/// Per case: VCO, VCC, VCE
+const FLAGS_SIZE: usize = 0x10;
+const FLAGS_START: usize = INPUT_START + INPUT_SIZE * CASES_PER_BATCH;
+/// Per case: VCO, VCC, VCE, target register, accumulator high/mid/low
+const OUTPUT_SIZE: usize = 0x50;
+const OUTPUT_START: usize = FLAGS_START + FLAGS_SIZE * CASES_PER_BATCH;
+
+/// Name, emitter, reference model
+const INSTRUCTIONS: [(&str, fn(&mut RSPAssembler, VR, VR, VR, Element), fn(&mut VectorUnit, VR, VR, VR, Element)); 22] = [
+    ("VADD", RSPAssembler::write_vadd, VectorUnit::vadd),
+    ("VSUB", RSPAssembler::write_vsub, VectorUnit::vsub),
+    ("VABS", RSPAssembler::write_vabs, VectorUnit::vabs),
+    ("VADDC", RSPAssembler::write_vaddc, VectorUnit::vaddc),
+    ("VSUBC", RSPAssembler::write_vsubc, VectorUnit::vsubc),
+    ("VCH", RSPAssembler::write_vch, VectorUnit::vch),
+    ("VCL", RSPAssembler::write_vcl, VectorUnit::vcl),
+    ("VCR", RSPAssembler::write_vcr, VectorUnit::vcr),
+    ("VLT", RSPAssembler::write_vlt, VectorUnit::vlt),
+    ("VEQ", RSPAssembler::write_veq, VectorUnit::veq),
+    ("VNE", RSPAssembler::write_vne, VectorUnit::vne),
+    ("VGE", RSPAssembler::write_vge, VectorUnit::vge),
+    ("VMRG", RSPAssembler::write_vmrg, VectorUnit::vmrg),
+    ("VAND", RSPAssembler::write_vand, VectorUnit::vand),
+    ("VNAND", RSPAssembler::write_vnand, VectorUnit::vnand),
+    ("VOR", RSPAssembler::write_vor, VectorUnit::vor),
+    ("VNOR", RSPAssembler::write_vnor, VectorUnit::vnor),
+    ("VXOR", RSPAssembler::write_vxor, VectorUnit::vxor),
+    ("VNXOR", RSPAssembler::write_vnxor, VectorUnit::vnxor),
+    ("VRNDN", RSPAssembler::write_vrndn, VectorUnit::vrndn),
+    ("VRNDP", RSPAssembler::write_vrndp, VectorUnit::vrndp),
+    ("VMACQ", RSPAssembler::write_vmacq, VectorUnit::vmacq),
+];
+
+/// Returns a random element. A quarter of them are special values that are likely to trigger edge cases
+fn random_element(random: &mut Rand32) -> u16 {
+    const SPECIAL: [u16; 8] = [0x0000, 0x0001, 0x7FFF, 0x8000, 0x8001, 0xFFFF, 0x0020, 0xFFE0];
+    let r = random.rand_u32();
+    if (r & 3) == 0 {
+        SPECIAL[((r >> 2) & 7) as usize]
+    } else {
+        (r >> 16) as u16
+    }
+}
+
+fn random_vector(random: &mut Rand32) -> Vector {
+    let mut result = Vector::new();
+    for i in 0..8 {
+        result.set16(i, random_element(random));
+    }
+    result
+}
+
+/// Assembles a program that runs the instruction once per element specifier. Everything that goes in is taken from DMEM
+fn assemble(emit: fn(&mut RSPAssembler, VR, VR, VR, Element)) {
+    let mut assembler = RSPAssembler::new(0);
+    for case in 0..CASES_PER_BATCH {
+        // LQV/SQV offsets only have 7 bits, so each case gets its own base registers
+        assembler.write_li(GPR::A0, (INPUT_START + case * INPUT_SIZE) as u32);
+        assembler.write_li(GPR::A1, (FLAGS_START + case * FLAGS_SIZE) as u32);
+        assembler.write_li(GPR::S3, (OUTPUT_START + case * OUTPUT_SIZE) as u32);
+
+        // Set up the accumulator through a multiplication
+        assembler.write_lqv(VR::V0, E::_0, 0x00, GPR::A0);
+        assembler.write_lqv(VR::V1, E::_0, 0x10, GPR::A0);
+        assembler.write_vmudh(VR::V3, VR::V0, VR::V1, Element::All);
+        assembler.write_vmadn(VR::V3, VR::V1, VR::V0, Element::All);
+
+        assembler.write_lqv(VR::V4, E::_0, 0x20, GPR::A0);
+        assembler.write_lqv(VR::V5, E::_0, 0x30, GPR::A0);
+        assembler.write_lqv(VR::V2, E::_0, 0x40, GPR::A0);
+
+        assembler.write_lw(GPR::S0, GPR::A1, 0);
+        assembler.write_lw(GPR::S1, GPR::A1, 4);
+        assembler.write_lw(GPR::S2, GPR::A1, 8);
+        assembler.write_ctc2(CP2FlagsRegister::VCO, GPR::S0);
+        assembler.write_ctc2(CP2FlagsRegister::VCC, GPR::S1);
+        assembler.write_ctc2(CP2FlagsRegister::VCE, GPR::S2);
+
+        emit(&mut assembler, VR::V2, VR::V4, VR::V5, Element::from_index(case).unwrap());
+
+        assembler.write_cfc2(CP2FlagsRegister::VCO, GPR::S0);
+        assembler.write_cfc2(CP2FlagsRegister::VCC, GPR::S1);
+        assembler.write_cfc2(CP2FlagsRegister::VCE, GPR::S2);
+        assembler.write_vsar(VR::V6, VSARAccumulator::High);
+        assembler.write_vsar(VR::V7, VSARAccumulator::Mid);
+        assembler.write_vsar(VR::V8, VSARAccumulator::Low);
+
+        assembler.write_sw(GPR::S0, GPR::S3, 0);
+        assembler.write_sw(GPR::S1, GPR::S3, 4);
+        assembler.write_sw(GPR::S2, GPR::S3, 8);
+        assembler.write_sqv(VR::V2, E::_0, 0x10, GPR::S3);
+        assembler.write_sqv(VR::V6, E::_0, 0x20, GPR::S3);
+        assembler.write_sqv(VR::V7, E::_0, 0x30, GPR::S3);
+        assembler.write_sqv(VR::V8, E::_0, 0x40, GPR::S3);
+    }
+    assembler.write_break();
+}
+
+/// Compares the outputs of one case against the reference model. CFC2 sign extends, so only the lower bits of the
+/// flags count. Elements are compared one by one so that the message can name the first one that differs
+fn compare_case(name: &str, batch: usize, case: usize, flags: [u32; 3], vectors: [Vector; 4], unit: &VectorUnit) -> Result<(), String> {
+    let e = Element::from_index(case).unwrap();
+    let expected_flags = [unit.vco() as u32, unit.vcc() as u32, unit.vce() as u32];
+    for (flag, (&actual, expected)) in ["VCO", "VCC", "VCE"].iter().zip(flags.iter().zip(expected_flags)) {
+        for element in 0..8 {
+            // VCO and VCC have a low and a high bit per element
+            let mask = if *flag == "VCE" { 1 << element } else { 0x101 << element };
+            soft_assert_eq2(actual & mask, expected & mask, || format!("{}: {} of batch {}, case {} (e={:?}), element {}", name, flag, batch, case, e, element))?;
+        }
+    }
+
+    let expected_vectors = [unit.register(VR::V2), unit.accumulator_high(), unit.accumulator_mid(), unit.accumulator_low()];
+    for (vector, (actual, expected)) in ["Result", "Acc[32..48]", "Acc[16..32]", "Acc[0..16]"].iter().zip(vectors.iter().zip(expected_vectors)) {
+        for element in 0..8 {
+            soft_assert_eq2(actual.get16(element), expected.get16(element), || format!("{}: {} of batch {}, case {} (e={:?}), element {}", name, vector, batch, case, e, element))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn randomized_vector_test(name: &str, emit: fn(&mut RSPAssembler, VR, VR, VR, Element), model: fn(&mut VectorUnit, VR, VR, VR, Element)) -> Result<(), String> {
+    let mut random = Rand32::new(0);
+
+    assemble(emit);
+
+    for batch in 0..BATCHES {
+        let mut units = vec![VectorUnit::new(); CASES_PER_BATCH];
+        for (case, unit) in units.iter_mut().enumerate() {
+            let mut inputs = [Vector::new(); 5];
+            for (i, input) in inputs.iter_mut().enumerate() {
+                *input = random_vector(&mut random);
+                SPMEM::write_vector_into_dmem(INPUT_START + case * INPUT_SIZE + i * 0x10, input);
+            }
+            let (vco, vcc, vce) = (random.rand_u32() & 0xFFFF, random.rand_u32() & 0xFFFF, random.rand_u32() & 0xFF);
+            let flags = FLAGS_START + case * FLAGS_SIZE;
+            SPMEM::write(flags, vco);
+            SPMEM::write(flags + 4, vcc);
+            SPMEM::write(flags + 8, vce);
+
+            // Mirror the program on the reference model
+            unit.set_register(VR::V0, inputs[0]);
+            unit.set_register(VR::V1, inputs[1]);
+            unit.vmudh(VR::V3, VR::V0, VR::V1, Element::All);
+            unit.vmadn(VR::V3, VR::V1, VR::V0, Element::All);
+            unit.set_register(VR::V4, inputs[2]);
+            unit.set_register(VR::V5, inputs[3]);
+            unit.set_register(VR::V2, inputs[4]);
+            unit.set_vco(vco as u16);
+            unit.set_vcc(vcc as u16);
+            unit.set_vce(vce as u8);
+            model(unit, VR::V2, VR::V4, VR::V5, Element::from_index(case).unwrap());
+        }
+
+        RSP::clear_broke();
+        RSP::run_and_wait(0);
+
+        for (case, unit) in units.iter().enumerate() {
+            let output = OUTPUT_START + case * OUTPUT_SIZE;
+            let mut vectors = [Vector::new(); 4];
+            for (i, vector) in vectors.iter_mut().enumerate() {
+                *vector = SPMEM::read_vector_from_dmem(output + 0x10 + i * 0x10);
+            }
+            let flags = [SPMEM::read(output), SPMEM::read(output + 4), SPMEM::read(output + 8)];
+            compare_case(name, batch, case, flags, vectors, unit)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub struct RandomizedVectorInstructions {}
+
+impl Test for RandomizedVectorInstructions {
+    fn name(&self) -> &str { "RSP vector instructions (randomized)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        let mut result: Vec<Box<dyn Any>> = Vec::new();
+        for i in 0..INSTRUCTIONS.len() as u32 {
+            result.push(Box::new(i));
+        }
+        result
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, emit, model) = INSTRUCTIONS[*(*value).downcast_ref::<u32>().unwrap() as usize];
+        randomized_vector_test(name, emit, model)
+    }
+}
@@ -12,16 +12,13 @@ use crate::graphics::cursor::Cursor;
 use crate::graphics::font::Font;
 use crate::graphics::system_font::FONT_GENEVA_9;
 use crate::math::vector::Vector;
+use crate::math::vector_unit;
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{CP0Register, E, Element, GPR, RSPAssembler, VR};
 use crate::rsp::spmem::SPMEM;
 use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::soft_assert_eq2;
 
-fn zero_extend_accum48(v: i64) -> u64 { ((v as u64) << 16) >> 16 }
-
-fn sign_extend_accum48(v: u64) -> i64 { ((v as i64) << 16) >> 16 }
-
 #[inline(always)]
 fn run_stress_test<F: Fn(&mut RSPAssembler) -> (), F2: Fn(u16, u16, u64) -> (u16, u64)>(name: &str, assembly_maker: F, cpu_computer: F2) -> Result<(), String> {
     // This runs every combination of vector values on the rsp, batched in STEPS_PER_RSP values at a time.
@@ -185,13 +182,7 @@ impl Test for VMULF {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMULF", |assembler| {
             assembler.write_vmulf(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, _accum| {
-            let product = (a as i16 as i32) * (b as i16 as i32);
-            let temp = ((product as i64) << 1) + 0x8000;
-            let result_val = if (temp > 0) && ((temp & !0x7FFFFFFF) != 0) { 0x7FFF } else { (temp >> 16) as u16 };
-
-            (result_val, zero_extend_accum48(temp))
-        })?;
+        }, vector_unit::vmulf_lane)?;
         Ok(())
     }
 }
@@ -208,20 +199,7 @@ impl Test for VMULU {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMULU", |assembler| {
             assembler.write_vmulu(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, _accum| {
-            let product = (a as i16 as i32) * (b as i16 as i32);
-            let product_shifted = ((product as i64) << 1) + 0x8000;
-            let result_val =
-                if (product_shifted & (1 << 48)) != 0 {
-                    0
-                } else if (product_shifted & !((1 << 31) - 1)) != 0 {
-                    0xFFFF
-                } else {
-                    (product_shifted >> 16) as u16
-                };
-
-            (result_val, zero_extend_accum48(product_shifted))
-        })?;
+        }, vector_unit::vmulu_lane)?;
         Ok(())
     }
 }
@@ -238,12 +216,7 @@ impl Test for VMUDL {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMUDL", |assembler| {
             assembler.write_vmudl(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, _accum| {
-            let product = (a as u32) * (b as u32);
-            let product_shifted = (product >> 16) as u16;
-
-            (product_shifted, product_shifted as u64)
-        })?;
+        }, vector_unit::vmudl_lane)?;
         Ok(())
     }
 }
@@ -260,18 +233,7 @@ impl Test for VMUDH {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMUDH", |assembler| {
             assembler.write_vmudh(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, _accum| {
-            let product = (a as i16 as i32) * (b as i16 as i32);
-
-            let result_val =
-                if product >= 0 {
-                    if (product & !0x7FFF) != 0 { 0x7FFFu16 } else { product as u16 }
-                } else {
-                    if (!product & !0x7FFF) != 0 { 0x8000u16 } else { product as u16 }
-                } as u16;
-
-            (result_val, (product as u32 as u64) << 16)
-        })?;
+        }, vector_unit::vmudh_lane)?;
         Ok(())
     }
 }
@@ -288,11 +250,7 @@ impl Test for VMUDM {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMUDM", |assembler| {
             assembler.write_vmudm(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, _accum| {
-            let product = (a as i16 as i32) * (b as u16 as i32);
-
-            ((product >> 16) as u16, zero_extend_accum48(product as i64))
-        })?;
+        }, vector_unit::vmudm_lane)?;
         Ok(())
     }
 }
@@ -309,12 +267,7 @@ impl Test for VMUDN {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMUDN", |assembler| {
             assembler.write_vmudn(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, _accum| {
-            let product = (a as u16 as i32) * (b as i16 as i32);
-            let result_val = product as u16;
-
-            (result_val, zero_extend_accum48(product as i64))
-        })?;
+        }, vector_unit::vmudn_lane)?;
         Ok(())
     }
 }
@@ -331,29 +284,7 @@ impl Test for VMULQ {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMULQ", |assembler| {
             assembler.write_vmulq(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, _accum| {
-            let product_shifted = ((a as i16 as i64) * (b as i16 as i64)) << 16;
-            let adjusted = product_shifted + (if product_shifted < 0 { 0x1F0000 } else { 0 });
-
-            let clamped =
-                if adjusted < 0 {
-                    if (!adjusted >> 32) != 0 {
-                        0x8000u16
-                    } else {
-                        (adjusted >> 17) as u16
-                    }
-                } else {
-                    if (adjusted >> 32) != 0 {
-                        0x7fffu16
-                    } else {
-                        (adjusted >> 17) as u16
-                    }
-                };
-
-            let result = clamped & 0xFFF0;
-
-            (result, zero_extend_accum48(adjusted))
-        })?;
+        }, vector_unit::vmulq_lane)?;
         Ok(())
     }
 }
@@ -370,20 +301,7 @@ impl Test for VMACF {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMACF", |assembler| {
             assembler.write_vmacf(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, accum| {
-            let product = (a as i16 as i32) * (b as i16 as i32);
-
-            let new_accum = ((product as i64) << 1) + sign_extend_accum48(accum);
-            let temp_shifted32 = (new_accum >> 16) as i32;
-            let result_val =
-                if temp_shifted32 >= 0 {
-                    if (temp_shifted32 & !0x7FFF) != 0 { 0x7FFFu16 } else { temp_shifted32 as u16 }
-                } else {
-                    if (!temp_shifted32 & !0x7FFF) != 0 { 0x8000u16 } else { temp_shifted32 as u16 }
-                } as u16;
-
-            (result_val, zero_extend_accum48(new_accum))
-        })?;
+        }, vector_unit::vmacf_lane)?;
         Ok(())
     }
 }
@@ -400,20 +318,7 @@ impl Test for VMACU {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMACU", |assembler| {
             assembler.write_vmacu(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, accum| {
-            let product = (a as i16 as i32) * (b as i16 as i32);
-            let new_accum = ((product as i64) << 1) + sign_extend_accum48(accum);
-            let result_val =
-                if (new_accum & (1 << 48)) != 0 {
-                    0
-                } else if (new_accum & !((1 << 31) - 1)) != 0 {
-                    0xFFFF
-                } else {
-                    (new_accum >> 16) as u16
-                };
-
-            (result_val, zero_extend_accum48(new_accum))
-        })?;
+        }, vector_unit::vmacu_lane)?;
         Ok(())
     }
 }
@@ -430,20 +335,7 @@ impl Test for VMADL {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMADL", |assembler| {
             assembler.write_vmadl(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, accum| {
-            let product = (a as u32) * (b as u32);
-            let product_shifted = (product >> 16) as u16;
-
-            let new_accum = (product_shifted as i64) + sign_extend_accum48(accum);
-            let result_val =
-                if new_accum >= 0 {
-                    if (new_accum & !0x7FFFFFFF) != 0 { 0xffff } else { new_accum as u16 }
-                } else {
-                    if (!new_accum & !0x7FFFFFFF) != 0 { 0 } else { new_accum as u16 }
-                } as u16;
-
-            (result_val, zero_extend_accum48(new_accum))
-        })?;
+        }, vector_unit::vmadl_lane)?;
         Ok(())
     }
 }
@@ -460,20 +352,7 @@ impl Test for VMADH {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMADH", |assembler| {
             assembler.write_vmadh(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, accum| {
-            let product = (a as i16 as i32) * (b as i16 as i32);
-
-            let new_accum = ((product as i64) << 16) + sign_extend_accum48(accum);
-            let temp_shifted32 = (new_accum >> 16) as i32;
-            let result_val =
-                if temp_shifted32 >= 0 {
-                    if (temp_shifted32 & !0x7FFF) != 0 { 0x7FFFu16 } else { temp_shifted32 as u16 }
-                } else {
-                    if (!temp_shifted32 & !0x7FFF) != 0 { 0x8000u16 } else { temp_shifted32 as u16 }
-                } as u16;
-
-            (result_val, zero_extend_accum48(new_accum))
-        })?;
+        }, vector_unit::vmadh_lane)?;
         Ok(())
     }
 }
@@ -490,20 +369,7 @@ impl Test for VMADM {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMADM", |assembler| {
             assembler.write_vmadm(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, accum| {
-            let product = (a as i16 as i32) * (b as u16 as i32);
-            let new_accum = (product as i64) + sign_extend_accum48(accum);
-            let product_shifted = new_accum >> 16;
-
-            let result_val =
-                if product_shifted >= 0 {
-                    if (product_shifted & !0x7FFF) != 0 { 0x7FFFu16 } else { product_shifted as u16 }
-                } else {
-                    if (!product_shifted & !0x7FFF) != 0 { 0x8000u16 } else { product_shifted as u16 }
-                } as u16;
-
-            (result_val, zero_extend_accum48(new_accum))
-        })?;
+        }, vector_unit::vmadm_lane)?;
         Ok(())
     }
 }
@@ -520,19 +386,7 @@ impl Test for VMADN {
     fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
         run_stress_test("VMADN", |assembler| {
             assembler.write_vmadn(VR::V3, VR::V1, VR::V0, Element::All);
-        }, |a, b, accum| {
-            let product = (a as u16 as i32) * (b as i16 as i32);
-
-            let new_accum = (product as i64) + sign_extend_accum48(accum);
-            let result_val =
-                if new_accum >= 0 {
-                    if (new_accum & !0x7FFFFFFF) != 0 { 0xffff } else { new_accum as u16 }
-                } else {
-                    if (!new_accum & !0x7FFFFFFF) != 0 { 0 } else { new_accum as u16 }
-                } as u16;
-
-            (result_val, zero_extend_accum48(new_accum))
-        })?;
+        }, vector_unit::vmadn_lane)?;
         Ok(())
     }
 }
@@ -10,11 +10,11 @@ use crate::graphics::color::RGBA5551;
 use crate::graphics::cursor::Cursor;
 use crate::graphics::font::Font;
 use crate::graphics::system_font::FONT_GENEVA_9;
+use crate::math::vector_unit::{rcp, rsq};
 use crate::rsp::rsp::RSP;
 use crate::rsp::rsp_assembler::{E, Element, GPR, RSPAssembler, VR};
 use crate::rsp::spmem::SPMEM;
 use crate::tests::{Level, Test};
-use crate::tests::rsp::op_vmov_vrcp::{rcp, rsq};
 use crate::tests::soft_asserts::soft_assert_eq2;
 
 fn run_stress_test<FEmitter: Fn(&mut RSPAssembler, VR), FSimulator: Fn(u32) -> u32>(name: &str, emit: FEmitter, simulate: FSimulator) -> Result<(), String> {
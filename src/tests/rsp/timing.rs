@@ -0,0 +1,152 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::cop0;
+use crate::rsp::rsp::RSP;
+use crate::rsp::rsp_assembler::RSPAssembler;
+use crate::rsp_asm;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_less;
+
+// These tests measure how long the RSP takes for a sequence of instructions. The sequence is written into IMEM
+// a number of times, followed by BREAK. The CPU reads Count before starting the RSP and after it saw it halt.
+// The same is done for a program that only consists of BREAK, which is subtracted to get rid of the time it
+// takes to start the RSP and to notice that it halted.
+//
+// No cycle counts have been measured on hardware yet, so these tests don't check absolute numbers. They only
+// compare sequences against each other, based on the RSP pipeline as it is commonly described: The scalar unit
+// (SU) and the vector unit (VU) can each issue one instruction per cycle, so a pair of a scalar and a vector
+// instruction is expected to be faster than a pair for the same unit. Vector loads/stores and the COP2 moves are
+// SU instructions. There are no hazards, instead the RSP stalls until an input is available, so a sequence with
+// a dependency is expected to be slower than the same sequence without it.
+
+/// How often the measured sequence is written into IMEM
+const REPEAT: u32 = 64;
+
+/// How often each program is run. The fastest run is used, as polling the status register adds some jitter
+const RUNS: usize = 4;
+
+/// Runs the program at IMEM 0 a few times and returns the fewest Count ticks a run took
+fn run_and_measure() -> u32 {
+    let mut fastest = u32::MAX;
+    for _ in 0..RUNS {
+        let start = cop0::count();
+        RSP::run_and_wait(0);
+        let end = cop0::count();
+        fastest = fastest.min(end.wrapping_sub(start));
+    }
+    fastest
+}
+
+/// Writes the sequence REPEAT times and returns how many RSP cycles the repetitions took
+fn measure<F: Fn(&mut RSPAssembler)>(write_sequence: F) -> u32 {
+    let mut assembler = RSPAssembler::new(0);
+    assembler.write_break();
+    let baseline = run_and_measure();
+
+    let mut assembler = RSPAssembler::new(0);
+    for _ in 0..REPEAT {
+        write_sequence(&mut assembler);
+    }
+    assembler.write_break();
+    let ticks = run_and_measure();
+
+    // Count increments at half the CPU clock (46.875 MHz) while the RSP runs at the RCP clock (62.5 MHz)
+    ticks.saturating_sub(baseline) * 4 / 3
+}
+
+/// Ensures that the first measurement took fewer RSP cycles than the second one
+fn check_faster(faster: (&str, u32), slower: (&str, u32)) -> Result<(), String> {
+    soft_assert_less(faster.1, slower.1, &format!("{} is expected to be faster than {} (RSP cycles for {} executions: {} vs {})", faster.0, slower.0, REPEAT, faster.1, slower.1))
+}
+
+pub struct RSPDualIssueTiming {}
+
+impl Test for RSPDualIssueTiming {
+    fn name(&self) -> &str { "RSP Timing: Dual issue" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // None of these instructions depend on each other. Two instructions for the same unit can't be issued
+        // together, while a scalar and a vector instruction can (in either order)
+        let vxor_vxor = ("VXOR, VXOR", measure(|assembler| rsp_asm!(assembler, vxor V1, V2, V3; vxor V4, V5, V6;)));
+        let addiu_addiu = ("ADDIU, ADDIU", measure(|assembler| rsp_asm!(assembler, addiu S0, S1, 1; addiu S2, S3, 1;)));
+        let vxor_addiu = ("VXOR, ADDIU", measure(|assembler| rsp_asm!(assembler, vxor V1, V2, V3; addiu S0, S1, 1;)));
+        let addiu_vxor = ("ADDIU, VXOR", measure(|assembler| rsp_asm!(assembler, addiu S0, S1, 1; vxor V1, V2, V3;)));
+        let vxor_lqv = ("VXOR, LQV", measure(|assembler| rsp_asm!(assembler, vxor V1, V2, V3; lqv V4[_0], 0x000(R0);)));
+        let vxor_mfc2 = ("VXOR, MFC2", measure(|assembler| rsp_asm!(assembler, vxor V1, V2, V3; mfc2 S0, V4[_0];)));
+
+        for pair in [vxor_addiu, addiu_vxor, vxor_lqv, vxor_mfc2] {
+            check_faster(pair, vxor_vxor)?;
+            check_faster(pair, addiu_addiu)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct RSPVectorDependencyTiming {}
+
+impl Test for RSPVectorDependencyTiming {
+    fn name(&self) -> &str { "RSP Timing: Vector dependency stalls" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // A vector instruction that needs the result of the previous one has to wait for it
+        let independent = measure(|assembler| rsp_asm!(assembler, vxor V1, V3, V2;));
+        let dependent = measure(|assembler| rsp_asm!(assembler, vxor V1, V1, V2;));
+
+        check_faster(("VXOR (independent)", independent), ("VXOR (dependent)", dependent))?;
+
+        Ok(())
+    }
+}
+
+pub struct RSPVectorLoadStallTiming {}
+
+impl Test for RSPVectorLoadStallTiming {
+    fn name(&self) -> &str { "RSP Timing: Stalls after vector loads" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // Without a dependency, the load and the vector instruction are issued together
+        let independent = measure(|assembler| rsp_asm!(assembler, lqv V1[_0], 0x000(R0); vxor V3, V4, V2;));
+        let dependent = measure(|assembler| rsp_asm!(assembler, lqv V1[_0], 0x000(R0); vxor V3, V1, V2;));
+
+        check_faster(("LQV, VXOR (independent)", independent), ("LQV, VXOR (dependent)", dependent))?;
+
+        Ok(())
+    }
+}
+
+pub struct RSPMFC2StallTiming {}
+
+impl Test for RSPMFC2StallTiming {
+    fn name(&self) -> &str { "RSP Timing: Stalls on MFC2" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // MFC2 of a register that the previous vector instruction writes has to wait for the result
+        let independent = measure(|assembler| rsp_asm!(assembler, vxor V1, V3, V2; mfc2 S0, V4[_0];));
+        let dependent = measure(|assembler| rsp_asm!(assembler, vxor V1, V3, V2; mfc2 S0, V1[_0];));
+
+        check_faster(("VXOR, MFC2 (independent)", independent), ("VXOR, MFC2 (dependent)", dependent))?;
+
+        Ok(())
+    }
+}
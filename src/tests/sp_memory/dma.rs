@@ -1,20 +1,47 @@
 use alloc::boxed::Box;
-use alloc::format;
+use alloc::{format, vec};
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::any::Any;
 
+use arbitrary_int::u12;
+
 use crate::MemoryMap;
-use crate::rsp::rsp::RSP;
+use crate::rsp::rsp::{DMALength, RSP};
 use crate::rsp::spmem::SPMEM;
 use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::soft_assert_eq2;
+use crate::uncached_memory::UncachedHeapMemory;
 
 // DMA:
 // - RDRAM address and SPMEM address are aligned on 8 byte boundaries (the lower 3 bits are ignoed)
 // - Length: To the written value 1 is added and then it is rounded up to the next multiple of 8 (e.g. 0..7 ==> 8 bytes, 8..15 => 16 bytes)
 // - A DMA goes either into IMEM or DMEM. If it overflows, it will overflow within that memory but never overlap into the other one
 
+// Helpers that are shared by the DMA tests
+
+/// Value SPMEM is filled with before a DMA, so that untouched words can be told apart
+pub const SPMEM_UNTOUCHED: u32 = 0xBADDECAF;
+
+/// Value RDRAM is filled with before a DMA, so that untouched words can be told apart
+pub const RDRAM_UNTOUCHED: u32 = 0xDEADBEEF;
+
+/// Creates a DMA source in RDRAM. Every word is the pattern combined with its own index
+pub fn create_source(words: usize, pattern: u32) -> UncachedHeapMemory<u32> {
+    let mut source = UncachedHeapMemory::<u32>::new_with_align(words, 8);
+    for i in 0..words {
+        source.write(i, pattern | (i as u32));
+    }
+    source
+}
+
+/// Fills the given range of SPMEM with the same value
+pub fn fill_spmem(range: core::ops::Range<usize>, value: u32) {
+    for offset in range.step_by(4) {
+        SPMEM::write(offset, value);
+    }
+}
+
 fn dma_test<const N: usize>(source_index: usize, spmem_index: u32, length: u32, expected_start_offset: usize, expected_sp_address_after_dma: u32, expected: [[u16; 8]; N]) -> Result<(), String> {
     // Create some test data. Use uncached memory to ensure the DMA engine can see it
     // without us having to flush any caches first
@@ -28,9 +55,7 @@ fn dma_test<const N: usize>(source_index: usize, spmem_index: u32, length: u32,
     }
 
     // Clear SPMEM
-    for i in 0..(N * 4) {
-        SPMEM::write(expected_start_offset + i * 4, 0xBADDECAF);
-    }
+    fill_spmem(expected_start_offset..(expected_start_offset + N * 0x10), SPMEM_UNTOUCHED);
 
     // DMA simple
     let source_ptr = unsafe { (source_data_uncached as *mut u8).add(source_index) };
@@ -281,3 +306,161 @@ impl Test for SPDMAFromDMEMWithOverflow {
     }
 
 }
+
+// 2D DMA: SP_RD_LEN/SP_WR_LEN also contain a count and skip field. count + 1 rows of length bytes are transferred.
+// After each row, skip bytes are skipped in RDRAM, while SPMEM is written (or read) contiguously.
+// - Like the length, the skip is a multiple of 8 (the lower 3 bits are ignored)
+// - Rows wrap around within DMEM or IMEM, just like single row DMAs
+// - After the DMA, SP_DRAM_ADDR points behind the last skip, the length field reads back as 0xFF8 and count
+//   as 0. skip keeps its value
+
+/// Name, SPMEM address, length, count, skip
+const DMA_2D: [(&str, u32, u32, u32, u32); 6] = [
+    ("3 rows of 16 bytes, skip 8", 0x100, 15, 2, 8),
+    ("4 rows of 8 bytes, no skip", 0x200, 7, 3, 0),
+    ("2 rows of 24 bytes, skip 40, IMEM", 0x1300, 23, 1, 40),
+    ("3 rows, unaligned length and skip", 0x400, 13, 2, 12),
+    ("4 rows, wrapping past the end of DMEM", 0xFE0, 15, 3, 8),
+    ("3 rows, wrapping past the end of IMEM", 0x1FF0, 15, 2, 16),
+];
+
+/// Size of the RDRAM buffer in words. This is large enough for every entry in DMA_2D
+const DMA_2D_RDRAM_WORDS: usize = 64;
+
+fn dma_2d_values() -> Vec<Box<dyn Any>> {
+    (0..DMA_2D.len() as u32).map(|i| -> Box<dyn Any> { Box::new(i) }).collect()
+}
+
+fn dma_2d_length(length: u32, count: u32, skip: u32) -> DMALength {
+    DMALength::new()
+        .with_length(u12::new(length))
+        .with_count(count as u8)
+        .with_skip(u12::new(skip))
+}
+
+/// Returns the SPMEM address that corresponds to the given offset within the RDRAM buffer (or None if
+/// the offset falls into a skipped area or behind the DMA)
+fn dma_2d_spmem_address(spmem: u32, length: u32, count: u32, skip: u32, rdram_offset: usize) -> Option<usize> {
+    let bytes_per_row = ((length | 7) + 1) as usize;
+    let bytes_per_row_in_rdram = bytes_per_row + (skip & !7) as usize;
+    let row = rdram_offset / bytes_per_row_in_rdram;
+    let offset_within_row = rdram_offset % bytes_per_row_in_rdram;
+    if row > count as usize || offset_within_row >= bytes_per_row {
+        return None;
+    }
+
+    let bank = (spmem & 0x1000) as usize;
+    Some(bank | (((spmem & 0xFF8) as usize + row * bytes_per_row + offset_within_row) & 0xFFF))
+}
+
+pub struct SPDMA2DIntoSPMEM {}
+
+impl Test for SPDMA2DIntoSPMEM {
+    fn name(&self) -> &str { "spmem: DMA RDRAM -> SPMEM (multiple rows with skip)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { dma_2d_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, spmem, length, count, skip) = DMA_2D[*(*value).downcast_ref::<u32>().unwrap() as usize];
+
+        let mut source = create_source(DMA_2D_RDRAM_WORDS, 0x5A00_0000);
+        fill_spmem(0..0x2000, SPMEM_UNTOUCHED);
+
+        RSP::start_dma_cpu_to_sp_2d(source.start_phyiscal() as *const u8, spmem, dma_2d_length(length, count, skip));
+        RSP::wait_until_dma_completed();
+
+        // Work out what every word of SPMEM is supposed to contain
+        let mut expected = vec![SPMEM_UNTOUCHED; 0x800];
+        for i in 0..DMA_2D_RDRAM_WORDS {
+            if let Some(address) = dma_2d_spmem_address(spmem, length, count, skip, i * 4) {
+                expected[address >> 2] = 0x5A00_0000 | (i as u32);
+            }
+        }
+        for (i, expected_value) in expected.iter().enumerate() {
+            soft_assert_eq2(SPMEM::read(i * 4), *expected_value, || format!("{}: SPMEM[0x{:04X}] after DMA", name, i * 4))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct SPDMA2DFromSPMEM {}
+
+impl Test for SPDMA2DFromSPMEM {
+    fn name(&self) -> &str { "spmem: DMA RDRAM <- SPMEM (multiple rows with skip)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { dma_2d_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, spmem, length, count, skip) = DMA_2D[*(*value).downcast_ref::<u32>().unwrap() as usize];
+
+        // Every word in SPMEM contains its own address
+        for offset in (0..0x2000).step_by(4) {
+            SPMEM::write(offset, 0xA500_0000 | (offset as u32));
+        }
+        let mut target = UncachedHeapMemory::<u32>::new_with_init_value(DMA_2D_RDRAM_WORDS, RDRAM_UNTOUCHED);
+
+        unsafe { RSP::start_dma_sp_to_cpu_2d(spmem, target.start_phyiscal() as *mut u8, dma_2d_length(length, count, skip)); }
+        RSP::wait_until_dma_completed();
+
+        for i in 0..DMA_2D_RDRAM_WORDS {
+            let expected = match dma_2d_spmem_address(spmem, length, count, skip, i * 4) {
+                Some(address) => 0xA500_0000 | (address as u32),
+                None => RDRAM_UNTOUCHED,
+            };
+            soft_assert_eq2(target.read(i), expected, || format!("{}: RDRAM word {} after DMA", name, i))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct SPDMA2DRegistersAfterDMA {}
+
+impl Test for SPDMA2DRegistersAfterDMA {
+    fn name(&self) -> &str { "spmem: DMA (multiple rows with skip), registers after DMA" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        let mut result: Vec<Box<dyn Any>> = Vec::new();
+        for i in 0..DMA_2D.len() as u32 {
+            result.push(Box::new((false, i)));
+            result.push(Box::new((true, i)));
+        }
+        result
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (from_spmem, index) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+        let (name, spmem, length, count, skip) = DMA_2D[index as usize];
+
+        let mut memory = UncachedHeapMemory::<u32>::new_with_init_value(DMA_2D_RDRAM_WORDS, RDRAM_UNTOUCHED);
+        let dram_address = memory.start_phyiscal() as u32;
+        let dma_length = dma_2d_length(length, count, skip);
+
+        if from_spmem {
+            unsafe { RSP::start_dma_sp_to_cpu_2d(spmem, dram_address as *mut u8, dma_length); }
+        } else {
+            RSP::start_dma_cpu_to_sp_2d(dram_address as *const u8, spmem, dma_length);
+        }
+        RSP::wait_until_dma_completed();
+
+        let rows = count + 1;
+        let bytes_per_row = (length | 7) + 1;
+        let expected_sp_address = (spmem & 0x1000) | ((spmem + rows * bytes_per_row) & 0xFF8);
+        let expected_dram_address = dram_address + rows * (bytes_per_row + (skip & !7));
+        let expected_length = DMALength::new().with_length(u12::new(0xFF8)).with_count(0).with_skip(u12::new(skip & !7));
+
+        soft_assert_eq2(RSP::sp_address(), expected_sp_address, || format!("{}: SP address after DMA", name))?;
+        soft_assert_eq2(RSP::dram_address(), expected_dram_address, || format!("{}: DRAM address after DMA", name))?;
+        soft_assert_eq2(RSP::read_length(), expected_length, || format!("{}: SP_RD_LEN after DMA", name))?;
+        soft_assert_eq2(RSP::write_length(), expected_length, || format!("{}: SP_WR_LEN after DMA", name))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::rsp::rsp::{RSP, SP_STATUS_DMA_BUSY, SP_STATUS_DMA_FULL};
+use crate::rsp::rsp_assembler::{CP0Register, GPR, RSPAssembler};
+use crate::rsp::spmem::SPMEM;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_eq2, soft_assert_greater_or_equal, soft_assert_less};
+
+use super::dma::{create_source, fill_spmem, SPMEM_UNTOUCHED};
+
+// DMA queue:
+// - The DMA engine can hold one pending DMA in addition to the one that is currently running. Starting a DMA
+//   while another one is running sets DMA_FULL, which is cleared once the pending DMA starts running
+// - DMA_BUSY stays set until both DMAs are done. The DMAs are executed in the order they were started
+// - The address registers reflect the running DMA. After both DMAs, they point to the end of the second one
+// - The DMAs can be started from the CPU or from the RSP via MTC0 - they share the same queue
+// - Unconfirmed: what happens when a third DMA is started while DMA_FULL is set (see SPDMAQueueThirdWhileFull)
+
+/// The first DMA is large so that the second one is queued while it is still running
+const FIRST_SPMEM: u32 = 0x000;
+const FIRST_LENGTH: u32 = 0x800;
+const FIRST_PATTERN: u32 = 0xA1000000;
+
+/// The second DMA partially overwrites what the first one wrote. If the order was reversed, the first DMA would win.
+/// It overwrites the very end, so that the first DMA doesn't get there while the CPU is still looking at the registers
+const SECOND_SPMEM: u32 = 0x700;
+const SECOND_LENGTH: u32 = 0x100;
+const SECOND_PATTERN: u32 = 0xB2000000;
+
+/// Started while the second DMA is still pending. It doesn't overlap with the other two, so that it's visible which
+/// of them arrived
+const THIRD_SPMEM: u32 = 0x900;
+const THIRD_LENGTH: u32 = 0x100;
+const THIRD_PATTERN: u32 = 0xC3000000;
+
+/// Returns what DMEM is expected to contain after the given DMAs ran (in that order)
+fn expected_dmem(offset: u32, dmas: &[(u32, u32, u32)]) -> u32 {
+    let mut result = SPMEM_UNTOUCHED;
+    for (spmem, length, pattern) in dmas {
+        if (offset >= *spmem) && (offset < *spmem + *length) {
+            result = *pattern | ((offset - *spmem) >> 2);
+        }
+    }
+    result
+}
+
+fn verify_dmem(range: core::ops::Range<u32>, dmas: &[(u32, u32, u32)]) -> Result<(), String> {
+    for offset in range.step_by(4) {
+        soft_assert_eq2(SPMEM::read(offset as usize), expected_dmem(offset, dmas), || format!("DMEM[0x{:03X}] after DMAs", offset))?;
+    }
+    Ok(())
+}
+
+pub struct SPDMAQueueFromCPU {}
+
+impl Test for SPDMAQueueFromCPU {
+    fn name(&self) -> &str { "spmem: DMA queue (started from CPU)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let mut first = create_source((FIRST_LENGTH >> 2) as usize, FIRST_PATTERN);
+        let mut second = create_source((SECOND_LENGTH >> 2) as usize, SECOND_PATTERN);
+        fill_spmem(0..0x1000, SPMEM_UNTOUCHED);
+
+        RSP::start_dma_cpu_to_sp(first.start_phyiscal() as *const u8, FIRST_SPMEM, FIRST_LENGTH - 1);
+        let status_after_first = RSP::status();
+        RSP::start_dma_cpu_to_sp(second.start_phyiscal() as *const u8, SECOND_SPMEM, SECOND_LENGTH - 1);
+        let sp_address_while_running = RSP::sp_address();
+        let status_after_second = RSP::status();
+        let full_after_second = RSP::dma_full();
+        let busy_after_second = RSP::dma_busy();
+
+        RSP::wait_until_dma_completed();
+
+        soft_assert_eq(status_after_first & (SP_STATUS_DMA_BUSY | SP_STATUS_DMA_FULL), SP_STATUS_DMA_BUSY, "Status after starting the first DMA")?;
+        soft_assert_eq(status_after_second & (SP_STATUS_DMA_BUSY | SP_STATUS_DMA_FULL), SP_STATUS_DMA_BUSY | SP_STATUS_DMA_FULL, "Status after queueing the second DMA")?;
+        soft_assert_eq(full_after_second, 1, "DMA_FULL after queueing the second DMA")?;
+        soft_assert_eq(busy_after_second, 1, "DMA_BUSY after queueing the second DMA")?;
+        // While the first DMA is running, the address register belongs to it (and not to the pending one). The first
+        // DMA can't have reached the area of the second one yet
+        soft_assert_greater_or_equal(sp_address_while_running, FIRST_SPMEM, "SP address while the first DMA is running")?;
+        soft_assert_less(sp_address_while_running, FIRST_SPMEM + FIRST_LENGTH, "SP address while the first DMA is running")?;
+        soft_assert_less(sp_address_while_running, SECOND_SPMEM, "SP address while the first DMA is running should be outside of the second DMA")?;
+
+        soft_assert_eq(RSP::status() & (SP_STATUS_DMA_BUSY | SP_STATUS_DMA_FULL), 0, "Status after both DMAs")?;
+        soft_assert_eq(RSP::dma_full(), 0, "DMA_FULL after both DMAs")?;
+        soft_assert_eq(RSP::dma_busy(), 0, "DMA_BUSY after both DMAs")?;
+        soft_assert_eq(RSP::sp_address(), SECOND_SPMEM + SECOND_LENGTH, "SP address after both DMAs")?;
+        soft_assert_eq(RSP::dram_address(), second.start_phyiscal() as u32 + SECOND_LENGTH, "DRAM address after both DMAs")?;
+
+        verify_dmem(0..0x1000, &[(FIRST_SPMEM, FIRST_LENGTH, FIRST_PATTERN), (SECOND_SPMEM, SECOND_LENGTH, SECOND_PATTERN)])
+    }
+}
+
+pub struct SPDMAQueueThirdWhileFull {}
+
+impl Test for SPDMAQueueThirdWhileFull {
+    fn name(&self) -> &str { "spmem: DMA queue (third DMA while full)" }
+
+    // The guess is that the queue only has room for one pending DMA and that starting another one while DMA_FULL is
+    // set overwrites the registers of the pending DMA, so the second DMA is lost and the third one runs in its place.
+    // None of this has been confirmed on hardware, so every failure reports what was actually observed
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let mut first = create_source((FIRST_LENGTH >> 2) as usize, FIRST_PATTERN);
+        let mut second = create_source((SECOND_LENGTH >> 2) as usize, SECOND_PATTERN);
+        let mut third = create_source((THIRD_LENGTH >> 2) as usize, THIRD_PATTERN);
+        fill_spmem(0..0x1000, SPMEM_UNTOUCHED);
+
+        RSP::start_dma_cpu_to_sp(first.start_phyiscal() as *const u8, FIRST_SPMEM, FIRST_LENGTH - 1);
+        RSP::start_dma_cpu_to_sp(second.start_phyiscal() as *const u8, SECOND_SPMEM, SECOND_LENGTH - 1);
+        let full_before_third = RSP::dma_full();
+        RSP::start_dma_cpu_to_sp(third.start_phyiscal() as *const u8, THIRD_SPMEM, THIRD_LENGTH - 1);
+        let full_after_third = RSP::dma_full();
+
+        RSP::wait_until_dma_completed();
+
+        let observed = format!(
+            "Observed: SP address=0x{:03X}, DRAM address=0x{:08X} (second DMA ends at 0x{:08X}, third at 0x{:08X}), DMEM[0x{:03X}]=0x{:08X}, DMEM[0x{:03X}]=0x{:08X}",
+            RSP::sp_address(),
+            RSP::dram_address(),
+            second.start_phyiscal() as u32 + SECOND_LENGTH,
+            third.start_phyiscal() as u32 + THIRD_LENGTH,
+            SECOND_SPMEM,
+            SPMEM::read(SECOND_SPMEM as usize),
+            THIRD_SPMEM,
+            SPMEM::read(THIRD_SPMEM as usize));
+
+        soft_assert_eq2(full_before_third, 1, || format!("DMA_FULL before starting the third DMA. {}", observed))?;
+        soft_assert_eq2(full_after_third, 1, || format!("DMA_FULL after starting the third DMA. {}", observed))?;
+        soft_assert_eq2(RSP::dma_full(), 0, || format!("DMA_FULL after all DMAs. {}", observed))?;
+        soft_assert_eq2(RSP::dma_busy(), 0, || format!("DMA_BUSY after all DMAs. {}", observed))?;
+        soft_assert_eq2(RSP::sp_address(), THIRD_SPMEM + THIRD_LENGTH, || format!("SP address after all DMAs (expected to be the end of the third DMA). {}", observed))?;
+        soft_assert_eq2(RSP::dram_address(), third.start_phyiscal() as u32 + THIRD_LENGTH, || format!("DRAM address after all DMAs (expected to be the end of the third DMA). {}", observed))?;
+
+        verify_dmem(0..0x1000, &[(FIRST_SPMEM, FIRST_LENGTH, FIRST_PATTERN), (THIRD_SPMEM, THIRD_LENGTH, THIRD_PATTERN)])
+            .map_err(|e| format!("{} (expected the second DMA to be lost). {}", e, observed))
+    }
+}
+
+/// Where the RSP program stores the flags it sees. This is outside of all DMA targets
+const RESULTS_SPMEM: u32 = 0xF00;
+
+pub struct SPDMAQueueFromRSP {}
+
+impl Test for SPDMAQueueFromRSP {
+    fn name(&self) -> &str { "spmem: DMA queue (started from RSP)" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let mut first = create_source((FIRST_LENGTH >> 2) as usize, FIRST_PATTERN);
+        let mut second = create_source((SECOND_LENGTH >> 2) as usize, SECOND_PATTERN);
+        fill_spmem(0..0x1000, SPMEM_UNTOUCHED);
+
+        let mut assembler = RSPAssembler::new(0);
+        assembler.write_li(GPR::S0, FIRST_SPMEM);
+        assembler.write_li(GPR::S1, first.start_phyiscal() as u32);
+        assembler.write_li(GPR::S2, FIRST_LENGTH - 1);
+        assembler.write_mtc0(CP0Register::SPAddress, GPR::S0);
+        assembler.write_mtc0(CP0Register::DRAMAddress, GPR::S1);
+        assembler.write_mtc0(CP0Register::ReadLength, GPR::S2);
+
+        assembler.write_li(GPR::S0, SECOND_SPMEM);
+        assembler.write_li(GPR::S1, second.start_phyiscal() as u32);
+        assembler.write_li(GPR::S2, SECOND_LENGTH - 1);
+        assembler.write_mtc0(CP0Register::SPAddress, GPR::S0);
+        assembler.write_mtc0(CP0Register::DRAMAddress, GPR::S1);
+        assembler.write_mtc0(CP0Register::ReadLength, GPR::S2);
+
+        // Record the flags while the DMAs are in flight
+        assembler.write_mfc0(CP0Register::DmaFull, GPR::T0);
+        assembler.write_mfc0(CP0Register::DmaBusy, GPR::T1);
+        assembler.write_sw(GPR::T0, GPR::R0, RESULTS_SPMEM as i16);
+        assembler.write_sw(GPR::T1, GPR::R0, RESULTS_SPMEM as i16 + 4);
+
+        // Wait for both DMAs to finish
+        let wait_loop = assembler.get_jump_target();
+        assembler.write_mfc0(CP0Register::DmaBusy, GPR::T1);
+        assembler.write_bgtz_backwards(GPR::T1, &wait_loop);
+        assembler.write_nop();
+
+        // Record the state after both DMAs
+        assembler.write_mfc0(CP0Register::DmaFull, GPR::T0);
+        assembler.write_mfc0(CP0Register::SPAddress, GPR::T2);
+        assembler.write_mfc0(CP0Register::DRAMAddress, GPR::T3);
+        assembler.write_sw(GPR::T0, GPR::R0, RESULTS_SPMEM as i16 + 8);
+        assembler.write_sw(GPR::T1, GPR::R0, RESULTS_SPMEM as i16 + 12);
+        assembler.write_sw(GPR::T2, GPR::R0, RESULTS_SPMEM as i16 + 16);
+        assembler.write_sw(GPR::T3, GPR::R0, RESULTS_SPMEM as i16 + 20);
+        assembler.write_break();
+
+        RSP::clear_broke();
+        RSP::run_and_wait(0);
+
+        soft_assert_eq(SPMEM::read(RESULTS_SPMEM as usize), 1, "DMA_FULL after queueing the second DMA (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(RESULTS_SPMEM as usize + 4), 1, "DMA_BUSY after queueing the second DMA (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(RESULTS_SPMEM as usize + 8), 0, "DMA_FULL after both DMAs (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(RESULTS_SPMEM as usize + 12), 0, "DMA_BUSY after both DMAs (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(RESULTS_SPMEM as usize + 16), SECOND_SPMEM + SECOND_LENGTH, "SP address after both DMAs (read via RSP MFC0)")?;
+        soft_assert_eq(SPMEM::read(RESULTS_SPMEM as usize + 20), second.start_phyiscal() as u32 + SECOND_LENGTH, "DRAM address after both DMAs (read via RSP MFC0)")?;
+
+        verify_dmem(0..RESULTS_SPMEM, &[(FIRST_SPMEM, FIRST_LENGTH, FIRST_PATTERN), (SECOND_SPMEM, SECOND_LENGTH, SECOND_PATTERN)])
+    }
+}
@@ -1,4 +1,5 @@
 pub mod dma;
+pub mod dma_queue;
 
 use alloc::boxed::Box;
 use alloc::string::String;
@@ -66,6 +66,8 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::address_error_exception::UnalignedJump {}),
         Box::new(super::address_error_exception::LWAddressNotSignExtended {}),
         Box::new(super::address_error_exception::SWAddressNotSignExtended {}),
+        Box::new(super::bus_errors::OpenBusReads {}),
+        Box::new(super::bus_errors::DataBusError {}),
         Box::new(super::arithmetic::nemu_port::LUIOpcodeTest1 {}),
         Box::new(super::arithmetic::nemu_port::LUIOpcodeTest2 {}),
         Box::new(super::arithmetic::nemu_port::LUIOpcodeTestIntoR0 {}),
@@ -445,6 +447,15 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::arithmetic::div::DIVU {}),
         Box::new(super::arithmetic::div::DDIV {}),
         Box::new(super::arithmetic::div::DDIVU {}),
+        Box::new(super::arithmetic::randomized::MULT),
+        Box::new(super::arithmetic::randomized::MULTU),
+        Box::new(super::arithmetic::randomized::DMULT),
+        Box::new(super::arithmetic::randomized::DMULTU),
+        Box::new(super::arithmetic::randomized::DIV),
+        Box::new(super::arithmetic::randomized::DIVU),
+        Box::new(super::arithmetic::randomized::DDIV),
+        Box::new(super::arithmetic::randomized::DDIVU),
+        Box::new(super::arithmetic::randomized::HILOMoves),
         Box::new(super::cart_memory::LW {}),
         Box::new(super::cart_memory::LH {}),
         Box::new(super::cart_memory::LB {}),
@@ -480,6 +491,8 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::cop0::ExceptPCNoMasking {}),
         Box::new(super::cop0::ErrorEPCNoMasking {}),
         Box::new(super::cop0::LLAddrIs32Bit {}),
+        Box::new(super::cop0::WatchLoMasking),
+        Box::new(super::cop0::WatchHiMasking),
         Box::new(super::cop0::StatusIs32Bit {}),
         Box::new(super::cop0::StatusMasking),
         Box::new(super::cop0::ConfigMasking),
@@ -553,6 +566,8 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::cop1::compares::C_NGE),
         Box::new(super::cop1::compares::C_LE),
         Box::new(super::cop1::compares::C_NGT),
+        Box::new(super::cop1::enables::ExceptionEnableMatrix),
+        Box::new(super::cop1::enables::StickyFlags),
         Box::new(super::cop1::randomized::AddS),
         Box::new(super::cop1::randomized::AddD),
         Box::new(super::cop1::randomized::SubS),
@@ -565,6 +580,9 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::cop1::randomized::SqrtD),
         Box::new(super::cop1::randomized::CvtSFromW),
         Box::new(super::cop1::randomized::CvtWFromS),
+        Box::new(super::cop1::randomized::BiasedConversions),
+        Box::new(super::cop1::randomized::BiasedAbsNegMov),
+        Box::new(super::cop1::randomized::BiasedCompares),
         Box::new(super::cop_unusable::COP3Usable {}),
         Box::new(super::cop_unusable::COP2Usable {}),
         Box::new(super::cop_unusable::COP1Usable {}),
@@ -592,12 +610,27 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::cop_unusable::COP3UsableMFC3 {}),
         Box::new(super::cop_unusable::COP2MFCBehavior {}),
         Box::new(super::cop_unusable::COP2LWC2Behavior {}),
+        Box::new(super::eret::EretWithEXL {}),
+        Box::new(super::eret::EretWithERL {}),
+        Box::new(super::eret::EretWithoutEXLOrERL {}),
+        Box::new(super::eret::EretNoDelaySlot {}),
+        Box::new(super::eret::EretClearsLLBit {}),
+        Box::new(super::eret::EretInUserModeWithoutCOP0 {}),
         Box::new(super::exception_instructions::Break {}),
         Box::new(super::exception_instructions::BreakDelay {}),
         Box::new(super::exception_instructions::Syscall {}),
         Box::new(super::exception_instructions::SyscallDelay {}),
         Box::new(super::exception_instructions::Reserved31 {}),
         Box::new(super::exception_instructions::Reserved31Delay {}),
+        Box::new(super::exception_priority::DataAccessPriority {}),
+        Box::new(super::exception_priority::FetchPriority {}),
+        Box::new(super::exception_priority::InterruptPriority {}),
+        Box::new(super::instruction_fetch::ExecuteFromDMEM {}),
+        Box::new(super::instruction_fetch::ExecuteFromIMEM {}),
+        Box::new(super::instruction_fetch::ExecuteFromCartridge {}),
+        Box::new(super::instruction_fetch::ExecuteFromPIFRAM {}),
+        Box::new(super::instruction_fetch::FetchFromUnmappedRCP {}),
+        Box::new(super::instruction_fetch::FetchFromPIFROM {}),
         Box::new(super::jumps::conditionals::BEQWithinDelay {}),
         Box::new(super::jumps::conditionals::BEQNotTakenWithinDelay {}),
         Box::new(super::jumps::conditionals::BEQWithinDelayOfJR {}),
@@ -620,6 +653,10 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::jumps::jr_and_jalr::JALRWithRegisterChangeInDelaySlot {}),
         Box::new(super::jumps::jr_and_jalr::JRWithinDelayOfJALR {}),
         Box::new(super::jumps::jr_and_jalr::JALRWithinDelayOfJALR {}),
+        Box::new(super::operating_modes::address_space::AddressSpace {}),
+        Box::new(super::operating_modes::instructions64::Instructions64KernelMode {}),
+        Box::new(super::operating_modes::instructions64::Instructions64SupervisorMode {}),
+        Box::new(super::operating_modes::instructions64::Instructions64UserMode {}),
         Box::new(super::overflow_exception::AddOverflowPositive {}),
         Box::new(super::overflow_exception::AddOverflowNegative {}),
         Box::new(super::overflow_exception::AddOverflowIntoR0 {}),
@@ -651,6 +688,15 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rdp::RunFromDMEM {}),
         Box::new(super::rdp::RunFromDMEMEnd {}),
         Box::new(super::rdp::RunFromDMEMOverflow {}),
+        Box::new(super::rdp::FreezeHoldsExecution {}),
+        Box::new(super::rdp::FreezeCurrentAdvancesWhilePrefetching {}),
+        Box::new(super::rdp::DoubleBufferedCommandLists {}),
+        Box::new(super::rdp::SetAndClearAtOnce {}),
+        Box::new(super::rdp::rsp_cop0::DPRegistersFromRSPIdle {}),
+        Box::new(super::rdp::rsp_cop0::DPRegistersFromRSPFrozen {}),
+        Box::new(super::rdp::rsp_cop0::DPRegistersFromRSPRunning {}),
+        Box::new(super::rdp::rsp_cop0::RSPStartsDisplayList {}),
+        Box::new(super::rdp::rsp_cop0::RSPStartsDisplayListFromDMEM {}),
 
         // The following are disabled for the time being as they are not stable on hardware yet
         // Box::new(super::rdp::filled_triangle::FilledTriangle1CycleDegenerateRect {}),
@@ -665,6 +711,11 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         // Box::new(super::rdp::filled_triangle::FilledTriangle1CycleNegativeYH {}),
         // Box::new(super::rdp::filled_triangle::FilledTriangle1CycleNegativeXL {}),
         // Box::new(super::rdp::filled_triangle::FilledTriangle1CycleRandomized {}),
+        Box::new(super::reserved_instructions::ReservedPrimaryOpcodes {}),
+        Box::new(super::reserved_instructions::ReservedSpecialFunctions {}),
+        Box::new(super::reserved_instructions::ReservedRegimm {}),
+        Box::new(super::reserved_instructions::ReservedCOP0 {}),
+        Box::new(super::reserved_instructions::ReservedCOP1 {}),
 
         // This should be RSP test #1
         Box::new(super::rsp::PCRegMasking {}),
@@ -674,6 +725,7 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rsp::op_break::BREAKWithinDelay2 {}),
         // This should be RSP test #3
         Box::new(super::rsp::wrap_around::WrapAround {}),
+        Box::new(super::rsp::disassembler::Disassembler {}),
         Box::new(super::rsp::ParallelRunning {}),
         // Non-vector instructions
         Box::new(super::rsp::op_add_addu::ADD {}),
@@ -705,7 +757,9 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rsp::op_sw::SWAligned {}),
         Box::new(super::rsp::op_sw::SWUnaligned {}),
         Box::new(super::rsp::op_j::J {}),
+        Box::new(super::rsp::op_j::JToLabel {}),
         Box::new(super::rsp::op_jal::JAL {}),
+        Box::new(super::rsp::op_jal::JALToLabel {}),
         Box::new(super::rsp::op_jr_jalr::JR {}),
         Box::new(super::rsp::op_jr_jalr::JRWithRegisterChangeInDelaySlot {}),
         Box::new(super::rsp::op_jr_jalr::JALR {}),
@@ -722,6 +776,8 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rsp::op_branches::BLTZALTestRA {}),
         Box::new(super::rsp::op_branches::BGEZAL {}),
         Box::new(super::rsp::op_branches::BGEZALTestRA {}),
+        Box::new(super::rsp::op_branches::BGTZBackwardsLoop {}),
+        Box::new(super::rsp::op_branches::BEQForwardLabel {}),
         Box::new(super::rsp::op_shifts::SLL {}),
         Box::new(super::rsp::op_shifts::SRL {}),
         Box::new(super::rsp::op_shifts::SRA {}),
@@ -815,6 +871,7 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rsp::op_vector_arithmetic::VCL {}),
         Box::new(super::rsp::op_vector_arithmetic::VCH {}),
         Box::new(super::rsp::op_vector_arithmetic::VCR {}),
+        Box::new(super::rsp::op_vector_arithmetic::VectorUnitModel {}),
         Box::new(super::rsp::op_vsar::VSAR {}),
         Box::new(super::rsp::op_vmacf::VMACFAll {}),
         Box::new(super::rsp::op_vmacf::VMACFH0 {}),
@@ -845,6 +902,7 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rsp::op_vmudh::VMUDHH0 {}),
         Box::new(super::rsp::op_vmudh::VMUDHQ1 {}),
         Box::new(super::rsp::op_vmudh::VMUDH7 {}),
+        Box::new(super::rsp::op_vmudh::VMUDHReferenceModel {}),
         Box::new(super::rsp::op_vmudl::VMUDLAll {}),
         Box::new(super::rsp::op_vmudl::VMUDLH1 {}),
         Box::new(super::rsp::op_vmudl::VMUDL5 {}),
@@ -863,6 +921,7 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rsp::op_vmulq::VMULQAll {}),
         Box::new(super::rsp::op_vmulq::VMULQH1 {}),
         Box::new(super::rsp::op_vmov_vrcp::VMOV {}),
+        Box::new(super::rsp::op_vmov_vrcp::VMOVWithRSPAsm {}),
         Box::new(super::rsp::op_vmov_vrcp::RCPTable {}),
         Box::new(super::rsp::op_vmov_vrcp::RSQTable {}),
         Box::new(super::rsp::op_vmov_vrcp::VRCPRegisterCombinations {}),
@@ -879,17 +938,21 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rsp::op_vmov_vrcp::HighUsesOutputVRSQTest {}),
         Box::new(super::rsp::op_vmov_vrcp::HighUsesOutputVRSQLTest {}),
         Box::new(super::rsp::op_vmov_vrcp::VRCPHSetsInputForVRCPL {}),
+        Box::new(super::rsp::op_vmov_vrcp::VRCPHSetsInputForVRCPLReferenceModel {}),
         Box::new(super::rsp::op_vmov_vrcp::VRCPLHiddenRegisterFlagExists {}),
         Box::new(super::rsp::op_vrndp::VRNDPWithEvenVS {}),
         Box::new(super::rsp::op_vrndp::VRNDPWithOddVS {}),
         Box::new(super::rsp::op_vrndp::VRNDPOverwriteItselfWithElement {}),
         Box::new(super::rsp::op_vrndp::VRNDPAccumulatorOverflowed {}),
 		Box::new(super::rsp::op_vrndp::VRNDPClampNegativeAccumulator {}),
+        Box::new(super::rsp::op_vrndp::VRNDPReferenceModel {}),
         Box::new(super::rsp::op_vrndn::VRNDNWithEvenVS {}),
         Box::new(super::rsp::op_vrndn::VRNDNWithOddVS {}),
         Box::new(super::rsp::op_vrndn::VRNDNOverwriteItselfWithElement {}),
         Box::new(super::rsp::op_vrndn::VRNDNAccumulatorOverflowed {}),
 		Box::new(super::rsp::op_vrndn::VRNDNClampNegativeAccumulator {}),
+        Box::new(super::rsp::op_vrndn::VRNDNReferenceModel {}),
+        Box::new(super::rsp::randomized::RandomizedVectorInstructions {}),
         Box::new(super::rsp::registers::SetClearInterrupt {}),
         Box::new(super::rsp::registers::SetClearHalt {}),
         Box::new(super::rsp::registers::SetClearSignal {}),
@@ -899,6 +962,10 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::rsp::registers::SemaphoreRegisterRSPOnly {}),
         Box::new(super::rsp::registers::SemaphoreRegisterMixed {}),
         Box::new(super::rsp::registers::RSPHaltItselfWithoutBreak {}),
+        Box::new(super::rsp::dma::RSPDMAIntoDMEM {}),
+        Box::new(super::rsp::dma::RSPDMAIntoIMEM {}),
+        Box::new(super::rsp::dma::RSPDMAFromDMEM {}),
+        Box::new(super::rsp::dma::RSPDMAWithConcurrentCPUDMA {}),
         Box::new(super::sp_memory::SW {}),
         Box::new(super::sp_memory::SWOutOfBounds {}),
         Box::new(super::sp_memory::SH {}),
@@ -917,10 +984,20 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::sp_memory::dma::SPDMAIntoIMEMUntilEnd {}),
         Box::new(super::sp_memory::dma::SPDMAIntoIMEMWithOverflow {}),
         Box::new(super::sp_memory::dma::SPDMAFromDMEMWithOverflow {}),
+        Box::new(super::sp_memory::dma::SPDMA2DIntoSPMEM {}),
+        Box::new(super::sp_memory::dma::SPDMA2DFromSPMEM {}),
+        Box::new(super::sp_memory::dma::SPDMA2DRegistersAfterDMA {}),
+        Box::new(super::sp_memory::dma_queue::SPDMAQueueFromCPU {}),
+        Box::new(super::sp_memory::dma_queue::SPDMAQueueThirdWhileFull {}),
+        Box::new(super::sp_memory::dma_queue::SPDMAQueueFromRSP {}),
 
         Box::new(super::tlb::WiredRandom {}),
         Box::new(super::tlb::WiredOutOfBoundsRandom {}),
         Box::new(super::tlb::WriteRandomExpectIgnored {}),
+        Box::new(super::tlb::TLBWR {}),
+        Box::new(super::tlb::TLBWRWiredOutOfBounds {}),
+        Box::new(super::tlb::TLBWRUsesRandom {}),
+        Box::new(super::tlb::WiredResetsRandom {}),
         Box::new(super::tlb::IndexMasking {}),
         Box::new(super::tlb::EntryLo0Masking {}),
         Box::new(super::tlb::EntryLo0Masking64 {}),
@@ -935,6 +1012,8 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::tlb::TLBUseTestRead1 {}),
         Box::new(super::tlb::TLBUseTestReadMatchViaASID {}),
         Box::new(super::tlb::TLBPMatch {}),
+        Box::new(super::tlb::multiple_matches::MultipleMatchesTLBP {}),
+        Box::new(super::tlb::multiple_matches::MultipleMatchesTranslation {}),
         Box::new(super::tlb::exceptions::ReadMiss4k {}),
         Box::new(super::tlb::exceptions::ReadMiss16k {}),
         Box::new(super::tlb::exceptions::ReadMiss64k {}),
@@ -953,6 +1032,9 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::tlb::exceptions::StoreNonDirty4k {}),
         Box::new(super::tlb::exceptions::StoreNonDirtyAndNonValid4k {}),
         Box::new(super::tlb::exceptions::LWTLBMissTest32 {}),
+        Box::new(super::tlb::vectors::TLBExceptionVectors {}),
+        Box::new(super::tlb::vectors::TLBExceptionVectorsBEV {}),
+        Box::new(super::tlb::vectors::TLBRefillWithEXL {}),
         Box::new(super::tlb64::AllLoads32BitAddress {}),
         Box::new(super::tlb64::AllLoads32BitAddressUncached {}),
         Box::new(super::tlb64::AllLoads0x90 {}),
@@ -978,6 +1060,22 @@ fn default_tests() -> Vec<Box<dyn Test>> {
         Box::new(super::traps::TLTIU {}),
         Box::new(super::traps::delay::TNEDelay1 {}),
         Box::new(super::traps::delay::TNEDelay2 {}),
+        Box::new(super::watchpoints::WatchpointRead {}),
+        Box::new(super::watchpoints::WatchpointWrite {}),
+        Box::new(super::watchpoints::WatchpointReadWrite {}),
+        Box::new(super::watchpoints::WatchpointDisabled {}),
+        Box::new(super::watchpoints::WatchpointSuppressedByEXL {}),
+
+        Box::new(super::timing::ALUTiming {}),
+        Box::new(super::timing::MultiplyDivideTiming {}),
+        Box::new(super::timing::FPUTiming {}),
+        Box::new(super::timing::UncachedLoadTiming {}),
+        Box::new(super::timing::DataCacheMissTiming {}),
+        Box::new(super::timing::DataCacheWritebackTiming {}),
+        Box::new(super::rsp::timing::RSPDualIssueTiming {}),
+        Box::new(super::rsp::timing::RSPVectorDependencyTiming {}),
+        Box::new(super::rsp::timing::RSPVectorLoadStallTiming {}),
+        Box::new(super::rsp::timing::RSPMFC2StallTiming {}),
 
         // This should be the overall last test
         Box::new(super::startup::TearDownTest {}),
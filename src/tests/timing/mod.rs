@@ -0,0 +1,311 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::arch::asm;
+
+use crate::cop0;
+use crate::MemoryMap;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_less;
+use crate::uncached_memory::UncachedHeapMemory;
+
+// These tests measure how long certain instructions take by reading Count before and after executing
+// them a number of times.
+//
+// No cycle counts have been measured on hardware yet, so these tests don't check absolute numbers. They only
+// compare instructions and kinds of memory accesses against each other:
+// - For ALU, multiply/divide and FPU instructions, the order follows from the latencies in the VR4300 manual,
+//   which are far enough apart that pipeline details don't change it
+// - For memory accesses, the order follows from which bus has to be involved (cache, RDRAM, PI, PIF)
+//
+// Each measurement runs twice and only the second result is used, so that instruction cache misses
+// don't show up in the result.
+
+/// Executes the given instructions `$repeat` times (with $6 and $7 as scratch registers) and returns
+/// the number of Count ticks this took. Additional operands are passed through to asm!
+macro_rules! measure {
+    ($repeat:literal, $instructions:literal $($operands:tt)*) => {{
+        let mut ticks = 0u32;
+        for _ in 0..2 {
+            let start: u32;
+            let end: u32;
+            unsafe {
+                asm!(concat!("
+                    .set noat
+                    .set noreorder
+                    MFC0 $2, $9
+                    .rept ", $repeat, "
+                    ", $instructions, "
+                    .endr
+                    MFC0 $3, $9
+                "), out("$2") start, out("$3") end, out("$6") _, out("$7") _ $($operands)*)
+            }
+            ticks = end.wrapping_sub(start);
+        }
+        ticks
+    }}
+}
+
+/// Ensures that the first measurement took fewer Count ticks than the second one
+fn check_faster(faster: (&str, u32), slower: (&str, u32)) -> Result<(), String> {
+    soft_assert_less(faster.1, slower.1, &format!("{} is expected to be faster than {} (Count ticks: {} vs {})", faster.0, slower.0, faster.1, slower.1))
+}
+
+pub struct ALUTiming {}
+
+impl Test for ALUTiming {
+    fn name(&self) -> &str { "Timing: ALU instructions" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // The manual lists a single cycle for every ALU instruction (even if it depends on the previous one),
+        // while MULT takes 5 before MFLO can read the result
+        let mult = ("MULT, MFLO", measure!(64, "MULT $4, $5; MFLO $6", in("$4") 1234u32, in("$5") 5678u32));
+        check_faster(("NOP", measure!(64, "NOP")), mult)?;
+        check_faster(("ADDU", measure!(64, "ADDU $6, $4, $5", in("$4") 1u32, in("$5") 2u32)), mult)?;
+        check_faster(("ADDU (dependent)", measure!(64, "ADDU $6, $6, $4", in("$4") 1u32)), mult)?;
+        check_faster(("DADDU", measure!(64, "DADDU $6, $4, $5", in("$4") 1u32, in("$5") 2u32)), mult)?;
+        check_faster(("AND", measure!(64, "AND $6, $4, $5", in("$4") 1u32, in("$5") 2u32)), mult)?;
+        check_faster(("SLT", measure!(64, "SLT $6, $4, $5", in("$4") 1u32, in("$5") 2u32)), mult)?;
+        check_faster(("SLL", measure!(64, "SLL $6, $4, 3", in("$4") 1u32)), mult)?;
+        check_faster(("DSLL32", measure!(64, "DSLL32 $6, $4, 3", in("$4") 1u32)), mult)?;
+        check_faster(("DSRAV", measure!(64, "DSRAV $6, $4, $5", in("$4") 1u32, in("$5") 2u32)), mult)?;
+        check_faster(("LUI", measure!(64, "LUI $6, 0x1234")), mult)?;
+
+        Ok(())
+    }
+}
+
+pub struct MultiplyDivideTiming {}
+
+impl Test for MultiplyDivideTiming {
+    fn name(&self) -> &str { "Timing: MULT/DMULT/DIV/DDIV" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // Each operation is followed by MFLO, which stalls until the result is available. The manual lists
+        // 5 cycles for MULT, 8 for DMULT, 37 for DIV and 69 for DDIV
+        let mult = ("MULT, MFLO", measure!(16, "MULT $4, $5; MFLO $6", in("$4") 1234u32, in("$5") 5678u32));
+        let multu = ("MULTU, MFLO", measure!(16, "MULTU $4, $5; MFLO $6", in("$4") 1234u32, in("$5") 5678u32));
+        let dmult = ("DMULT, MFLO", measure!(16, "DMULT $4, $5; MFLO $6", in("$4") 1234u32, in("$5") 5678u32));
+        let dmultu = ("DMULTU, MFLO", measure!(16, "DMULTU $4, $5; MFLO $6", in("$4") 1234u32, in("$5") 5678u32));
+        let div = ("DIV, MFLO", measure!(16, "DIV $0, $4, $5; MFLO $6", in("$4") 5678u32, in("$5") 1234u32));
+        let divu = ("DIVU, MFLO", measure!(16, "DIVU $0, $4, $5; MFLO $6", in("$4") 5678u32, in("$5") 1234u32));
+        let ddiv = ("DDIV, MFLO", measure!(16, "DDIV $0, $4, $5; MFLO $6", in("$4") 5678u32, in("$5") 1234u32));
+        let ddivu = ("DDIVU, MFLO", measure!(16, "DDIVU $0, $4, $5; MFLO $6", in("$4") 5678u32, in("$5") 1234u32));
+
+        check_faster(mult, dmult)?;
+        check_faster(multu, dmultu)?;
+        check_faster(dmult, div)?;
+        check_faster(dmultu, divu)?;
+        check_faster(div, ddiv)?;
+        check_faster(divu, ddivu)?;
+
+        Ok(())
+    }
+}
+
+pub struct FPUTiming {}
+
+impl Test for FPUTiming {
+    fn name(&self) -> &str { "Timing: FPU arithmetic" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // Each instruction depends on the result of the previous one, so this measures the latency. The manual
+        // lists 1 cycle for ABS/NEG/MOV, 3 for ADD, 5 and 8 for MUL.S/MUL.D and 29 and 58 for DIV and SQRT.
+        // All operands are 1.0, so that the result stays 1.0 and no special cases (denormals etc) come up
+        let add_s = ("ADD.S", measure!(16, "ADD.S $f0, $f0, $f2", inout("$f0") 1.0f32 => _, in("$f2") 1.0f32));
+        let add_d = ("ADD.D", measure!(16, "ADD.D $f0, $f0, $f2", inout("$f0") 1.0f64 => _, in("$f2") 1.0f64));
+        let mul_s = ("MUL.S", measure!(16, "MUL.S $f0, $f0, $f2", inout("$f0") 1.0f32 => _, in("$f2") 1.0f32));
+        let mul_d = ("MUL.D", measure!(16, "MUL.D $f0, $f0, $f2", inout("$f0") 1.0f64 => _, in("$f2") 1.0f64));
+        let div_s = ("DIV.S", measure!(16, "DIV.S $f0, $f0, $f2", inout("$f0") 1.0f32 => _, in("$f2") 1.0f32));
+        let div_d = ("DIV.D", measure!(16, "DIV.D $f0, $f0, $f2", inout("$f0") 1.0f64 => _, in("$f2") 1.0f64));
+        let sqrt_s = ("SQRT.S", measure!(16, "SQRT.S $f0, $f0", inout("$f0") 1.0f32 => _));
+        let sqrt_d = ("SQRT.D", measure!(16, "SQRT.D $f0, $f0", inout("$f0") 1.0f64 => _));
+
+        check_faster(("ABS.S", measure!(16, "ABS.S $f0, $f0", inout("$f0") 1.0f32 => _)), add_s)?;
+        check_faster(("ABS.D", measure!(16, "ABS.D $f0, $f0", inout("$f0") 1.0f64 => _)), add_d)?;
+        check_faster(("NEG.S", measure!(16, "NEG.S $f0, $f0", inout("$f0") 1.0f32 => _)), add_s)?;
+        check_faster(("NEG.D", measure!(16, "NEG.D $f0, $f0", inout("$f0") 1.0f64 => _)), add_d)?;
+        check_faster(("MOV.S", measure!(16, "MOV.S $f0, $f0", inout("$f0") 1.0f32 => _)), add_s)?;
+        check_faster(("MOV.D", measure!(16, "MOV.D $f0, $f0", inout("$f0") 1.0f64 => _)), add_d)?;
+        check_faster(add_s, mul_s)?;
+        check_faster(add_d, mul_d)?;
+        check_faster(mul_s, mul_d)?;
+        check_faster(mul_s, div_s)?;
+        check_faster(mul_d, div_d)?;
+        check_faster(div_s, div_d)?;
+        check_faster(sqrt_s, sqrt_d)?;
+
+        Ok(())
+    }
+}
+
+/// Used to have something to read from cartridge ROM
+static ROM_DATA: [u32; 4] = [0x0123_4567, 0x89AB_CDEF, 0xFEDC_BA98, 0x7654_3210];
+
+pub struct UncachedLoadTiming {}
+
+impl Test for UncachedLoadTiming {
+    fn name(&self) -> &str { "Timing: Uncached loads" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let mut rdram = UncachedHeapMemory::<u32>::new_with_init_value(4, 0);
+        let rdram_address = MemoryMap::physical_to_uncached_mut::<u32>(rdram.start_phyiscal());
+        let rom_address = MemoryMap::uncached_cart_address(&ROM_DATA[0] as *const u32);
+        let spmem_address = MemoryMap::uncached_spmem_address::<u32>(0);
+        let pifram_address = MemoryMap::uncached_pifram_address::<u32>(0);
+
+        let rdram_ticks = measure!(8, "LW $6, 0($4)", in("$4") rdram_address);
+        let rom_ticks = measure!(8, "LW $6, 0($4)", in("$4") rom_address);
+        let spmem_ticks = measure!(8, "LW $6, 0($4)", in("$4") spmem_address);
+        let pifram_ticks = measure!(8, "LW $6, 0($4)", in("$4") pifram_address);
+
+        // The PI and the PIF are much slower than RDRAM and SP memory
+        check_faster(("LW from RDRAM", rdram_ticks), ("LW from cartridge ROM", rom_ticks))?;
+        check_faster(("LW from SP DMEM", spmem_ticks), ("LW from cartridge ROM", rom_ticks))?;
+        check_faster(("LW from cartridge ROM", rom_ticks), ("LW from PIF RAM", pifram_ticks))?;
+
+        Ok(())
+    }
+}
+
+/// Loads one word from each of 8 consecutive data cache lines (16 bytes each) and returns the number of
+/// Count ticks this took
+fn load_8_lines(address: *const u32) -> u32 {
+    let start: u32;
+    let end: u32;
+    unsafe {
+        asm!("
+            .set noat
+            .set noreorder
+            MFC0 $2, $9
+            LW $6, 0($4)
+            LW $6, 16($4)
+            LW $6, 32($4)
+            LW $6, 48($4)
+            LW $6, 64($4)
+            LW $6, 80($4)
+            LW $6, 96($4)
+            LW $6, 112($4)
+            MFC0 $3, $9
+        ", in("$4") address, out("$2") start, out("$3") end, out("$6") _)
+    }
+    end.wrapping_sub(start)
+}
+
+/// Stores one word into each of 8 consecutive data cache lines, which makes them dirty if they are cached
+fn store_8_lines(address: *mut u32) {
+    for line in 0..8 {
+        unsafe { (address.add(line * 4)).write_volatile(line as u32); }
+    }
+}
+
+/// Hit Invalidate for 8 consecutive data cache lines
+fn invalidate_8_lines(address: usize) {
+    for line in 0..8 {
+        // 0x11: Hit Invalidate (D)
+        unsafe { cop0::cache::<0x11, 0>(address + line * 16); }
+    }
+}
+
+pub struct DataCacheMissTiming {}
+
+impl Test for DataCacheMissTiming {
+    fn name(&self) -> &str { "Timing: Data cache hit and miss" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // 8 data cache lines (16 bytes each). The loads go through KSEG0 so that they are cached
+        let mut memory = UncachedHeapMemory::<u32>::new_with_align(32, 16);
+        let cached_address = (memory.start_phyiscal() | 0x8000_0000) as *const u32;
+        let uncached_address = (memory.start_phyiscal() | 0xA000_0000) as *const u32;
+
+        let mut miss_ticks = 0;
+        let mut hit_ticks = 0;
+        let mut uncached_ticks = 0;
+        // As with measure!, the first iteration is only there to get the code into the instruction cache
+        for _ in 0..2 {
+            invalidate_8_lines(cached_address as usize);
+
+            // Each load goes to a different line, so each one misses
+            miss_ticks = load_8_lines(cached_address);
+            // Now all lines are in the cache
+            hit_ticks = load_8_lines(cached_address);
+            // The same memory, but without going through the cache
+            uncached_ticks = load_8_lines(uncached_address);
+        }
+
+        // Don't leave anything behind in the cache, as the memory will be reused through KSEG1
+        invalidate_8_lines(cached_address as usize);
+
+        // Both a miss and an uncached load have to go to RDRAM, so they are expected to be slower than a hit
+        check_faster(("LW (data cache hit)", hit_ticks), ("LW (data cache miss)", miss_ticks))?;
+        check_faster(("LW (data cache hit)", hit_ticks), ("LW (uncached)", uncached_ticks))?;
+
+        Ok(())
+    }
+}
+
+pub struct DataCacheWritebackTiming {}
+
+impl Test for DataCacheWritebackTiming {
+    fn name(&self) -> &str { "Timing: Data cache miss with writeback" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // The data cache is 8KB and direct mapped, so two blocks that are 8KB apart use the same lines
+        const DCACHE_SIZE: usize = 8 * 1024;
+        let mut memory = UncachedHeapMemory::<u32>::new_with_align((DCACHE_SIZE / 4) + 32, 16);
+        let block_a = (memory.start_phyiscal() | 0x8000_0000) as *mut u32;
+        let block_b = ((memory.start_phyiscal() + DCACHE_SIZE) | 0x8000_0000) as *mut u32;
+
+        let mut clean_miss_ticks = 0;
+        let mut dirty_miss_ticks = 0;
+        // As with measure!, the first iteration is only there to get the code into the instruction cache
+        for _ in 0..2 {
+            invalidate_8_lines(block_a as usize);
+            invalidate_8_lines(block_b as usize);
+
+            // Bring block A into the cache, then replace it with block B. A is clean, so it is simply dropped
+            load_8_lines(block_a);
+            clean_miss_ticks = load_8_lines(block_b);
+
+            // Make block B dirty. Bringing block A back now has to write B back to RDRAM first
+            store_8_lines(block_b);
+            dirty_miss_ticks = load_8_lines(block_a);
+        }
+
+        // Block B was written back already. Don't leave block A behind either, as the memory will be
+        // reused through KSEG1
+        invalidate_8_lines(block_a as usize);
+        invalidate_8_lines(block_b as usize);
+
+        // Replacing a dirty line has to write it back to RDRAM first
+        check_faster(("LW (data cache miss, clean line replaced)", clean_miss_ticks), ("LW (data cache miss, dirty line replaced)", dirty_miss_ticks))?;
+
+        Ok(())
+    }
+}
@@ -8,14 +8,15 @@ use core::cmp::min;
 use arbitrary_int::{u2, u27};
 
 use crate::cop0;
+use crate::cop0::RegisterIndex;
 use crate::cop0::{make_entry_hi, make_entry_lo};
 use crate::memory_map::MemoryMap;
 use crate::tests::{Level, Test};
 use crate::tests::soft_asserts::{soft_assert_eq, soft_assert_greater_or_equal, soft_assert_less};
 
 pub mod exceptions;
-
-// TODO: TLBWR
+pub mod multiple_matches;
+pub mod vectors;
 
 pub struct WiredRandom {}
 
@@ -134,6 +135,209 @@ impl Test for WriteRandomExpectIgnored {
     }
 }
 
+/// Reads back EntryHi of all TLB entries
+fn read_all_entry_hi() -> [u64; 32] {
+    let mut result = [0u64; 32];
+    for i in 0..32 {
+        unsafe {
+            cop0::set_index(i);
+            cop0::tlbr();
+        }
+        result[i as usize] = cop0::entry_hi();
+    }
+    result
+}
+
+/// Value of RANDOM one instruction after it read as `random`
+fn next_random(random: u32, wired: u32) -> u32 {
+    if random == wired { 31 } else { random.wrapping_sub(1) & 63 }
+}
+
+/// Sets up EntryHi/EntryLo for an entry with the given VPN and returns EntryHi
+fn prepare_entry(vpn: u32) -> u64 {
+    let entry_hi = make_entry_hi(0, u27::new(vpn), u2::new(0));
+    unsafe {
+        cop0::set_entry_lo0(0);
+        cop0::set_entry_lo1(0);
+        cop0::set_pagemask(0);
+        cop0::set_entry_hi(entry_hi);
+    }
+    entry_hi
+}
+
+/// Returns the one entry that changed. Fails if none or more than one changed
+fn find_changed_entry(before: &[u64; 32], after: &[u64; 32]) -> Result<u32, String> {
+    let changed: Vec<usize> = (0..32).filter(|i| before[*i] != after[*i]).collect();
+    if changed.len() != 1 {
+        return Err(format!("TLBWR was expected to write exactly one entry, but the following entries changed: {:?}", changed));
+    }
+    Ok(changed[0] as u32)
+}
+
+/// Writes an entry with the given VPN through TLBWR and returns the index that was written
+fn tlbwr_and_find_index(vpn: u32) -> Result<u32, String> {
+    let before = read_all_entry_hi();
+    prepare_entry(vpn);
+    unsafe { cop0::tlbwr(); }
+    find_changed_entry(&before, &read_all_entry_hi())
+}
+
+pub struct TLBWR {}
+
+impl Test for TLBWR {
+    fn name(&self) -> &str { "TLBWR" }
+
+    fn level(&self) -> Level { Level::BasicFunctionality }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        for wired in [0, 10, 31] {
+            unsafe { cop0::clear_tlb(); }
+            unsafe { cop0::set_wired(wired); }
+
+            // TLBWR writes to the index in Random, so it is expected to never touch the wired entries
+            let mut value_seen = [false; 32];
+            let mut count_values_seen = 0;
+            for i in 0..64 {
+                let index = tlbwr_and_find_index(0x1000 + i)?;
+                soft_assert_greater_or_equal(index, wired, format!("TLBWR (with WIRED={}) wrote to a wired entry", wired).as_str())?;
+                if !value_seen[index as usize] {
+                    value_seen[index as usize] = true;
+                    count_values_seen += 1;
+                }
+            }
+            soft_assert_greater_or_equal(count_values_seen, min(4, 32 - wired), format!("TLBWR (with WIRED={}) was expected to write to a couple of different entries", wired).as_str())?;
+        }
+
+        unsafe { cop0::set_wired(0); }
+        unsafe { cop0::clear_tlb(); }
+
+        Ok(())
+    }
+}
+
+pub struct TLBWRWiredOutOfBounds {}
+
+impl Test for TLBWRWiredOutOfBounds {
+    fn name(&self) -> &str { "TLBWR (Wired OOB)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // If WIRED>31, RANDOM can be [0..63] (see WiredOutOfBoundsRandom). As there are only 32 entries,
+        // TLBWR is expected to still write exactly one entry (using the lower bits of RANDOM). This also
+        // includes entries below WIRED
+        unsafe { cop0::clear_tlb(); }
+        unsafe { cop0::set_wired(40); }
+
+        let mut value_seen = [false; 32];
+        let mut count_values_seen = 0;
+        for i in 0..128 {
+            let index = tlbwr_and_find_index(0x1000 + i)?;
+            if !value_seen[index as usize] {
+                value_seen[index as usize] = true;
+                count_values_seen += 1;
+            }
+        }
+
+        unsafe { cop0::set_wired(0); }
+        unsafe { cop0::clear_tlb(); }
+
+        soft_assert_greater_or_equal(count_values_seen, 8, "TLBWR (with WIRED=40) was expected to write to a couple of different entries")?;
+
+        Ok(())
+    }
+}
+
+pub struct TLBWRUsesRandom {}
+
+impl Test for TLBWRUsesRandom {
+    fn name(&self) -> &str { "TLBWR (index is RANDOM)" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        // RANDOM is read right before the TLBWR. As it counts down with every instruction, TLBWR is expected to
+        // see the value after the one that was read. Only 32 entries exist, so the upper bit (which can be set if
+        // WIRED > 31) is ignored
+        for wired in [0, 10, 31, 40] {
+            unsafe { cop0::clear_tlb(); }
+            unsafe { cop0::set_wired(wired); }
+
+            for i in 0..64 {
+                let before = read_all_entry_hi();
+                let entry_hi = prepare_entry(0x1000 + i);
+                let random: u32;
+                unsafe {
+                    asm!("
+                        .set noreorder
+                        mfc0 {random}, ${Random}
+                        tlbwr
+                        nop
+                        nop
+                    ", random = out(reg) random,
+                        Random = const RegisterIndex::Random as u32)
+                }
+                let expected_index = next_random(random, wired) & 31;
+                let index = find_changed_entry(&before, &read_all_entry_hi())?;
+
+                unsafe {
+                    cop0::set_index(index);
+                    cop0::tlbr();
+                }
+                soft_assert_eq(cop0::entry_hi(), entry_hi, format!("EntryHi of entry {} after TLBWR", index).as_str())?;
+                soft_assert_eq(index, expected_index, format!("Entry written by TLBWR (RANDOM read right before TLBWR was {}, WIRED={})", random, wired).as_str())?;
+            }
+        }
+
+        unsafe { cop0::set_wired(0); }
+        unsafe { cop0::clear_tlb(); }
+
+        Ok(())
+    }
+}
+
+pub struct WiredResetsRandom {}
+
+impl Test for WiredResetsRandom {
+    fn name(&self) -> &str { "Writing Wired resets Random" }
+
+    fn level(&self) -> Level { Level::Timing }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        for wired in [0, 5, 16, 29, 30, 31] {
+            // Read RANDOM right after writing WIRED. It is expected to be 31, but as it counts down with
+            // every instruction, allow for a little bit of slack
+            let random: u32;
+            unsafe {
+                asm!("
+                    .set noreorder
+                    mtc0 {wired}, ${Wired}
+                    nop
+                    mfc0 {random}, ${Random}
+                    nop
+                ", wired = in(reg) wired,
+                    random = out(reg) random,
+                    Wired = const RegisterIndex::Wired as u32,
+                    Random = const RegisterIndex::Random as u32)
+            }
+            soft_assert_greater_or_equal(random, if wired > 28 { wired } else { 28 }, format!("RANDOM right after setting WIRED to {}", wired).as_str())?;
+            soft_assert_less(random, 32, format!("RANDOM right after setting WIRED to {}", wired).as_str())?;
+        }
+
+        unsafe { cop0::set_wired(0); }
+
+        Ok(())
+    }
+}
+
 pub struct IndexMasking {}
 
 impl Test for IndexMasking {
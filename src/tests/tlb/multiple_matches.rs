@@ -0,0 +1,137 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use arbitrary_int::{u2, u27};
+
+use crate::cop0;
+use crate::cop0::{make_entry_hi, make_entry_lo};
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+use crate::uncached_memory::UncachedHeapMemory;
+
+// The TLB is supposed to never contain two entries that match the same virtual address. The VR4300 manual
+// says that this can cause a TLB shutdown (Status.TS), which disables the TLB until reset.
+// The tests below write two overlapping entries through TLBWI and look at:
+// - which index TLBP reports (and whether Index.P is set)
+// - which of the two entries is used for the translation
+// - whether Status.TS gets set
+// None of this has been observed on hardware yet. The expectations below (the N64 never shuts down the TLB
+// and the entry with the lowest index wins, no matter which one was written last) are guesses, so these tests
+// are PoorlyUnderstoodQuirk until they have been run on hardware.
+
+const VIRTUAL_ADDRESS: u32 = 0x0DEA_0000;
+
+const PAGEMASK_4K: u32 = 0;
+const PAGEMASK_16K: u32 = 0b11 << 13;
+
+#[derive(Copy, Clone)]
+struct Entry {
+    index: u32,
+    pagemask: u32,
+    asid: u8,
+    global: bool,
+}
+
+const fn entry(index: u32, pagemask: u32, asid: u8, global: bool) -> Entry {
+    Entry { index, pagemask, asid, global }
+}
+
+/// Name, first entry that is written, second entry that is written, current ASID, expected TLBP result
+const CASES: [(&str, Entry, Entry, u8, u32); 7] = [
+    ("same page size, both global", entry(5, PAGEMASK_4K, 1, true), entry(20, PAGEMASK_4K, 1, true), 1, 5),
+    ("same page size, both global, higher index written first", entry(20, PAGEMASK_4K, 1, true), entry(5, PAGEMASK_4K, 1, true), 1, 5),
+    ("4k and 16k", entry(5, PAGEMASK_4K, 1, true), entry(20, PAGEMASK_16K, 1, true), 1, 5),
+    ("16k and 4k", entry(5, PAGEMASK_16K, 1, true), entry(20, PAGEMASK_4K, 1, true), 1, 5),
+    ("matching ASID and global", entry(5, PAGEMASK_4K, 1, false), entry(20, PAGEMASK_4K, 2, true), 1, 5),
+    ("global and matching ASID", entry(5, PAGEMASK_4K, 2, true), entry(20, PAGEMASK_4K, 1, false), 1, 5),
+    // Sanity check: Only the global entry matches
+    ("mismatching ASID and global", entry(5, PAGEMASK_4K, 1, false), entry(20, PAGEMASK_4K, 2, true), 3, 20),
+];
+
+fn case_values() -> Vec<Box<dyn Any>> {
+    (0..CASES.len() as u32).map(|i| -> Box<dyn Any> { Box::new(i) }).collect()
+}
+
+/// Writes the given entry, mapping VIRTUAL_ADDRESS (uncached) to the given physical page
+unsafe fn write_entry(entry: Entry, physical: usize) {
+    unsafe {
+        cop0::write_tlb(
+            entry.index,
+            entry.pagemask,
+            make_entry_lo(entry.global, true, true, 2, (physical >> 12) as u32),
+            make_entry_lo(entry.global, false, false, 2, 0),
+            make_entry_hi(entry.asid, u27::new(VIRTUAL_ADDRESS >> 13), u2::new(0)));
+    }
+}
+
+pub struct MultipleMatchesTLBP {}
+
+impl Test for MultipleMatchesTLBP {
+    fn name(&self) -> &str { "TLB: Multiple matching entries (TLBP)" }
+
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { case_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, first, second, asid, expected_index) = CASES[*(*value).downcast_ref::<u32>().unwrap() as usize];
+
+        unsafe {
+            cop0::clear_tlb();
+            write_entry(first, 0);
+            write_entry(second, 0);
+        }
+
+        unsafe { cop0::set_entry_hi(make_entry_hi(asid, u27::new(VIRTUAL_ADDRESS >> 13), u2::new(0))); }
+        cop0::tlbp();
+        let index = cop0::index();
+        let tlb_shutdown = cop0::status().tlb_shutdown();
+
+        unsafe { cop0::clear_tlb(); }
+
+        soft_assert_eq2(index, expected_index, || format!("{}: TLBP result (unconfirmed guess: lowest index, Index.P clear)", name))?;
+        soft_assert_eq2(tlb_shutdown, false, || format!("{}: Status.TS (unconfirmed guess: never set)", name))?;
+
+        Ok(())
+    }
+}
+
+pub struct MultipleMatchesTranslation {}
+
+impl Test for MultipleMatchesTranslation {
+    fn name(&self) -> &str { "TLB: Multiple matching entries (translation)" }
+
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { case_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, first, second, asid, expected_index) = CASES[*(*value).downcast_ref::<u32>().unwrap() as usize];
+
+        // Both entries point to their own 16k page (aligned, so that it works for either page size)
+        let mut first_page = UncachedHeapMemory::<u32>::new_with_align(4096, 16384);
+        let mut second_page = UncachedHeapMemory::<u32>::new_with_align(4096, 16384);
+        first_page.write(6, 0x1111_1111);
+        second_page.write(6, 0x2222_2222);
+
+        unsafe {
+            cop0::clear_tlb();
+            write_entry(first, first_page.start_phyiscal());
+            write_entry(second, second_page.start_phyiscal());
+            cop0::set_entry_hi(make_entry_hi(asid, u27::new(0), u2::new(0)));
+        }
+
+        let read_back = unsafe { ((VIRTUAL_ADDRESS + 0x18) as *const u32).read_volatile() };
+        let tlb_shutdown = cop0::status().tlb_shutdown();
+
+        unsafe { cop0::clear_tlb(); }
+
+        let expected = if expected_index == first.index { 0x1111_1111 } else { 0x2222_2222 };
+        soft_assert_eq2(read_back, expected, || format!("{}: Value read through the TLB (unconfirmed guess: entry {} wins)", name, expected_index))?;
+        soft_assert_eq2(tlb_shutdown, false, || format!("{}: Status.TS (unconfirmed guess: never set)", name))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,359 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::arch::asm;
+
+use crate::assembler::{Assembler, GPR};
+use crate::cop0;
+use crate::cop0::{Cause, CauseException, RegisterIndex, Status, StatusKSU};
+use crate::exception_handler::{expect_exception, ExceptionContext};
+use crate::MemoryMap;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+use crate::tests::tlb::exceptions::setup_tlb_page;
+use crate::user_mode;
+
+// TLB exceptions use one of three vectors:
+// - 0x000: TLB refill (no matching entry) in 32 bit addressing mode
+// - 0x080: XTLB refill (no matching entry) in 64 bit addressing mode. Which of KX/SX/UX matters depends
+//          on the segment of the faulting address (e.g. UX for a kuseg address, even in kernel mode)
+// - 0x180: Everything else (invalid entry, Mod) and every refill that happens while EXL is already set
+// The exception handler records the vector it was entered through in ExceptionContext.k0_exception_vector.
+//
+// With Status.BEV (tlb_miss_vectors) set, all of these move to 0xBFC00200 (+0x000, +0x080, +0x180). That
+// region is PIF ROM, which can't hold an exception handler. After boot, PIF ROM is expected to read as zero,
+// which the CPU executes as NOPs until it reaches PIF RAM at 0xBFC007C0. A jump to the regular exception
+// handler is placed there. The vector is then told apart by how long the CPU took to get to PIF RAM, compared
+// to a SYSCALL (which always uses +0x180). As this relies on behavior that isn't well understood, those tests
+// are PoorlyUnderstoodQuirk
+
+const LW_R0_A0: u32 = 0x8C80_0000;
+const SW_R0_A0: u32 = 0xAC80_0000;
+
+const UNMAPPED_ADDRESS: u32 = 0x0020_1234;
+
+/// Unmapped address in xkseg (kernel) and xsseg (supervisor), which are only accessible with KX and SX
+const UNMAPPED_ADDRESS_XKSEG: u64 = 0xC000_0000_0020_1234;
+const UNMAPPED_ADDRESS_XSSEG: u64 = 0x4000_0000_0020_1234;
+
+#[derive(Copy, Clone)]
+enum Page {
+    /// No TLB entry at all: Refill
+    Unmapped,
+
+    /// No TLB entry for an address in the 64 bit only segment of the current mode (xkseg or xsseg): Refill
+    Unmapped64,
+
+    /// Valid=0: TLBL/TLBS, but not a refill
+    Invalid,
+
+    /// Dirty=0: Mod on stores
+    ReadOnly,
+}
+
+/// Name, mode, 64 bit addressing, page, store, expected exception and vector
+const VECTORS: [(&str, StatusKSU, bool, Page, bool, CauseException, u64); 19] = [
+    ("kernel: refill", StatusKSU::Kernel, false, Page::Unmapped, false, CauseException::TLBL, 0xFFFFFFFF_80000000),
+    ("kernel: refill (store)", StatusKSU::Kernel, false, Page::Unmapped, true, CauseException::TLBS, 0xFFFFFFFF_80000000),
+    ("kernel (KX): xtlb refill", StatusKSU::Kernel, true, Page::Unmapped64, false, CauseException::TLBL, 0xFFFFFFFF_80000080),
+    ("kernel (KX): xtlb refill (store)", StatusKSU::Kernel, true, Page::Unmapped64, true, CauseException::TLBS, 0xFFFFFFFF_80000080),
+    ("kernel (KX): refill in kuseg (UX=0)", StatusKSU::Kernel, true, Page::Unmapped, false, CauseException::TLBL, 0xFFFFFFFF_80000000),
+    ("kernel: invalid", StatusKSU::Kernel, false, Page::Invalid, false, CauseException::TLBL, 0xFFFFFFFF_80000180),
+    ("kernel (KX): invalid", StatusKSU::Kernel, true, Page::Invalid, false, CauseException::TLBL, 0xFFFFFFFF_80000180),
+    ("kernel: mod", StatusKSU::Kernel, false, Page::ReadOnly, true, CauseException::Mod, 0xFFFFFFFF_80000180),
+    ("kernel (KX): mod", StatusKSU::Kernel, true, Page::ReadOnly, true, CauseException::Mod, 0xFFFFFFFF_80000180),
+    ("user: refill", StatusKSU::User, false, Page::Unmapped, false, CauseException::TLBL, 0xFFFFFFFF_80000000),
+    ("user (UX): xtlb refill", StatusKSU::User, true, Page::Unmapped, false, CauseException::TLBL, 0xFFFFFFFF_80000080),
+    ("user: invalid", StatusKSU::User, false, Page::Invalid, true, CauseException::TLBS, 0xFFFFFFFF_80000180),
+    ("user (UX): invalid", StatusKSU::User, true, Page::Invalid, true, CauseException::TLBS, 0xFFFFFFFF_80000180),
+    ("user (UX): mod", StatusKSU::User, true, Page::ReadOnly, true, CauseException::Mod, 0xFFFFFFFF_80000180),
+    ("supervisor: refill", StatusKSU::Supervisor, false, Page::Unmapped, false, CauseException::TLBL, 0xFFFFFFFF_80000000),
+    ("supervisor (SX): xtlb refill", StatusKSU::Supervisor, true, Page::Unmapped64, false, CauseException::TLBL, 0xFFFFFFFF_80000080),
+    ("supervisor: invalid", StatusKSU::Supervisor, false, Page::Invalid, false, CauseException::TLBL, 0xFFFFFFFF_80000180),
+    ("supervisor (SX): invalid", StatusKSU::Supervisor, true, Page::Invalid, false, CauseException::TLBL, 0xFFFFFFFF_80000180),
+    ("supervisor (SX): mod", StatusKSU::Supervisor, true, Page::ReadOnly, true, CauseException::Mod, 0xFFFFFFFF_80000180),
+];
+
+/// Name, 64 bit addressing (KX), page, store, expected exception and offset of the vector from 0xBFC00200.
+/// All of these run in kernel mode
+const BEV_VECTORS: [(&str, bool, Page, bool, CauseException, u32); 4] = [
+    ("BEV: refill", false, Page::Unmapped, false, CauseException::TLBL, 0x000),
+    ("BEV (KX): xtlb refill", true, Page::Unmapped64, false, CauseException::TLBL, 0x080),
+    ("BEV: invalid", false, Page::Invalid, false, CauseException::TLBL, 0x180),
+    ("BEV: mod", false, Page::ReadOnly, true, CauseException::Mod, 0x180),
+];
+
+/// Sets up the TLB for the page and returns the address to access
+fn address_for_page(page: Page, ksu: StatusKSU) -> u64 {
+    match page {
+        Page::Unmapped => {
+            unsafe { cop0::clear_tlb(); }
+            UNMAPPED_ADDRESS as i32 as u64
+        }
+        Page::Unmapped64 => {
+            unsafe { cop0::clear_tlb(); }
+            if matches!(ksu, StatusKSU::Supervisor) { UNMAPPED_ADDRESS_XSSEG } else { UNMAPPED_ADDRESS_XKSEG }
+        }
+        Page::Invalid => (setup_tlb_page(0, false, true) + 4092) as i32 as u64,
+        Page::ReadOnly => (setup_tlb_page(0, true, false) + 4092) as i32 as u64,
+    }
+}
+
+/// Loads or stores at the given address with the given Status. Status is restored right after. Also returns
+/// Count as it was right before the access
+fn access_in_kernel_mode(status: Status, address: u64, store: bool, code: CauseException) -> Result<(ExceptionContext, u32), String> {
+    let mut count_before: u32 = 0;
+    let exception_context = expect_exception(code, 1, || {
+        unsafe {
+            if store {
+                asm!("
+                    .set noat
+                    .set noreorder
+                    ld {address}, 0({address_pointer})
+                    mtc0 {status}, ${Status}
+                    nop
+                    nop
+                    mfc0 {count_before}, ${Count}
+                    sw $0, 0({address})
+                    mtc0 {default_status}, ${Status}
+                    nop
+                    nop
+                ", status = in(reg) status.raw_value(),
+                    default_status = in(reg) Status::DEFAULT.raw_value(),
+                    address_pointer = in(reg) &address as *const u64,
+                    address = out(reg) _,
+                    count_before = out(reg) count_before,
+                    Count = const RegisterIndex::Count as u32,
+                    Status = const RegisterIndex::Status as u32)
+            } else {
+                asm!("
+                    .set noat
+                    .set noreorder
+                    ld {address}, 0({address_pointer})
+                    mtc0 {status}, ${Status}
+                    nop
+                    nop
+                    mfc0 {count_before}, ${Count}
+                    lw $0, 0({address})
+                    mtc0 {default_status}, ${Status}
+                    nop
+                    nop
+                ", status = in(reg) status.raw_value(),
+                    default_status = in(reg) Status::DEFAULT.raw_value(),
+                    address_pointer = in(reg) &address as *const u64,
+                    address = out(reg) _,
+                    count_before = out(reg) count_before,
+                    Count = const RegisterIndex::Count as u32,
+                    Status = const RegisterIndex::Status as u32)
+            }
+        }
+
+        Ok(())
+    })?;
+
+    Ok((exception_context, count_before))
+}
+
+pub struct TLBExceptionVectors {}
+
+impl Test for TLBExceptionVectors {
+    fn name(&self) -> &str { "TLB: Exception vectors" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        (0..VECTORS.len() as u32).map(|i| -> Box<dyn Any> { Box::new(i) }).collect()
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, ksu, extended, page, store, expected, vector) = VECTORS[*(*value).downcast_ref::<u32>().unwrap() as usize];
+
+        let address = address_for_page(page, ksu);
+
+        let (status, exception_context) = match ksu {
+            StatusKSU::User | StatusKSU::Supervisor => {
+                let status = Status::DEFAULT
+                    .with_ksu(ksu)
+                    .with_ux(extended && matches!(ksu, StatusKSU::User))
+                    .with_sx(extended && matches!(ksu, StatusKSU::Supervisor));
+                let instruction = if store { SW_R0_A0 } else { LW_R0_A0 };
+                let exception_context = user_mode::run_in_mode(status, &[instruction], [address, 0, 0, 0], expected)?;
+                soft_assert_eq2(exception_context.exceptpc, user_mode::CODE_VIRTUAL_ADDRESS as u64, || format!("{}: ExceptPC", name))?;
+                (status, exception_context)
+            }
+            _ => {
+                let status = Status::DEFAULT.with_kx(extended);
+                (status, access_in_kernel_mode(status, address, store, expected)?.0)
+            }
+        };
+
+        soft_assert_eq2(exception_context.k0_exception_vector, vector, || format!("{}: Exception Vector", name))?;
+        soft_assert_eq2(exception_context.cause, Cause::new().with_exception(expected), || format!("{}: Cause", name))?;
+        soft_assert_eq2(exception_context.status, status.with_exl(true).raw_value(), || format!("{}: Status", name))?;
+        soft_assert_eq2(exception_context.badvaddr, address, || format!("{}: BadVAddr", name))?;
+
+        Ok(())
+    }
+}
+
+pub struct TLBRefillWithEXL {}
+
+impl Test for TLBRefillWithEXL {
+    fn name(&self) -> &str { "TLB: Refill while EXL is set uses the general vector" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { vec! { Box::new(false), Box::new(true) } }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let kx = *(*value).downcast_ref::<bool>().unwrap();
+        let status = Status::DEFAULT.with_kx(kx).with_exl(true);
+
+        unsafe { cop0::clear_tlb(); }
+
+        // With EXL set, EPC isn't updated by the exception. Point it at the faulting LW, so that the exception
+        // handler can skip over it
+        let mut faulting_instruction: u64 = 0;
+        let exception_context = expect_exception(CauseException::TLBL, 1, || {
+            unsafe {
+                asm!("
+                    .set noat
+                    .set noreorder
+                    la {faulting_instruction}, 2f
+                    dmtc0 {faulting_instruction}, ${ExceptPC}
+                    mtc0 {status}, ${Status}
+                    nop
+                    nop
+                2:
+                    lw $0, 0({address})
+                    mtc0 {default_status}, ${Status}
+                    nop
+                    nop
+                ", faulting_instruction = out(reg) faulting_instruction,
+                    status = in(reg) status.raw_value(),
+                    default_status = in(reg) Status::DEFAULT.raw_value(),
+                    address = in(reg) UNMAPPED_ADDRESS,
+                    ExceptPC = const RegisterIndex::ExceptPC as u32,
+                    Status = const RegisterIndex::Status as u32)
+            }
+
+            Ok(())
+        })?;
+
+        soft_assert_eq2(exception_context.k0_exception_vector, 0xFFFFFFFF_80000180, || format!("Exception Vector (KX={})", kx))?;
+        soft_assert_eq2(exception_context.exceptpc, faulting_instruction as u32 as i32 as u64, || format!("ExceptPC is expected to be unchanged (KX={})", kx))?;
+        soft_assert_eq2(exception_context.cause, Cause::new().with_exception(CauseException::TLBL), || format!("Cause (KX={})", kx))?;
+        soft_assert_eq2(exception_context.status, status.raw_value(), || format!("Status (KX={})", kx))?;
+        soft_assert_eq2(exception_context.badvaddr, UNMAPPED_ADDRESS as u64, || format!("BadVAddr (KX={})", kx))?;
+
+        Ok(())
+    }
+}
+
+/// First BEV vector. The others are at +0x080 and +0x180
+const BEV_VECTOR_BASE: usize = 0xBFC0_0200;
+
+/// Number of words between the first BEV vector and PIF RAM
+const BEV_SLIDE_WORDS: usize = (MemoryMap::PHYSICAL_PIFRAM_BASE - (BEV_VECTOR_BASE & 0x1FFF_FFFF)) >> 2;
+
+/// Placed at the start of PIF RAM: Puts Count into $27 (k1) and continues at the regular exception handler
+const BEV_LANDING: [u32; 5] = [
+    0x401B_4800,  // MFC0 $27, Count
+    0x3C1A_8000,  // LUI $26, 0x8000
+    0x375A_0180,  // ORI $26, $26, 0x0180
+    Assembler::make_jr(GPR::K0),
+    0,
+];
+
+/// Raises SYSCALL with the given Status. Returns the exception context and Count right before the SYSCALL
+fn syscall_in_kernel_mode(status: Status) -> Result<(ExceptionContext, u32), String> {
+    let mut count_before: u32 = 0;
+    let exception_context = expect_exception(CauseException::Sys, 1, || {
+        unsafe {
+            asm!("
+                .set noat
+                .set noreorder
+                mtc0 {status}, ${Status}
+                nop
+                nop
+                mfc0 {count_before}, ${Count}
+                syscall
+                mtc0 {default_status}, ${Status}
+                nop
+                nop
+            ", status = in(reg) status.raw_value(),
+                default_status = in(reg) Status::DEFAULT.raw_value(),
+                count_before = out(reg) count_before,
+                Count = const RegisterIndex::Count as u32,
+                Status = const RegisterIndex::Status as u32)
+        }
+
+        Ok(())
+    })?;
+
+    Ok((exception_context, count_before))
+}
+
+pub struct TLBExceptionVectorsBEV {}
+
+impl Test for TLBExceptionVectorsBEV {
+    fn name(&self) -> &str { "TLB: Exception vectors (BEV)" }
+
+    fn level(&self) -> Level { Level::PoorlyUnderstoodQuirk }
+
+    fn values(&self) -> Vec<Box<dyn Any>> {
+        (0..BEV_VECTORS.len() as u32).map(|i| -> Box<dyn Any> { Box::new(i) }).collect()
+    }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> {
+        let (name, extended, page, store, expected, vector_offset) = BEV_VECTORS[*(*value).downcast_ref::<u32>().unwrap() as usize];
+
+        // Everything between the vectors and PIF RAM has to read as zero (NOP), otherwise the CPU would run
+        // into whatever is there. Reading it also tells how long a single word takes
+        let read_start = cop0::count();
+        for i in 0..BEV_SLIDE_WORDS {
+            let word = unsafe { (BEV_VECTOR_BASE as *const u32).add(i).read_volatile() };
+            if word != 0 {
+                return Err(format!("PIF ROM at 0x{:08X} reads as 0x{:08X} instead of 0, so the BEV vectors can't be reached", BEV_VECTOR_BASE + (i << 2), word));
+            }
+        }
+        let read_ticks = cop0::count().wrapping_sub(read_start);
+
+        let pifram = MemoryMap::uncached_pifram_address::<u32>(0x0);
+        let saved_pifram: Vec<u32> = (0..BEV_LANDING.len()).map(|i| unsafe { pifram.add(i).read_volatile() }).collect();
+        for (i, instruction) in BEV_LANDING.iter().enumerate() {
+            unsafe { pifram.add(i).write_volatile(*instruction); }
+        }
+
+        let address = address_for_page(page, StatusKSU::Kernel);
+        let status = Status::DEFAULT.with_kx(extended).with_tlb_miss_vectors(true);
+        let syscall_result = syscall_in_kernel_mode(Status::DEFAULT.with_tlb_miss_vectors(true));
+        let access_result = access_in_kernel_mode(status, address, store, expected);
+
+        for (i, word) in saved_pifram.iter().enumerate() {
+            unsafe { pifram.add(i).write_volatile(*word); }
+        }
+
+        let (syscall_context, syscall_count_before) = syscall_result?;
+        let (exception_context, count_before) = access_result?;
+
+        soft_assert_eq2(syscall_context.k0_exception_vector, 0xFFFFFFFF_80000180, || format!("{}: SYSCALL didn't arrive through PIF RAM", name))?;
+        soft_assert_eq2(exception_context.k0_exception_vector, 0xFFFFFFFF_80000180, || format!("{}: Exception didn't arrive through PIF RAM", name))?;
+        soft_assert_eq2(exception_context.cause, Cause::new().with_exception(expected), || format!("{}: Cause", name))?;
+        soft_assert_eq2(exception_context.status, status.with_exl(true).raw_value(), || format!("{}: Status", name))?;
+        soft_assert_eq2(exception_context.badvaddr, address, || format!("{}: BadVAddr", name))?;
+
+        // SYSCALL uses +0x180. Every vector further down has to run through that many more NOPs
+        let syscall_ticks = (syscall_context.k1 as u32).wrapping_sub(syscall_count_before) as i64;
+        let ticks = (exception_context.k1 as u32).wrapping_sub(count_before) as i64;
+        let extra_words = (ticks - syscall_ticks) * (BEV_SLIDE_WORDS as i64) / (read_ticks as i64);
+        let estimated_offset = 0x180 - extra_words * 4;
+        let closest_vector = [0x000u32, 0x080, 0x180].iter().copied().min_by_key(|offset| (*offset as i64 - estimated_offset).abs()).unwrap();
+        soft_assert_eq2(closest_vector, vector_offset, || format!("{}: Exception Vector (offset from 0xBFC00200, estimated as 0x{:X})", name, estimated_offset))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use core::arch::asm;
+
+use crate::assembler::{Assembler, GPR};
+use crate::cop0::{RegisterIndex, Status};
+use crate::MemoryMap;
+use crate::uncached_memory::UncachedHeapMemory;
+
+// Some tests need to run an instruction that neither the compiler nor the assembler would emit (reserved
+// encodings, accesses that are supposed to fault etc). Such an instruction is written into uncached memory
+// (so that there is no need to worry about the instruction cache), followed by a return, and jumped to.
+
+const NOP: u32 = 0;
+
+/// Writes the instruction (either by itself or within the delay slot of a BEQ), followed by a return
+pub fn write_instruction(instruction: u32, delay: bool) -> UncachedHeapMemory<u32> {
+    let mut code = UncachedHeapMemory::<u32>::new(4);
+    if delay {
+        code.write(0, Assembler::make_beq(GPR::R0, GPR::R0, 1));
+        code.write(1, instruction);
+        code.write(2, Assembler::make_jr(GPR::RA));
+        code.write(3, NOP);
+    } else {
+        code.write(0, instruction);
+        code.write(1, Assembler::make_jr(GPR::RA));
+        code.write(2, NOP);
+        code.write(3, NOP);
+    }
+    code
+}
+
+/// Returns the (sign extended) uncached address of the code
+pub fn address(code: &mut UncachedHeapMemory<u32>) -> u64 {
+    MemoryMap::physical_to_uncached_mut::<u32>(code.start_phyiscal()) as u32 as i32 as u64
+}
+
+/// Calls into code with $4 and $5 preset. The code address is a full 64 bit address, so that code in
+/// 64 bit address space can be called (if Status allows it)
+pub fn call(code_address: u64, args: [u64; 2]) {
+    call_with_status(Status::DEFAULT, code_address, args)
+}
+
+/// Like [`call`], but runs the code with the given Status. Afterwards, Status is [`Status::DEFAULT`]
+pub fn call_with_status(status: Status, code_address: u64, args: [u64; 2]) {
+    let values = [args[0], args[1], code_address];
+    unsafe {
+        asm!("
+            .set noat
+            .set noreorder
+            ld $4, 0($8)
+            ld $5, 8($8)
+            ld $25, 16($8)
+            mtc0 $9, ${Status}
+            nop
+            nop
+            DADDIU $24, $31, 0  // Stash RA in $24
+            JALR $25
+            NOP
+            DADDIU $31, $24, 0  // Restore original RA
+            mtc0 {default_status}, ${Status}
+            nop
+            nop
+        ", default_status = in(reg) Status::DEFAULT.raw_value(),
+            Status = const RegisterIndex::Status as u32,
+            in("$8") values.as_ptr(), in("$9") status.raw_value(),
+            out("$2") _, out("$4") _, out("$5") _, out("$24") _, out("$25") _)
+    }
+}
@@ -0,0 +1,223 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::arch::asm;
+
+use crate::assembler::Opcode;
+use crate::cop0;
+use crate::cop0::{Cause, CauseException, RegisterIndex, Status};
+use crate::exception_handler::expect_exception;
+use crate::MemoryMap;
+use crate::tests::{Level, Test};
+use crate::tests::soft_asserts::soft_assert_eq2;
+use crate::tests::uncached_code;
+use crate::uncached_memory::UncachedHeapMemory;
+
+// WatchLo/WatchHi define an 8 byte aligned physical address. Any load (if WatchLo.R is set) or store
+// (if WatchLo.W is set) that touches this doubleword causes a Watch exception before the access happens.
+// Only bits 31..3 of the address are compared, so the size of the access doesn't matter - it only matters
+// which doubleword it falls into.
+// Watch exceptions are not taken while EXL is set.
+//
+// Each access is run through uncached_code. $4 points to the watched doubleword.
+
+const INITIAL_VALUE: u64 = 0xAAAAAAAA_AAAAAAAA;
+
+/// Name, opcode, offset relative to the watched doubleword
+const ACCESSES: [(&str, u32, i16); 24] = [
+    ("LB +0", Opcode::LB as u32, 0),
+    ("LB +7", Opcode::LB as u32, 7),
+    ("LB -1", Opcode::LB as u32, -1),
+    ("LB +8", Opcode::LB as u32, 8),
+    ("LH +6", Opcode::LH as u32, 6),
+    ("LW +4", Opcode::LW as u32, 4),
+    ("LW -4", Opcode::LW as u32, -4),
+    ("LW +8", Opcode::LW as u32, 8),
+    ("LD +0", Opcode::LD as u32, 0),
+    ("LD -8", Opcode::LD as u32, -8),
+    ("LD +8", Opcode::LD as u32, 8),
+    ("LWL +3", Opcode::LWL as u32, 3),
+    ("LDL +5", Opcode::LDL as u32, 5),
+    ("SB +0", Opcode::SB as u32, 0),
+    ("SB +7", Opcode::SB as u32, 7),
+    ("SB -1", Opcode::SB as u32, -1),
+    ("SB +8", Opcode::SB as u32, 8),
+    ("SH +2", Opcode::SH as u32, 2),
+    ("SW +4", Opcode::SW as u32, 4),
+    ("SW -4", Opcode::SW as u32, -4),
+    ("SW +8", Opcode::SW as u32, 8),
+    ("SD +0", Opcode::SD as u32, 0),
+    ("SD +8", Opcode::SD as u32, 8),
+    ("SWL +1", Opcode::SWL as u32, 1),
+];
+
+fn is_store(opcode: u32) -> bool {
+    [Opcode::SB as u32, Opcode::SH as u32, Opcode::SW as u32, Opcode::SD as u32, Opcode::SWL as u32].contains(&opcode)
+}
+
+fn access_values() -> Vec<Box<dyn Any>> {
+    let mut result: Vec<Box<dyn Any>> = Vec::new();
+    for i in 0..ACCESSES.len() {
+        result.push(Box::new((false, i as u32)));
+        result.push(Box::new((true, i as u32)));
+    }
+    result
+}
+
+/// Runs the access (either by itself or in the delay slot of a BEQ) with the given WatchLo.R/WatchLo.W
+/// and ensures that a Watch exception happens exactly when expected
+fn test_watchpoint(value: &Box<dyn Any>, read: bool, write: bool) -> Result<(), String> {
+    let (delay, index) = *(*value).downcast_ref::<(bool, u32)>().unwrap();
+    let (name, opcode, offset) = ACCESSES[index as usize];
+    let store = is_store(opcode);
+    let overlapping = (0..8).contains(&offset);
+    let expect_watch = overlapping && if store { write } else { read };
+
+    // Stores write $0, loads go into $5
+    let instruction = (opcode << 26) | (4 << 21) | ((if store { 0 } else { 5 }) << 16) | (offset as u16 as u32);
+
+    let mut code = uncached_code::write_instruction(instruction, delay);
+    let code_address = uncached_code::address(&mut code);
+
+    // The watched doubleword is in the middle, so that accesses right before and after it are harmless
+    let mut memory = UncachedHeapMemory::<u64>::new_with_init_value(3, INITIAL_VALUE);
+    let watched_physical = memory.start_phyiscal() + 8;
+    let watched_address = MemoryMap::physical_to_uncached_mut::<u64>(watched_physical) as u32 as i32 as u64;
+
+    let access = || uncached_code::call(code_address, [watched_address, 0]);
+
+    unsafe {
+        cop0::set_watchhi(0);
+        cop0::set_watchlo((watched_physical as u32 & 0xFFFFFFF8) | ((read as u32) << 1) | (write as u32));
+    }
+
+    let result = if expect_watch {
+        expect_exception(CauseException::Watch, if delay { 2 } else { 1 }, || {
+            access();
+            Ok(())
+        }).map(Some)
+    } else {
+        access();
+        Ok(None)
+    };
+
+    unsafe { cop0::set_watchlo(0); }
+
+    match result? {
+        Some(exception_context) => {
+            soft_assert_eq2(exception_context.k0_exception_vector, 0xFFFFFFFF_80000180, || format!("{}: Exception Vector", name))?;
+            soft_assert_eq2(exception_context.exceptpc, code_address, || format!("{}: ExceptPC", name))?;
+            soft_assert_eq2(exception_context.cause, Cause::new().with_exception(CauseException::Watch).with_branch_delay(delay), || format!("{}: Cause", name))?;
+            soft_assert_eq2(exception_context.status, 0x24000002, || format!("{}: Status", name))?;
+            if store {
+                soft_assert_eq2(memory.read(1), INITIAL_VALUE, || format!("{}: The store is expected to not happen", name))?;
+            }
+        }
+        None => {
+            if store && overlapping {
+                soft_assert_eq2(memory.read(1) == INITIAL_VALUE, false, || format!("{}: The store is expected to happen", name))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub struct WatchpointRead {}
+
+impl Test for WatchpointRead {
+    fn name(&self) -> &str { "Watchpoint (WatchLo.R)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { access_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_watchpoint(value, true, false) }
+}
+
+pub struct WatchpointWrite {}
+
+impl Test for WatchpointWrite {
+    fn name(&self) -> &str { "Watchpoint (WatchLo.W)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { access_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_watchpoint(value, false, true) }
+}
+
+pub struct WatchpointReadWrite {}
+
+impl Test for WatchpointReadWrite {
+    fn name(&self) -> &str { "Watchpoint (WatchLo.R and WatchLo.W)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { access_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_watchpoint(value, true, true) }
+}
+
+pub struct WatchpointDisabled {}
+
+impl Test for WatchpointDisabled {
+    fn name(&self) -> &str { "Watchpoint (neither WatchLo.R nor WatchLo.W)" }
+
+    fn level(&self) -> Level { Level::RarelyUsed }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { access_values() }
+
+    fn run(&self, value: &Box<dyn Any>) -> Result<(), String> { test_watchpoint(value, false, false) }
+}
+
+pub struct WatchpointSuppressedByEXL {}
+
+impl Test for WatchpointSuppressedByEXL {
+    fn name(&self) -> &str { "Watchpoint (suppressed while EXL is set)" }
+
+    fn level(&self) -> Level { Level::Weird }
+
+    fn values(&self) -> Vec<Box<dyn Any>> { Vec::new() }
+
+    fn run(&self, _value: &Box<dyn Any>) -> Result<(), String> {
+        let mut memory = UncachedHeapMemory::<u64>::new_with_init_value(1, INITIAL_VALUE);
+        let watched_physical = memory.start_phyiscal();
+        let watched_address = MemoryMap::physical_to_uncached_mut::<u64>(watched_physical) as usize;
+
+        unsafe {
+            cop0::set_watchhi(0);
+            cop0::set_watchlo((watched_physical as u32 & 0xFFFFFFF8) | 0b11);
+        }
+
+        // Neither the load nor the store are expected to cause an exception (if they do, the test fails)
+        let read_back: u32;
+        unsafe {
+            asm!("
+                .set noat
+                .set noreorder
+                mtc0 {status}, ${Status}
+                nop
+                nop
+                sw $0, 0({address})
+                lw {read_back}, 4({address})
+                mtc0 {default_status}, ${Status}
+                nop
+                nop
+            ", status = in(reg) Status::DEFAULT.with_exl(true).raw_value(),
+                default_status = in(reg) Status::DEFAULT.raw_value(),
+                address = in(reg) watched_address,
+                read_back = out(reg) read_back,
+                Status = const RegisterIndex::Status as u32)
+        }
+
+        unsafe { cop0::set_watchlo(0); }
+
+        soft_assert_eq2(read_back, 0xAAAAAAAA, || String::from("Value loaded while EXL is set"))?;
+        soft_assert_eq2(memory.read(0), 0x00000000_AAAAAAAA, || String::from("Memory after storing while EXL is set"))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use alloc::string::String;
+use core::arch::asm;
+use arbitrary_int::{u2, u27};
+
+use crate::cop0;
+use crate::cop0::{CauseException, make_entry_hi, make_entry_lo, RegisterIndex, Status};
+use crate::exception_handler::{expect_exception_returning_to_kernel, ExceptionContext};
+use crate::uncached_memory::UncachedHeapMemory;
+
+// To run code in user or supervisor mode, it has to live at an address that is accessible in that
+// mode. A 4k page is mapped (uncached and writable) into kuseg/suseg, which is accessible from every mode.
+// Everything after the code within that page can be used by the code as scratch memory.
+// The code is entered through ERET (with EXL=1 and the target KSU) and always ends with an exception
+// (either one raised by the code itself or a SYSCALL at the very end). The exception handler then
+// returns to kernel mode (see expect_exception_returning_to_kernel).
+
+/// Virtual address the code is mapped to
+pub const CODE_VIRTUAL_ADDRESS: u32 = 0x0C0D_C000;
+
+/// TLB index used for the mapping. It is invalidated again after the code ran
+pub const CODE_TLB_INDEX: u32 = 31;
+
+const SYSCALL: u32 = 0x0000_000C;
+
+/// Runs the given instructions with the given Status (which typically sets KSU to user or supervisor).
+/// Before running, $4 to $7 (a0 to a3) are set to the values in args. The instructions are free to
+/// use $1 to $25.
+///
+/// A SYSCALL is appended after the instructions. The context of the first exception (which is
+/// either raised by the instructions themselves or the SYSCALL) is returned. It contains all GPRs
+/// at the time of the exception. Afterwards, Status is [`Status::DEFAULT`].
+pub fn run_in_mode(status: Status, instructions: &[u32], args: [u64; 4], expected: CauseException) -> Result<ExceptionContext, String> {
+    let mut code = UncachedHeapMemory::<u32>::new_with_align(1024, 4096);
+    assert!(instructions.len() < code.count());
+    for (i, instruction) in instructions.iter().enumerate() {
+        code.write(i, *instruction);
+    }
+    code.write(instructions.len(), SYSCALL);
+
+    unsafe {
+        cop0::write_tlb(
+            CODE_TLB_INDEX,
+            0,
+            make_entry_lo(true, true, true, 2, (code.start_phyiscal() >> 12) as u32),
+            make_entry_lo(true, false, false, 0, 0),
+            make_entry_hi(0, u27::new(CODE_VIRTUAL_ADDRESS >> 13), u2::new(0)));
+    }
+
+    let result = expect_exception_returning_to_kernel(expected, || {
+        unsafe {
+            asm!("
+                .set noat
+                .set noreorder
+                // RA is where the exception handler returns to. Stash the original on the stack,
+                // which is the only thing the code isn't allowed to touch
+                addiu $sp, $sp, -16
+                sd $31, 0($sp)
+
+                ld $4, 0($8)
+                ld $5, 8($8)
+                ld $6, 16($8)
+                ld $7, 24($8)
+                mtc0 $9, ${Status}
+                dmtc0 $10, ${ExceptPC}
+                nop
+                nop
+                la $31, 2f
+                eret
+
+            2:
+                li $1, {KERNEL_STATUS}
+                mtc0 $1, ${Status}
+                nop
+                nop
+                ld $31, 0($sp)
+                addiu $sp, $sp, 16
+            ", Status = const RegisterIndex::Status as u32,
+                ExceptPC = const RegisterIndex::ExceptPC as u32,
+                KERNEL_STATUS = const Status::DEFAULT.raw_value(),
+                inout("$8") args.as_ptr() => _,
+                inout("$9") status.with_exl(true).raw_value() => _,
+                inout("$10") CODE_VIRTUAL_ADDRESS => _,
+                out("$2") _, out("$3") _, out("$4") _, out("$5") _, out("$6") _, out("$7") _,
+                out("$11") _, out("$12") _, out("$13") _, out("$14") _, out("$15") _, out("$16") _, out("$17") _,
+                out("$18") _, out("$19") _, out("$20") _, out("$21") _, out("$22") _, out("$23") _, out("$24") _,
+                out("$25") _)
+        }
+
+        Ok(())
+    });
+
+    unsafe {
+        cop0::write_tlb(CODE_TLB_INDEX, 0, 0, 0, make_entry_hi(1, u27::new(0), u2::new(0)));
+        cop0::set_entry_hi(0);
+    }
+
+    result
+}